@@ -0,0 +1,15 @@
+use std::process::Command;
+
+// bakes the git revision into the program for `GetVersion`, empty when built outside a checkout.
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=BRC20_ORACLE_GIT_HASH={}", hash.trim());
+    println!("cargo:rerun-if-changed=../../.git/HEAD");
+    println!("cargo:rerun-if-changed=../../.git/refs/heads");
+}
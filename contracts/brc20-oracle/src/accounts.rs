@@ -0,0 +1,148 @@
+//! Accounts of the instructions clients assemble most, in the order the program reads them. Each
+//! struct is used with `Pubkey` by clients (`to_account_metas`) and with `&AccountInfo` by the
+//! program (`from_accounts`), so order and writability are only written down here.
+
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::instruction::AccountMeta;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+/// `SetCommittee` and `SetNetworkCommittee`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetCommitteeAccounts<T> {
+    pub payer: T,
+    pub committee: T,
+    pub system_program: T,
+    pub ix_sysvar: T,
+}
+
+impl SetCommitteeAccounts<Pubkey> {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new(self.committee, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.ix_sysvar, false),
+        ]
+    }
+}
+
+impl<'a, 'b> SetCommitteeAccounts<&'a AccountInfo<'b>> {
+    pub fn from_accounts(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            payer: next_account_info(iter)?,
+            committee: next_account_info(iter)?,
+            system_program: next_account_info(iter)?,
+            ix_sysvar: next_account_info(iter)?,
+        })
+    }
+}
+
+/// `Insert`. `stats` and `request` are optional, older clients don't pass them. `stats` is the one
+/// global stats account, inserts passing it are serialized on it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InsertAccounts<T> {
+    pub committee: T,
+    pub asset: T,
+    pub ix_sysvar: T,
+    pub payer: T,
+    pub head: T,
+    pub system_program: T,
+    pub stats: Option<T>,
+    // the first requester's record, for the latency stats.
+    pub request: Option<T>,
+}
+
+impl InsertAccounts<Pubkey> {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.committee, false),
+            AccountMeta::new(self.asset, false),
+            AccountMeta::new_readonly(self.ix_sysvar, false),
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new(self.head, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        // the request record can only follow the stats account.
+        if let Some(stats) = self.stats {
+            metas.push(AccountMeta::new(stats, false));
+            metas.extend(self.request.map(|request| AccountMeta::new_readonly(request, false)));
+        }
+        metas
+    }
+}
+
+impl<'a, 'b> InsertAccounts<&'a AccountInfo<'b>> {
+    pub fn from_accounts(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            committee: next_account_info(iter)?,
+            asset: next_account_info(iter)?,
+            ix_sysvar: next_account_info(iter)?,
+            payer: next_account_info(iter)?,
+            head: next_account_info(iter)?,
+            system_program: next_account_info(iter)?,
+            stats: next_account_info(iter).ok(),
+            request: next_account_info(iter).ok(),
+        })
+    }
+}
+
+/// `Reject`, and `Revoke` and `Refresh` which take the same accounts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RejectAccounts<T> {
+    pub committee: T,
+    pub asset: T,
+    pub ix_sysvar: T,
+}
+
+pub type RevokeAccounts<T> = RejectAccounts<T>;
+
+pub type RefreshAccounts<T> = RejectAccounts<T>;
+
+impl RejectAccounts<Pubkey> {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.committee, false),
+            AccountMeta::new(self.asset, false),
+            AccountMeta::new_readonly(self.ix_sysvar, false),
+        ]
+    }
+}
+
+impl<'a, 'b> RejectAccounts<&'a AccountInfo<'b>> {
+    pub fn from_accounts(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            committee: next_account_info(iter)?,
+            asset: next_account_info(iter)?,
+            ix_sysvar: next_account_info(iter)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_account_metas() {
+        let mut accounts = InsertAccounts {
+            committee: Pubkey::new_unique(),
+            asset: Pubkey::new_unique(),
+            ix_sysvar: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            head: Pubkey::new_unique(),
+            system_program: Pubkey::new_unique(),
+            stats: None,
+            request: Some(Pubkey::new_unique()),
+        };
+        // without the stats account the request record would be read as stats.
+        assert_eq!(6, accounts.to_account_metas().len());
+        accounts.stats = Some(Pubkey::new_unique());
+        let metas = accounts.to_account_metas();
+        assert_eq!(8, metas.len());
+        assert!(metas[3].is_signer && !metas[0].is_writable && metas[1].is_writable && !metas[7].is_writable);
+    }
+}
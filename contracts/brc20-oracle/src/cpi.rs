@@ -0,0 +1,34 @@
+//! For consumer programs reading attestations. Build the oracle with `no-entrypoint` to depend on
+//! it. Either read the accounts in place with `read_amount`, or ask the oracle with `get_amount`.
+//! Both give the same `AmountView`, and the caller decides what a stale or missing amount means
+//! for it. Pin the oracle program id you trust, an account passed in can be any program.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program::{get_return_data, invoke};
+use solana_program::program_error::ProgramError;
+use crate::error::Brc20OracleError;
+use crate::types::{AmountView, Brc20Key, Brc20OracleInstruction};
+
+/// `GetAmount` through CPI, the view is read back from the return data of `oracle_program`.
+pub fn get_amount<'a>(
+    oracle_program: &AccountInfo<'a>,
+    asset_info: &AccountInfo<'a>,
+    policy_info: &AccountInfo<'a>,
+    key: &Brc20Key,
+) -> Result<AmountView, ProgramError> {
+    let instruction = Instruction {
+        program_id: *oracle_program.key,
+        accounts: vec![
+            AccountMeta::new_readonly(*asset_info.key, false),
+            AccountMeta::new_readonly(*policy_info.key, false),
+        ],
+        data: Brc20OracleInstruction::GetAmount(key.clone()).try_to_vec()?,
+    };
+    invoke(&instruction, &[asset_info.clone(), policy_info.clone(), oracle_program.clone()])?;
+    match get_return_data() {
+        Some((program_id, data)) if &program_id == oracle_program.key => Ok(AmountView::try_from_slice(&data)?),
+        _ => Err(Brc20OracleError::MissingAmountView.into()),
+    }
+}
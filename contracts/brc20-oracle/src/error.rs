@@ -28,6 +28,106 @@ pub enum Brc20OracleError {
     IncorrectCommitteeId,
     #[error("Committee not set")]
     CommitteeNotSet,
+    #[error("Incorrect snapshot PDA")]
+    IncorrectSnapshotPDA,
+    #[error("Duplicate snapshot for this height and tick")]
+    DuplicateSnapshot,
+    #[error("Snapshot not published")]
+    SnapshotNotPublished,
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof,
+    #[error("Incorrect verifying key PDA")]
+    IncorrectVerifyingKeyPDA,
+    #[error("Verifying key already set")]
+    DuplicateVerifyingKey,
+    #[error("Verifying key not set")]
+    VerifyingKeyNotSet,
+    #[error("Invalid groth16 proof")]
+    InvalidProof,
+    #[error("Brc20 request expired")]
+    RequestExpired,
+    #[error("Brc20 request not expired")]
+    RequestNotExpired,
+    #[error("Incorrect requester account")]
+    IncorrectRequester,
+    #[error("Heartbeat slot is stale or in the future")]
+    StaleHeartbeat,
+    #[error("Incorrect tick policy PDA")]
+    IncorrectTickPolicyPDA,
+    #[error("Tick policy nonce must increase")]
+    StaleTickPolicy,
+    #[error("Incorrect sat range PDA")]
+    IncorrectSatRangePDA,
+    #[error("Sat range is empty")]
+    InvalidSatRange,
+    #[error("Unsupported Brc20 key version")]
+    UnsupportedKeyVersion,
+    #[error("Invalid Brc20 amount")]
+    InvalidAmount,
+    #[error("Incorrect request record PDA")]
+    IncorrectRequestPDA,
+    #[error("Requester must sign")]
+    MissingRequesterSignature,
+    #[error("Incorrect address map PDA")]
+    IncorrectAddressMapPDA,
+    #[error("Address entry is older than the stored one")]
+    StaleAddressEntry,
+    #[error("Incorrect asset head PDA")]
+    IncorrectHeadPDA,
+    #[error("Request was rejected by the committee")]
+    RequestRejected,
+    #[error("Incorrect height range PDA")]
+    IncorrectHeightRangePDA,
+    #[error("Height range is empty")]
+    InvalidHeightRange,
+    #[error("Delta base height must be below the key height")]
+    InvalidDeltaBase,
+    #[error("Delta base asset is not set")]
+    DeltaBaseNotSet,
+    #[error("Incorrect version PDA")]
+    IncorrectVersionPDA,
+    #[error("Incorrect tick registry PDA")]
+    IncorrectTickRegistryPDA,
+    #[error("Incorrect index page PDA")]
+    IncorrectIndexPagePDA,
+    #[error("Only a set asset can be revoked")]
+    RevokeNotSet,
+    #[error("Revocation targets another attestation")]
+    RevisionMismatch,
+    #[error("Attestation revoked")]
+    AttestationRevoked,
+    #[error("Incorrect consumer PDA")]
+    IncorrectConsumerPDA,
+    #[error("Consumer already registered for this target")]
+    ConsumerAlreadyRegistered,
+    #[error("Consumer signature missing")]
+    MissingConsumerSignature,
+    #[error("Key is not in canonical form")]
+    NonCanonicalKey,
+    #[error("Network not allowed by this build")]
+    NetworkNotAllowed,
+    #[error("Incorrect stats PDA")]
+    IncorrectStatsPDA,
+    #[error("Incorrect blinded asset PDA")]
+    IncorrectBlindedAssetPDA,
+    #[error("Proof verification is not available on this chain")]
+    ProofsUnsupported,
+    #[error("No amount view returned by the oracle")]
+    MissingAmountView,
+    #[error("Account is in a legacy layout, migrate it first")]
+    LegacyLayout,
+    #[error("Message header doesn't match the signed value")]
+    InvalidMessageHeader,
+    #[error("Only a set asset can be refreshed")]
+    RefreshNotSet,
+    #[error("Only a pending request can be joined")]
+    RequestNotPending,
+    #[error("Admin must sign")]
+    MissingAdminSignature,
+    #[error("Incorrect program data account")]
+    IncorrectProgramData,
+    #[error("Signer isn't the program upgrade authority")]
+    IncorrectAdmin,
 }
 
 impl From<Brc20OracleError> for ProgramError {
@@ -0,0 +1,59 @@
+use borsh::BorshSerialize;
+#[cfg(not(feature = "no-alt-bn128"))]
+use solana_program::alt_bn128::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing};
+use solana_program::keccak::hash;
+use solana_program::pubkey::Pubkey;
+use crate::message::{bound_message, MessageKind};
+use crate::types::{Brc20Asset, Groth16Proof, VerifyingKey};
+
+/// Public inputs binding a proof to an asset: keccak of the asset as it will be stored, bound to
+/// its address like a committee message, split into two 128-bit big-endian scalars so both fit in
+/// the bn254 scalar field.
+pub fn asset_public_inputs(address: &Pubkey, asset: &Brc20Asset) -> std::io::Result<[[u8; 32]; 2]> {
+    let digest = hash(&bound_message(MessageKind::ProofInsert, address, 0, &asset.try_to_vec()?)).to_bytes();
+    let mut inputs = [[0u8; 32]; 2];
+    inputs[0][16..].copy_from_slice(&digest[..16]);
+    inputs[1][16..].copy_from_slice(&digest[16..]);
+    Ok(inputs)
+}
+
+/// Whether this build can verify proofs, see the `no-alt-bn128` feature.
+pub const SUPPORTED: bool = cfg!(not(feature = "no-alt-bn128"));
+
+/// Checks e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1.
+#[cfg(not(feature = "no-alt-bn128"))]
+pub fn verify(vk: &VerifyingKey, proof: &Groth16Proof, public_inputs: &[[u8; 32]]) -> bool {
+    if vk.ic.len() != public_inputs.len() + 1 {
+        return false;
+    }
+    let mut vk_x = vk.ic[0];
+    for (input, ic) in public_inputs.iter().zip(&vk.ic[1..]) {
+        let product = match alt_bn128_multiplication(&[&ic[..], &input[..]].concat()) {
+            Ok(product) => product,
+            Err(_) => return false,
+        };
+        let sum = match alt_bn128_addition(&[&product[..], &vk_x[..]].concat()) {
+            Ok(sum) => sum,
+            Err(_) => return false,
+        };
+        vk_x.copy_from_slice(&sum);
+    }
+
+    let pairing_input = [
+        &proof.a[..], &proof.b[..],
+        &vk.alpha_g1[..], &vk.beta_g2[..],
+        &vk_x[..], &vk.gamma_g2[..],
+        &proof.c[..], &vk.delta_g2[..],
+    ]
+    .concat();
+    match alt_bn128_pairing(&pairing_input) {
+        Ok(result) => result.len() == 32 && result[..31].iter().all(|b| *b == 0) && result[31] == 1,
+        Err(_) => false,
+    }
+}
+
+// no proof verifies without the syscalls.
+#[cfg(feature = "no-alt-bn128")]
+pub fn verify(_vk: &VerifyingKey, _proof: &Groth16Proof, _public_inputs: &[[u8; 32]]) -> bool {
+    false
+}
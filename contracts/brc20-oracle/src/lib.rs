@@ -1,26 +1,75 @@
 pub mod types;
+pub mod accounts;
+pub mod cpi;
 pub mod error;
+pub mod merkle;
+pub mod groth16;
+pub mod message;
+pub mod pda;
 #[cfg(test)]
 pub mod test;
 
 use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey, system_instruction};
 use solana_program::account_info::next_account_info;
-use solana_program::program::invoke_signed;
+use solana_program::program::{invoke, invoke_signed, set_return_data};
 use solana_program::rent::Rent;
 use solana_program::sysvar::Sysvar;
-use solana_program::keccak::hash;
+use solana_program::clock::Clock;
+use solana_program::keccak::hashv;
+use solana_program::log::sol_log_data;
 use solana_program::ed25519_program::ID as ED25519_ID;
+use solana_program::bpf_loader_upgradeable;
 use solana_program::instruction::Instruction;
+use solana_program::program_error::ProgramError;
 use solana_program::sysvar::instructions::load_instruction_at_checked;
 use types::*;
+use accounts::{InsertAccounts, RefreshAccounts, RejectAccounts, RevokeAccounts, SetCommitteeAccounts};
+use pda::*;
 use error::Brc20OracleError;
+use message::{
+    signing_message_for_address_entry, signing_message_for_asset, signing_message_for_blinded_asset,
+    signing_message_for_committee, signing_message_for_delta, signing_message_for_heartbeat,
+    signing_message_for_height_range, signing_message_for_network_committee, signing_message_for_refresh,
+    signing_message_for_rejection, signing_message_for_revocation, signing_message_for_sat_range, signing_message_for_snapshot,
+    signing_message_for_tick_policy, signing_message_for_verifying_key,
+};
 
 #[cfg(not(feature = "no-entrypoint"))]
 solana_program::entrypoint!(process_instruction);
 
-pub const COMMITTEE_PREFIX: [u8; 9] = *b"Committee";
-pub const ASSET_PREFIX: [u8; 5] = *b"Asset";
+#[cfg(not(feature = "no-entrypoint"))]
+solana_security_txt::security_txt! {
+    name: "BRC20 Oracle",
+    project_url: "https://github.com/boolnetwork/brc20-oracle-solana",
+    contacts: "link:https://github.com/boolnetwork/brc20-oracle-solana/security/advisories/new",
+    policy: "Please report vulnerabilities privately through a GitHub security advisory, not a public issue.",
+    source_code: "https://github.com/boolnetwork/brc20-oracle-solana",
+    source_revision: env!("BRC20_ORACLE_GIT_HASH")
+}
+
+pub use pda::{
+    ADDRESS_MAP_PREFIX, ASSET_PREFIX, BLINDED_ASSET_PREFIX, COMMITTEE_PREFIX, CONSUMER_PREFIX, HEAD_PREFIX,
+    HEIGHT_RANGE_PREFIX, INDEX_PAGE_PREFIX, REQUEST_PREFIX, SAT_RANGE_PREFIX, SNAPSHOT_PREFIX, STATS_PREFIX, TICK_POLICY_PREFIX, TICK_REGISTRY_PREFIX,
+    VERIFIER_PREFIX, VERSION_PREFIX,
+};
+
+// the committee is expected to send a heartbeat at least this often (~10 minutes).
+pub const HEARTBEAT_INTERVAL: u64 = 1500;
+
+// assets per [IndexPage].
+pub const INDEX_PAGE_CAPACITY: u64 = 64;
+
+// upper bounds in slots of the first five `OracleStats::latency_buckets`.
+pub const LATENCY_BUCKET_BOUNDS: [u64; 5] = [10, 50, 150, 750, 3000];
+
+// compute units the transaction carrying one instruction may use at most, the ed25519 instruction
+// included. `test_compute_budgets` fails once an instruction outgrows them, run it with `cargo test-sbf`
+// to meter the sbf build. Batch sizing and compute unit limits downstream rely on these.
+pub const REQUEST_COMPUTE_BUDGET: u32 = 80_000;
+pub const INSERT_COMPUTE_BUDGET: u32 = 60_000;
+pub const REJECT_COMPUTE_BUDGET: u32 = 30_000;
+pub const REVOKE_COMPUTE_BUDGET: u32 = 30_000;
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -30,8 +79,47 @@ pub fn process_instruction(
     let instruction = Brc20OracleInstruction::try_from_slice(instruction_data)?;
     match instruction {
         Brc20OracleInstruction::SetCommittee(committee, signature) => set_committee(program_id, accounts, committee, signature),
-        Brc20OracleInstruction::Request(key) => request(program_id, accounts, key),
-        Brc20OracleInstruction::Insert(key, amount, signature) => insert(program_id, accounts, key, amount, signature),
+        Brc20OracleInstruction::Request(key, ttl) => request(program_id, accounts, key, ttl),
+        Brc20OracleInstruction::Insert(key, amount, confidence, source, signature) => {
+            insert(program_id, accounts, key, amount, confidence, source, signature)
+        }
+        Brc20OracleInstruction::PublishSnapshot(header, snapshot, signature) => {
+            publish_snapshot(program_id, accounts, header, snapshot, signature)
+        }
+        Brc20OracleInstruction::MaterializeEntry(key, amount, proof) => materialize_entry(program_id, accounts, key, amount, proof),
+        Brc20OracleInstruction::SetVerifyingKey(vk, signature) => set_verifying_key(program_id, accounts, vk, signature),
+        Brc20OracleInstruction::InsertWithProof(key, amount, proof) => insert_with_proof(program_id, accounts, key, amount, proof),
+        Brc20OracleInstruction::Expire(key) => expire(program_id, accounts, key),
+        Brc20OracleInstruction::Heartbeat(beat, signature) => heartbeat(program_id, accounts, beat, signature),
+        Brc20OracleInstruction::SetTickPolicy(policy, signature) => set_tick_policy(program_id, accounts, policy, signature),
+        Brc20OracleInstruction::GetAmount(key) => get_amount(program_id, accounts, key),
+        Brc20OracleInstruction::AttestSatRange(header, attestation, signature) => {
+            attest_sat_range(program_id, accounts, header, attestation, signature)
+        }
+        Brc20OracleInstruction::CloseRequest(key) => close_request(program_id, accounts, key),
+        Brc20OracleInstruction::InsertAddressEntry(key, amount, signature) => insert_address_entry(program_id, accounts, key, amount, signature),
+        Brc20OracleInstruction::Reject(key, reason, signature) => reject(program_id, accounts, key, reason, signature),
+        Brc20OracleInstruction::AttestHeightRange(header, attestation, signature) => {
+            attest_height_range(program_id, accounts, header, attestation, signature)
+        }
+        Brc20OracleInstruction::InsertDelta(key, delta, signature) => insert_delta(program_id, accounts, key, delta, signature),
+        Brc20OracleInstruction::GetVersion => get_version(program_id, accounts),
+        Brc20OracleInstruction::Revoke(key, revision, reason, signature) => {
+            revoke(program_id, accounts, key, revision, reason, signature)
+        }
+        Brc20OracleInstruction::RegisterConsumer(target) => register_consumer(program_id, accounts, target),
+        Brc20OracleInstruction::SetNetworkCommittee(network, committee, signature) => {
+            set_network_committee(program_id, accounts, network, committee, signature)
+        }
+        Brc20OracleInstruction::VerifyCommitteeSignature(network, message, signature) => {
+            verify_committee_signature(program_id, accounts, network, message, signature)
+        }
+        Brc20OracleInstruction::RequestBlinded(blinded) => request_blinded(program_id, accounts, blinded),
+        Brc20OracleInstruction::InsertBlinded(blinded, amount, signature) => {
+            insert_blinded(program_id, accounts, blinded, amount, signature)
+        }
+        Brc20OracleInstruction::Migrate(key) => migrate(program_id, accounts, key),
+        Brc20OracleInstruction::Refresh(key, revision, signature) => refresh(program_id, accounts, key, revision, signature),
     }
 }
 
@@ -41,50 +129,85 @@ pub fn set_committee(
     mut committee: Committee,
     signature: Vec<u8>,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let payer_info = next_account_info(account_info_iter)?;
-    let committee_info = next_account_info(account_info_iter)?;
-    let system_program = next_account_info(account_info_iter)?;
-    let ix_sysvar_info = next_account_info(account_info_iter)?;
+    let (committee_address, bump) = find_committee_address(program_id);
+    committee.bump = bump;
+    let sign_msg = signing_message_for_committee(committee.id, &committee.address)?;
+    store_committee(program_id, accounts, committee, &sign_msg, signature, &committee_address, &[&COMMITTEE_PREFIX, &[bump]])
+}
 
-    let (committee_address, bump) = Pubkey::find_program_address(
-        &[&COMMITTEE_PREFIX],
-        program_id,
-    );
-    if committee_info.key != &committee_address {
+/// Same as `set_committee` for the committee attesting keys of `network`. Mainnet resolves to the
+/// original committee account.
+pub fn set_network_committee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    network: BitcoinNetwork,
+    mut committee: Committee,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    check_network(network)?;
+    let (committee_address, bump) = find_network_committee_address(program_id, network);
+    committee.bump = bump;
+    let sign_msg = signing_message_for_network_committee(network, committee.id, &committee.address)?;
+    let (network_seed, bump_seed) = ([network as u8], [bump]);
+    let mainnet_seeds: [&[u8]; 2] = [&COMMITTEE_PREFIX, &bump_seed];
+    let network_seeds: [&[u8]; 3] = [&COMMITTEE_PREFIX, &network_seed, &bump_seed];
+    let seeds: &[&[u8]] = match network {
+        BitcoinNetwork::Mainnet => &mainnet_seeds,
+        _ => &network_seeds,
+    };
+    store_committee(program_id, accounts, committee, &sign_msg, signature, &committee_address, seeds)
+}
+
+// creates the committee account at `committee_address`, or rotates it when `sign_msg` is signed by
+// the current committee.
+fn store_committee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut committee: Committee,
+    sign_msg: &[u8],
+    signature: Vec<u8>,
+    committee_address: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let SetCommitteeAccounts {
+        payer: payer_info,
+        committee: committee_info,
+        system_program,
+        ix_sysvar: ix_sysvar_info,
+    } = SetCommitteeAccounts::from_accounts(accounts)?;
+
+    if committee_info.key != committee_address {
         return Err(Brc20OracleError::IncorrectCommitteePDA.into());
     }
 
-    let parse_committee = Committee::try_from_slice(&committee_info.data.borrow());
-    match parse_committee {
-        Ok(brc20_committee) => {
-            if committee_info.owner != program_id {
-                return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
-            }
-            if committee.id != brc20_committee.id + 1 {
-                return Err(Brc20OracleError::IncorrectCommitteeId.into());
-            }
-            let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
-            verify_ed25519_ix(&ix, brc20_committee.address.as_ref(), &committee.try_to_vec()?, &signature)?;
-            committee.uid = brc20_committee.uid;
+    // only data written by this program is trusted, an account it doesn't own can only be created.
+    if committee_info.owner == program_id {
+        let brc20_committee = migrate_committee(payer_info, committee_info, system_program, committee.bump)?;
+        // ids move by one, so a signature made for an earlier rotation can't be replayed.
+        if brc20_committee.id.checked_add(1) != Some(committee.id) {
+            return Err(Brc20OracleError::IncorrectCommitteeId.into());
         }
-        Err(_) => {
-            if committee.id != 0 {
-                return Err(Brc20OracleError::IncorrectCommitteeId.into());
-            }
-            let size = committee.try_to_vec()?.len();
-            invoke_signed(
-                &system_instruction::create_account(
-                    payer_info.key,
-                    &committee_info.key,
-                    Rent::get()?.minimum_balance(size),
-                    size as u64,
-                    program_id,
-                ),
-                &[payer_info.clone(), committee_info.clone(), system_program.clone()],
-                &[&[&COMMITTEE_PREFIX, &[bump]]],
-            )?;
+        let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+        verify_ed25519_ix(&ix, brc20_committee.address.as_ref(), sign_msg, &signature)?;
+        committee.uid = brc20_committee.uid;
+        committee.last_heartbeat_slot = Clock::get()?.slot;
+    } else {
+        if committee.id != 0 {
+            return Err(Brc20OracleError::IncorrectCommitteeId.into());
         }
+        committee.last_heartbeat_slot = Clock::get()?.slot;
+        let size = committee.try_to_vec()?.len();
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                committee_info.key,
+                Rent::get()?.minimum_balance(size),
+                size as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), committee_info.clone(), system_program.clone()],
+            &[seeds],
+        )?;
     }
     committee.serialize(&mut &mut committee_info.data.borrow_mut()[..])?;
     msg!("set committee: {:?}", committee);
@@ -95,56 +218,112 @@ pub fn request(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     key: Brc20Key,
+    ttl: Option<u64>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let payer_info = next_account_info(account_info_iter)?;
     let committee_info = next_account_info(account_info_iter)?;
     let brc20_asset_info = next_account_info(account_info_iter)?;
+    let request_info = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
+    // optional, older clients don't pass the tick registry and index page.
+    let tick_registry_info = next_account_info(account_info_iter).ok();
+    let index_page_info = next_account_info(account_info_iter).ok();
 
-    let (committee_address, _) = Pubkey::find_program_address(
-        &[&COMMITTEE_PREFIX],
-        program_id,
-    );
+    let (committee_address, committee_bump) = find_committee_address(program_id);
     if committee_info.key != &committee_address {
         return Err(Brc20OracleError::IncorrectCommitteePDA.into());
     }
-    let mut committee = match Committee::try_from_slice(&committee_info.data.borrow()) {
-        Ok(committee) => committee,
-        Err(_) => return Err(Brc20OracleError::CommitteeNotSet.into()),
-    };
+    let mut committee = migrate_committee(payer_info, committee_info, system_program, committee_bump)?;
+    check_key_version(&key)?;
+    check_key_canonical(&key)?;
+    check_key_network(&key)?;
+
+    // every requester gets its own record, the asset itself is shared.
+    let (request_address, request_bump) = find_request_address(program_id, brc20_asset_info.key, payer_info.key);
+    if &request_address != request_info.key {
+        return Err(Brc20OracleError::IncorrectRequestPDA.into());
+    }
+    let parse_record = RequestRecord::try_from_slice(&request_info.data.borrow());
+    if parse_record.is_ok() {
+        return Err(Brc20OracleError::DuplicateRequest.into());
+    }
 
     // initialize corresponding asset account rents.
-    let (asset_address, bump) = Pubkey::find_program_address(
-        &[&ASSET_PREFIX, hash(key.try_to_vec()?.as_slice()).as_ref()],
-        program_id,
-    );
+    let (asset_address, bump) = find_asset_address(program_id, &key);
     if &asset_address != brc20_asset_info.key {
         return Err(Brc20OracleError::IncorrectAssetPDA.into());
     }
     let parse_amount = Brc20Asset::try_from_slice(&brc20_asset_info.data.borrow());
     match parse_amount {
-        Ok(_) => return Err(Brc20OracleError::DuplicateRequest.into()),
+        // only a pending request can be joined, an answered or dead one has nothing to wait for.
+        Ok(asset) => {
+            if asset.set {
+                return Err(Brc20OracleError::RequestNotPending.into());
+            }
+            check_not_expired(&asset)?;
+            check_not_rejected(&asset)?;
+            msg!("join request for key: {:?}", key);
+        }
         Err(_) => {
-            let asset = Brc20Asset { prefix: ASSET_PREFIX, uid: committee.uid, set: false, key: key.clone(), amount: 0 };
+            let expire_slot = match ttl {
+                Some(ttl) => Some(Clock::get()?.slot.saturating_add(ttl)),
+                None => None,
+            };
+            let asset = Brc20Asset {
+                prefix: ASSET_PREFIX,
+                uid: committee.uid,
+                set: false,
+                key: key.clone(),
+                amount: Brc20Amount::ZERO,
+                requester: *payer_info.key,
+                expire_slot,
+                attested_slot: 0,
+                rejected: None,
+                confidence: None,
+                source: AttestationSource::default(),
+                revoked: None,
+                bump,
+            };
             committee.uid += 1;
             let size = asset.try_to_vec()?.len();
             invoke_signed(
                 &system_instruction::create_account(
                     payer_info.key,
                     brc20_asset_info.key,
-                    Rent::get()?.minimum_balance(size),
+                    Rent::get()?.minimum_balance(max_asset_len(&asset)?),
                     size as u64,
                     program_id,
                 ),
                 &[payer_info.clone(), brc20_asset_info.clone(), system_program.clone()],
-                &[&[&ASSET_PREFIX, hash(key.try_to_vec()?.as_slice()).as_ref(), &[bump]]],
+                &[&[&ASSET_PREFIX, &borsh_seed(&key)?, &[bump]]],
             )?;
             asset.serialize(&mut &mut brc20_asset_info.data.borrow_mut()[..])?;
             committee.serialize(&mut &mut committee_info.data.borrow_mut()[..])?;
+            if let Some(tick_registry_info) = tick_registry_info {
+                update_tick_registry(program_id, payer_info, tick_registry_info, system_program, &asset, &asset_address)?;
+            }
+            if let Some(index_page_info) = index_page_info {
+                append_index_entry(program_id, payer_info, index_page_info, system_program, asset.uid, &asset_address)?;
+            }
             msg!("new request for key: {:?}", key);
         }
     }
+
+    let record = RequestRecord {
+        asset: asset_address,
+        requester: *payer_info.key,
+        created_slot: Clock::get()?.slot,
+    };
+    create_pda_account(
+        payer_info,
+        request_info,
+        system_program,
+        record.try_to_vec()?.len(),
+        program_id,
+        &[&REQUEST_PREFIX, asset_address.as_ref(), payer_info.key.as_ref(), &[request_bump]],
+    )?;
+    record.serialize(&mut &mut request_info.data.borrow_mut()[..])?;
     Ok(())
 }
 
@@ -152,113 +331,1680 @@ pub fn insert(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     key: Brc20Key,
-    amount: u128,
+    amount: Brc20Amount,
+    confidence: Option<Brc20Amount>,
+    source: AttestationSource,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let InsertAccounts {
+        committee: committee_info,
+        asset: brc20_asset_info,
+        ix_sysvar: ix_sysvar_info,
+        payer: payer_info,
+        head: head_info,
+        system_program,
+        stats: stats_info,
+        request: request_info,
+    } = InsertAccounts::from_accounts(accounts)?;
+
+    // keys are attested by the committee of their network.
+    let committee = load_network_committee(program_id, committee_info, key.network())?;
+    let mut asset = load_asset(program_id, brc20_asset_info, &key)?;
+    let asset_address = *brc20_asset_info.key;
+
+    if asset.revoked.is_some() {
+        return Err(Brc20OracleError::AttestationRevoked.into());
+    }
+    if asset.set && (asset.amount != amount || asset.confidence != confidence || asset.source != source) {
+        return Err(Brc20OracleError::DuplicateInsert.into());
+    }
+    if !asset.set {
+        check_not_expired(&asset)?;
+        check_not_rejected(&asset)?;
+    }
+    check_amount(amount)?;
+    if let Some(confidence) = confidence {
+        check_amount(confidence)?;
+    }
+    let sign_msg = signing_message_for_asset(&asset_address, &asset, amount, confidence, source)?;
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &sign_msg, &signature)?;
+    // a retried insert of the same amount succeeds without taking the write.
+    // the signature is the original one, so attested_slot is left alone, `Refresh` moves it.
+    if asset.set {
+        msg!("confirmed unchanged: {:?}", asset.key);
+        return Ok(());
+    }
+    asset.amount = amount;
+    asset.confidence = confidence;
+    asset.source = source;
+    asset.set = true;
+    asset.attested_slot = Clock::get()?.slot;
+    // requests made before assets were funded for their largest encoding are topped up here.
+    fund_account(payer_info, brc20_asset_info, system_program, max_asset_len(&asset)?)?;
+    write_asset(brc20_asset_info, &asset)?;
+    update_head(program_id, payer_info, head_info, system_program, &asset.key, &asset_address)?;
+    if let Some(stats_info) = stats_info {
+        let latency = match request_info {
+            Some(request_info) => request_latency(program_id, request_info, &asset_address, &asset)?,
+            None => None,
+        };
+        record_insert(program_id, payer_info, stats_info, system_program, latency)?;
+    }
+    msg!("insert asset: {:?}", asset);
+    Ok(())
+}
+
+pub fn insert_delta(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    delta: AmountDelta,
     signature: Vec<u8>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let committee_info = next_account_info(account_info_iter)?;
     let brc20_asset_info = next_account_info(account_info_iter)?;
+    let base_asset_info = next_account_info(account_info_iter)?;
     let ix_sysvar_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let head_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
 
-    // check committee info's correctness.
-    if committee_info.owner != program_id {
+    let committee = load_network_committee(program_id, committee_info, key.network())?;
+    let mut asset = load_asset(program_id, brc20_asset_info, &key)?;
+    let asset_address = *brc20_asset_info.key;
+    // the base is the same tick and owner at an earlier height.
+    if delta.base_height >= key.height {
+        return Err(Brc20OracleError::InvalidDeltaBase.into());
+    }
+    let base_key = Brc20Key { height: delta.base_height, ..key.clone() };
+    let (base_address, _) = find_asset_address(program_id, &base_key);
+    if &base_address != base_asset_info.key {
+        return Err(Brc20OracleError::IncorrectAssetPDA.into());
+    }
+    if base_asset_info.owner != program_id {
         return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
     }
-    let (committee_info_address, _) = Pubkey::find_program_address(&[&COMMITTEE_PREFIX], program_id);
-    if &committee_info_address != committee_info.key {
-        return Err(Brc20OracleError::IncorrectCommitteePDA.into());
+    let base = match Brc20Asset::try_from_slice(&base_asset_info.data.borrow()) {
+        Ok(base) if base.set => base,
+        _ => return Err(Brc20OracleError::DeltaBaseNotSet.into()),
+    };
+    if base.revoked.is_some() {
+        return Err(Brc20OracleError::AttestationRevoked.into());
+    }
+
+    if asset.set {
+        return Err(Brc20OracleError::DuplicateInsert.into());
+    }
+    check_not_expired(&asset)?;
+    check_not_rejected(&asset)?;
+    let amount = match base.amount.checked_apply(delta.amount_change) {
+        Some(amount) => amount,
+        None => return Err(Brc20OracleError::InvalidAmount.into()),
+    };
+
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &signing_message_for_delta(&asset_address, &asset, &delta)?, &signature)?;
+    asset.amount = amount;
+    asset.set = true;
+    asset.attested_slot = Clock::get()?.slot;
+    asset.serialize(&mut &mut brc20_asset_info.data.borrow_mut()[..])?;
+    update_head(program_id, payer_info, head_info, system_program, &asset.key, &asset_address)?;
+    msg!("insert delta: {:?} from height {} by {}", asset, delta.base_height, delta.amount_change);
+    Ok(())
+}
+
+pub fn publish_snapshot(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    header: MessageHeader,
+    snapshot: Snapshot,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let committee_info = next_account_info(account_info_iter)?;
+    let snapshot_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+
+    let committee = load_committee(program_id, committee_info)?;
+    let (snapshot_address, bump) = find_snapshot_address(program_id, snapshot.height, &snapshot.tick);
+    if snapshot_info.key != &snapshot_address {
+        return Err(Brc20OracleError::IncorrectSnapshotPDA.into());
+    }
+    // one root per height and tick, published roots are immutable.
+    if Snapshot::try_from_slice(&snapshot_info.data.borrow()).is_ok() {
+        return Err(Brc20OracleError::DuplicateSnapshot.into());
     }
-    // check corresponding amount address's correctness.
-    let (asset_address, _) = Pubkey::find_program_address(
-        &[&ASSET_PREFIX, hash(key.try_to_vec()?.as_slice()).as_ref()],
+
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    let sign_msg = signing_message_for_snapshot(&snapshot)?;
+    verify_carried_message(&ix, ix_sysvar_info, committee.address.as_ref(), &header, &sign_msg, &signature)?;
+
+    let size = snapshot.try_to_vec()?.len();
+    create_pda_account(
+        payer_info,
+        snapshot_info,
+        system_program,
+        size,
         program_id,
-    );
-    if &asset_address != brc20_asset_info.key {
-        return Err(Brc20OracleError::IncorrectAssetPDA.into());
+        &[&SNAPSHOT_PREFIX, &snapshot.height.to_le_bytes(), &snapshot.tick, &[bump]],
+    )?;
+    snapshot.serialize(&mut &mut snapshot_info.data.borrow_mut()[..])?;
+    msg!("publish snapshot: {:?}", snapshot);
+    Ok(())
+}
+
+pub fn materialize_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    amount: Brc20Amount,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let committee_info = next_account_info(account_info_iter)?;
+    let snapshot_info = next_account_info(account_info_iter)?;
+    let brc20_asset_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    // keys are attested by the committee of their network.
+    let mut committee = load_network_committee(program_id, committee_info, key.network())?;
+    check_key_version(&key)?;
+    check_key_canonical(&key)?;
+    check_key_network(&key)?;
+    check_amount(amount)?;
+
+    // check the snapshot covers the key's height and tick.
+    let (snapshot_address, _) = find_snapshot_address(program_id, key.height, &key.tick);
+    if snapshot_info.key != &snapshot_address {
+        return Err(Brc20OracleError::IncorrectSnapshotPDA.into());
     }
-    // check corresponding amount's owner.
-    if brc20_asset_info.owner != program_id {
-        return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+    if snapshot_info.owner != program_id {
+        return Err(Brc20OracleError::SnapshotNotPublished.into());
+    }
+    let snapshot = match Snapshot::try_from_slice(&snapshot_info.data.borrow()) {
+        Ok(snapshot) => snapshot,
+        Err(_) => return Err(Brc20OracleError::SnapshotNotPublished.into()),
+    };
+    if !merkle::verify_proof(&snapshot.root, merkle::leaf_hash(&key, amount)?, &proof) {
+        return Err(Brc20OracleError::InvalidMerkleProof.into());
     }
 
+    let (asset_address, bump) = find_asset_address(program_id, &key);
+    if &asset_address != brc20_asset_info.key {
+        return Err(Brc20OracleError::IncorrectAssetPDA.into());
+    }
     let asset = Brc20Asset::try_from_slice(&brc20_asset_info.data.borrow());
     match asset {
+        // already requested, fill it in place.
         Ok(mut asset) => {
+            if brc20_asset_info.owner != program_id {
+                return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+            }
+            if asset.revoked.is_some() {
+                return Err(Brc20OracleError::AttestationRevoked.into());
+            }
             if asset.set {
                 return Err(Brc20OracleError::DuplicateInsert.into());
             }
+            check_not_expired(&asset)?;
+            check_not_rejected(&asset)?;
             asset.amount = amount;
             asset.set = true;
-            let committee = match Committee::try_from_slice(&committee_info.data.borrow()) {
-                Ok(committee) => committee,
-                Err(_) => return Err(Brc20OracleError::CommitteeNotSet.into()),
+            asset.attested_slot = Clock::get()?.slot;
+            asset.serialize(&mut &mut brc20_asset_info.data.borrow_mut()[..])?;
+            msg!("materialize asset: {:?}", asset);
+        }
+        Err(_) => {
+            let asset = Brc20Asset {
+                prefix: ASSET_PREFIX,
+                uid: committee.uid,
+                set: true,
+                key: key.clone(),
+                amount,
+                requester: *payer_info.key,
+                expire_slot: None,
+                attested_slot: Clock::get()?.slot,
+                rejected: None,
+                confidence: None,
+                source: AttestationSource::default(),
+                revoked: None,
+                bump,
             };
-            let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
-            verify_ed25519_ix(&ix, committee.address.as_ref(), &asset.try_to_vec()?, &signature)?;
+            committee.uid += 1;
+            let size = asset.try_to_vec()?.len();
+            create_pda_account(
+                payer_info,
+                brc20_asset_info,
+                system_program,
+                size,
+                program_id,
+                &[&ASSET_PREFIX, &borsh_seed(&key)?, &[bump]],
+            )?;
+            fund_account(payer_info, brc20_asset_info, system_program, max_asset_len(&asset)?)?;
             asset.serialize(&mut &mut brc20_asset_info.data.borrow_mut()[..])?;
-            msg!("insert asset: {:?}", asset);
-        },
-        Err(_) => return Err(Brc20OracleError::RequestNotInitialized.into())
+            committee.serialize(&mut &mut committee_info.data.borrow_mut()[..])?;
+            msg!("materialize asset: {:?}", asset);
+        }
+    }
+    Ok(())
+}
+
+pub fn set_verifying_key(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    vk: VerifyingKey,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let committee_info = next_account_info(account_info_iter)?;
+    let vk_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+
+    // a verifying key would be useless on a chain without the alt_bn128 syscalls.
+    if !groth16::SUPPORTED {
+        return Err(Brc20OracleError::ProofsUnsupported.into());
+    }
+    let committee = load_committee(program_id, committee_info)?;
+    let (vk_address, bump) = find_verifying_key_address(program_id);
+    if vk_info.key != &vk_address {
+        return Err(Brc20OracleError::IncorrectVerifyingKeyPDA.into());
+    }
+    if VerifyingKey::try_from_slice(&vk_info.data.borrow()).is_ok() {
+        return Err(Brc20OracleError::DuplicateVerifyingKey.into());
     }
 
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &signing_message_for_verifying_key(&vk)?, &signature)?;
+
+    let size = vk.try_to_vec()?.len();
+    create_pda_account(payer_info, vk_info, system_program, size, program_id, &[&VERIFIER_PREFIX, &[bump]])?;
+    vk.serialize(&mut &mut vk_info.data.borrow_mut()[..])?;
+    msg!("set verifying key with {} public inputs", vk.ic.len().saturating_sub(1));
     Ok(())
 }
 
-pub fn verify_ed25519_ix(ix: &Instruction, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> ProgramResult {
-    if ix.program_id       != ED25519_ID                   ||  // The program id we expect
-        !ix.accounts.is_empty()                            ||  // With no context accounts
-        ix.data.len()       != (16 + 64 + 32 + msg.len())      // And data of this size
-    {
-        return Err(Brc20OracleError::InvalidSigner.into());
+pub fn insert_with_proof(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    amount: Brc20Amount,
+    proof: Groth16Proof,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vk_info = next_account_info(account_info_iter)?;
+    let brc20_asset_info = next_account_info(account_info_iter)?;
+    // optional, older clients don't pass them. Payer, head and system program keep the head
+    // current like `Insert`, the stats account can only follow them.
+    let payer_info = next_account_info(account_info_iter).ok();
+    let head_info = next_account_info(account_info_iter).ok();
+    let system_program = next_account_info(account_info_iter).ok();
+    let stats_info = next_account_info(account_info_iter).ok();
+
+    if !groth16::SUPPORTED {
+        return Err(Brc20OracleError::ProofsUnsupported.into());
     }
-    check_ed25519_data(&ix.data, pubkey, msg, sig)?; // If that's not the case, check data
+    let (vk_address, _) = find_verifying_key_address(program_id);
+    if vk_info.key != &vk_address {
+        return Err(Brc20OracleError::IncorrectVerifyingKeyPDA.into());
+    }
+    if vk_info.owner != program_id {
+        return Err(Brc20OracleError::VerifyingKeyNotSet.into());
+    }
+    let vk = match VerifyingKey::try_from_slice(&vk_info.data.borrow()) {
+        Ok(vk) => vk,
+        Err(_) => return Err(Brc20OracleError::VerifyingKeyNotSet.into()),
+    };
+
+    let mut asset = load_asset(program_id, brc20_asset_info, &key)?;
+    if asset.set {
+        return Err(Brc20OracleError::DuplicateInsert.into());
+    }
+    check_not_expired(&asset)?;
+    check_not_rejected(&asset)?;
+    check_amount(amount)?;
+    asset.amount = amount;
+    asset.set = true;
+    // the proof attests to exactly the asset bytes the committee would otherwise sign.
+    if !groth16::verify(&vk, &proof, &groth16::asset_public_inputs(brc20_asset_info.key, &asset)?) {
+        return Err(Brc20OracleError::InvalidProof.into());
+    }
+    asset.attested_slot = Clock::get()?.slot;
+    asset.serialize(&mut &mut brc20_asset_info.data.borrow_mut()[..])?;
+    if let (Some(payer_info), Some(head_info), Some(system_program)) = (payer_info, head_info, system_program) {
+        update_head(program_id, payer_info, head_info, system_program, &asset.key, brc20_asset_info.key)?;
+        if let Some(stats_info) = stats_info {
+            record_insert(program_id, payer_info, stats_info, system_program, None)?;
+        }
+    }
+    msg!("insert asset with proof: {:?}", asset);
+
     Ok(())
 }
 
-pub fn check_ed25519_data(data: &[u8], pubkey: &[u8], msg: &[u8], sig: &[u8]) -> ProgramResult {
-    // According to this layout used by the Ed25519Program
-    // https://github.com/solana-labs/solana-web3.js/blob/master/src/ed25519-program.ts#L33
+pub fn expire(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let brc20_asset_info = next_account_info(account_info_iter)?;
+    let requester_info = next_account_info(account_info_iter)?;
+    let tick_registry_info = next_account_info(account_info_iter)?;
+    let index_page_info = next_account_info(account_info_iter)?;
+    // followed by optional (request record, requester) pairs, closed along with the asset.
 
-    // "Deserializing" byte slices
-    let num_signatures = &[data[0]]; // Byte  0
-    let padding = &[data[1]]; // Byte  1
-    let signature_offset = &data[2..=3]; // Bytes 2,3
-    let signature_instruction_index = &data[4..=5]; // Bytes 4,5
-    let public_key_offset = &data[6..=7]; // Bytes 6,7
-    let public_key_instruction_index = &data[8..=9]; // Bytes 8,9
-    let message_data_offset = &data[10..=11]; // Bytes 10,11
-    let message_data_size = &data[12..=13]; // Bytes 12,13
-    let message_instruction_index = &data[14..=15]; // Bytes 14,15
+    let asset = load_asset(program_id, brc20_asset_info, &key)?;
+    let asset_address = *brc20_asset_info.key;
+    if asset.set {
+        return Err(Brc20OracleError::DuplicateInsert.into());
+    }
+    // a rejected request will never be answered, no need to wait for the ttl.
+    match asset.expire_slot {
+        _ if asset.rejected.is_some() => {}
+        Some(expire_slot) if Clock::get()?.slot > expire_slot => {}
+        _ => return Err(Brc20OracleError::RequestNotExpired.into()),
+    }
+    if requester_info.key != &asset.requester {
+        return Err(Brc20OracleError::IncorrectRequester.into());
+    }
 
-    let data_pubkey = &data[16..16 + 32]; // Bytes 16..16+32
-    let data_sig = &data[48..48 + 64]; // Bytes 48..48+64
-    let data_msg = &data[112..]; // Bytes 112..end
+    // close the asset account, refunding its rent to the requester.
+    close_account(brc20_asset_info, requester_info);
+    // an unanswered asset never became a head, the registry and index drop it too.
+    unlink_tick_registry(program_id, tick_registry_info, &asset, &asset_address)?;
+    remove_index_entry(program_id, index_page_info, requester_info, &asset, &asset_address)?;
+    // every requester gets the rent of its record back, not only the first one.
+    while let Ok(request_info) = next_account_info(account_info_iter) {
+        let record_requester_info = next_account_info(account_info_iter)?;
+        let (request_address, _) = find_request_address(program_id, &asset_address, record_requester_info.key);
+        if &request_address != request_info.key {
+            return Err(Brc20OracleError::IncorrectRequestPDA.into());
+        }
+        if request_info.owner != program_id {
+            return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+        }
+        close_account(request_info, record_requester_info);
+    }
+    msg!("expire request for key: {:?}", key);
+    Ok(())
+}
 
-    // Expected values
-    let exp_public_key_offset: u16 = 16; // 2*u8 + 7*u16
-    let exp_signature_offset: u16 = exp_public_key_offset + pubkey.len() as u16;
-    let exp_message_data_offset: u16 = exp_signature_offset + sig.len() as u16;
-    let exp_num_signatures: u8 = 1;
-    let exp_message_data_size: u16 = msg.len().try_into().unwrap();
+pub fn reject(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    reason: RejectReason,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let RejectAccounts { committee: committee_info, asset: brc20_asset_info, ix_sysvar: ix_sysvar_info } =
+        RejectAccounts::from_accounts(accounts)?;
 
-    // Header and Arg Checks
-    // Header
-    if num_signatures != &exp_num_signatures.to_le_bytes()
-        || padding != &[0]
-        || signature_offset != &exp_signature_offset.to_le_bytes()
-        || signature_instruction_index != &u16::MAX.to_le_bytes()
-        || public_key_offset != &exp_public_key_offset.to_le_bytes()
-        || public_key_instruction_index != &u16::MAX.to_le_bytes()
-        || message_data_offset != &exp_message_data_offset.to_le_bytes()
-        || message_data_size != &exp_message_data_size.to_le_bytes()
-        || message_instruction_index != &u16::MAX.to_le_bytes()
-    {
-        return Err(Brc20OracleError::InvalidSigner.into());
+    let committee = load_network_committee(program_id, committee_info, key.network())?;
+    let mut asset = load_asset(program_id, brc20_asset_info, &key)?;
+    let asset_address = *brc20_asset_info.key;
+    if asset.set {
+        return Err(Brc20OracleError::DuplicateInsert.into());
     }
+    check_not_rejected(&asset)?;
 
-    // Arguments
-    if data_pubkey != pubkey || data_msg != msg || data_sig != sig {
-        return Err(Brc20OracleError::InvalidSigner.into());
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &signing_message_for_rejection(&asset_address, &asset, reason)?, &signature)?;
+    asset.rejected = Some(reason);
+    write_asset(brc20_asset_info, &asset)?;
+    msg!("reject request for key: {:?}, reason: {:?}", key, reason);
+    Ok(())
+}
+
+/// Marks the attestation made at `revision` (its `attested_slot`) as revoked. The asset stays readable
+/// with its amount, consumers see `AmountStatus::Revoked`.
+pub fn revoke(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    revision: u64,
+    reason: RevokeReason,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let RevokeAccounts { committee: committee_info, asset: brc20_asset_info, ix_sysvar: ix_sysvar_info } =
+        RevokeAccounts::from_accounts(accounts)?;
+
+    let committee = load_network_committee(program_id, committee_info, key.network())?;
+    let mut asset = load_asset(program_id, brc20_asset_info, &key)?;
+    let asset_address = *brc20_asset_info.key;
+    if !asset.set {
+        return Err(Brc20OracleError::RevokeNotSet.into());
+    }
+    if asset.revoked.is_some() {
+        return Err(Brc20OracleError::AttestationRevoked.into());
     }
+    if asset.attested_slot != revision {
+        return Err(Brc20OracleError::RevisionMismatch.into());
+    }
+
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &signing_message_for_revocation(&asset_address, &asset, reason)?, &signature)?;
+    asset.revoked = Some(reason);
+    write_asset(brc20_asset_info, &asset)?;
+    sol_log_data(&[b"Revoke", asset_address.as_ref(), &revision.to_le_bytes(), &reason.try_to_vec()?]);
+    msg!("revoke attestation for key: {:?}, revision: {}, reason: {:?}", key, revision, reason);
     Ok(())
 }
+
+/// Re-attests an unchanged asset: only `attested_slot` moves, to the current slot. The signature is
+/// bound to the asset at `revision`, so it can't be replayed once the slot moved.
+pub fn refresh(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    revision: u64,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let RefreshAccounts { committee: committee_info, asset: brc20_asset_info, ix_sysvar: ix_sysvar_info } =
+        RefreshAccounts::from_accounts(accounts)?;
+
+    let committee = load_network_committee(program_id, committee_info, key.network())?;
+    let mut asset = load_asset(program_id, brc20_asset_info, &key)?;
+    let asset_address = *brc20_asset_info.key;
+    if !asset.set {
+        return Err(Brc20OracleError::RefreshNotSet.into());
+    }
+    if asset.revoked.is_some() {
+        return Err(Brc20OracleError::AttestationRevoked.into());
+    }
+    if asset.attested_slot != revision {
+        return Err(Brc20OracleError::RevisionMismatch.into());
+    }
+
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &signing_message_for_refresh(&asset_address, &asset)?, &signature)?;
+    asset.attested_slot = Clock::get()?.slot;
+    asset.serialize(&mut &mut brc20_asset_info.data.borrow_mut()[..])?;
+    msg!("confirmed unchanged: {:?}, attested_slot: {}", key, asset.attested_slot);
+    Ok(())
+}
+
+/// Records that `consumer` reads `target`. Purely informational, reads never require it.
+pub fn register_consumer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    target: ConsumerTarget,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let consumer_info = next_account_info(account_info_iter)?;
+    let consumer_record_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    // programs register through invoke_signed with their own PDA.
+    if !consumer_info.is_signer {
+        return Err(Brc20OracleError::MissingConsumerSignature.into());
+    }
+    let (consumer_record_address, bump) = find_consumer_address(program_id, &target, consumer_info.key);
+    if consumer_record_info.key != &consumer_record_address {
+        return Err(Brc20OracleError::IncorrectConsumerPDA.into());
+    }
+    if consumer_record_info.owner == program_id {
+        return Err(Brc20OracleError::ConsumerAlreadyRegistered.into());
+    }
+
+    let record = ConsumerRecord {
+        prefix: CONSUMER_PREFIX,
+        target: target.clone(),
+        consumer: *consumer_info.key,
+        registered_slot: Clock::get()?.slot,
+    };
+    create_pda_account(
+        payer_info,
+        consumer_record_info,
+        system_program,
+        record.try_to_vec()?.len(),
+        program_id,
+        &[&CONSUMER_PREFIX, &borsh_seed(&target)?, consumer_info.key.as_ref(), &[bump]],
+    )?;
+    record.serialize(&mut &mut consumer_record_info.data.borrow_mut()[..])?;
+    msg!("register consumer {} for {:?}", consumer_info.key, target);
+    Ok(())
+}
+
+/// Dry run of the committee signature check: `message` is verified against the committee of
+/// `network` through the same ed25519 path as a real rotation or insert, and nothing is written.
+/// Lets an operator confirm a new signer's output before relying on it. Sets return data `[1]`
+/// when the signature verifies, otherwise fails like the real instruction would.
+pub fn verify_committee_signature(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    network: BitcoinNetwork,
+    message: Vec<u8>,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let committee_info = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+
+    let committee = load_network_committee(program_id, committee_info, network)?;
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &message, &signature)?;
+    msg!("committee signature verified: {}", committee.address);
+    set_return_data(&[1]);
+    Ok(())
+}
+
+/// Request by `blind_key` instead of the key itself, so scanning requests doesn't tell which
+/// addresses a requester follows. The network of a blinded key is unknown on chain, the mainnet
+/// committee answers it.
+pub fn request_blinded(program_id: &Pubkey, accounts: &[AccountInfo], blinded: [u8; 32]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let blinded_asset_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let (blinded_asset_address, bump) = find_blinded_asset_address(program_id, &blinded);
+    if blinded_asset_info.key != &blinded_asset_address {
+        return Err(Brc20OracleError::IncorrectBlindedAssetPDA.into());
+    }
+    if BlindedAsset::try_from_slice(&blinded_asset_info.data.borrow()).is_ok() {
+        return Err(Brc20OracleError::DuplicateRequest.into());
+    }
+    let asset = BlindedAsset {
+        blinded,
+        set: false,
+        amount: Brc20Amount::ZERO,
+        requester: *payer_info.key,
+        attested_slot: 0,
+    };
+    create_pda_account(
+        payer_info,
+        blinded_asset_info,
+        system_program,
+        asset.try_to_vec()?.len(),
+        program_id,
+        &[&BLINDED_ASSET_PREFIX, &blinded, &[bump]],
+    )?;
+    asset.serialize(&mut &mut blinded_asset_info.data.borrow_mut()[..])?;
+    msg!("new blinded request: {:?}", blinded_asset_address);
+    Ok(())
+}
+
+pub fn insert_blinded(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    blinded: [u8; 32],
+    amount: Brc20Amount,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let committee_info = next_account_info(account_info_iter)?;
+    let blinded_asset_info = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+
+    let committee = load_committee(program_id, committee_info)?;
+    let (blinded_asset_address, _) = find_blinded_asset_address(program_id, &blinded);
+    if blinded_asset_info.key != &blinded_asset_address {
+        return Err(Brc20OracleError::IncorrectBlindedAssetPDA.into());
+    }
+    if blinded_asset_info.owner != program_id {
+        return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+    }
+    let mut asset = match BlindedAsset::try_from_slice(&blinded_asset_info.data.borrow()) {
+        Ok(asset) => asset,
+        Err(_) => return Err(Brc20OracleError::RequestNotInitialized.into()),
+    };
+    if asset.set {
+        return Err(Brc20OracleError::DuplicateInsert.into());
+    }
+    check_amount(amount)?;
+
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    let sign_msg = signing_message_for_blinded_asset(&blinded_asset_address, &asset, amount)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &sign_msg, &signature)?;
+
+    asset.set = true;
+    asset.amount = amount;
+    asset.attested_slot = Clock::get()?.slot;
+    asset.serialize(&mut &mut blinded_asset_info.data.borrow_mut()[..])?;
+    msg!("insert blinded asset: {:?}", asset);
+    Ok(())
+}
+
+/// Rewrites the mainnet committee (`key` None) or the asset of `key` from a layout written by an
+/// earlier program version, keeping their data. Anyone can pay for the larger account, accounts
+/// already in the current layout are left alone. An asset is also topped up to the rent of its largest
+/// encoding, which later instructions need to grow it.
+pub fn migrate(program_id: &Pubkey, accounts: &[AccountInfo], key: Option<Brc20Key>) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let account_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if account_info.owner != program_id {
+        return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+    }
+    match key {
+        None => {
+            let (committee_address, bump) = find_committee_address(program_id);
+            if account_info.key != &committee_address {
+                return Err(Brc20OracleError::IncorrectCommitteePDA.into());
+            }
+            migrate_committee(payer_info, account_info, system_program, bump)?;
+        }
+        Some(key) => {
+            let (asset_address, bump) = find_asset_address(program_id, &key);
+            if account_info.key != &asset_address {
+                return Err(Brc20OracleError::IncorrectAssetPDA.into());
+            }
+            let mut asset = match Brc20Asset::decode_any_version(&account_info.data.borrow()) {
+                Ok(asset) => asset,
+                Err(_) => return Err(Brc20OracleError::RequestNotInitialized.into()),
+            };
+            let size = asset.try_to_vec()?.len();
+            if account_info.data_len() < size {
+                asset.bump = bump;
+                grow_account(payer_info, account_info, system_program, size)?;
+                asset.serialize(&mut &mut account_info.data.borrow_mut()[..])?;
+                msg!("migrated asset: {:?}", asset);
+            }
+            fund_account(payer_info, account_info, system_program, max_asset_len(&asset)?)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn close_request(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let requester_info = next_account_info(account_info_iter)?;
+    let request_info = next_account_info(account_info_iter)?;
+
+    if !requester_info.is_signer {
+        return Err(Brc20OracleError::MissingRequesterSignature.into());
+    }
+    let (asset_address, _) = find_asset_address(program_id, &key);
+    let (request_address, _) = find_request_address(program_id, &asset_address, requester_info.key);
+    if &request_address != request_info.key {
+        return Err(Brc20OracleError::IncorrectRequestPDA.into());
+    }
+    if request_info.owner != program_id {
+        return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+    }
+
+    // the shared asset is left alone, only this requester's rent comes back.
+    close_account(request_info, requester_info);
+    msg!("close request for key: {:?}", key);
+    Ok(())
+}
+
+pub fn heartbeat(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    heartbeat: Heartbeat,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let committee_info = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+    // optional, older clients don't pass the stats account.
+    let stats_info = next_account_info(account_info_iter).ok();
+
+    let mut committee = load_committee(program_id, committee_info)?;
+    if heartbeat.id != committee.id {
+        return Err(Brc20OracleError::IncorrectCommitteeId.into());
+    }
+    // slots must strictly increase so an old heartbeat can't be replayed.
+    if heartbeat.slot <= committee.last_heartbeat_slot || heartbeat.slot > Clock::get()?.slot {
+        return Err(Brc20OracleError::StaleHeartbeat.into());
+    }
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &signing_message_for_heartbeat(&heartbeat)?, &signature)?;
+
+    if let Some(stats_info) = stats_info {
+        record_heartbeat(program_id, stats_info, committee.last_heartbeat_slot, heartbeat.slot)?;
+    }
+    committee.last_heartbeat_slot = heartbeat.slot;
+    committee.serialize(&mut &mut committee_info.data.borrow_mut()[..])?;
+    msg!("heartbeat: {:?}", heartbeat);
+    Ok(())
+}
+
+pub fn set_tick_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    policy: TickPolicy,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let committee_info = next_account_info(account_info_iter)?;
+    let policy_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let programdata_info = next_account_info(account_info_iter)?;
+
+    // a policy can weaken freshness, so neither the admin nor the committee can set one alone.
+    check_admin(program_id, admin_info, programdata_info)?;
+    let committee = load_committee(program_id, committee_info)?;
+    let (policy_address, bump) = find_tick_policy_address(program_id, &policy.tick);
+    if policy_info.key != &policy_address {
+        return Err(Brc20OracleError::IncorrectTickPolicyPDA.into());
+    }
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &signing_message_for_tick_policy(&policy)?, &signature)?;
+
+    let parse_policy = TickPolicy::try_from_slice(&policy_info.data.borrow());
+    match parse_policy {
+        Ok(current) => {
+            if policy_info.owner != program_id {
+                return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+            }
+            if policy.nonce <= current.nonce {
+                return Err(Brc20OracleError::StaleTickPolicy.into());
+            }
+        }
+        Err(_) => {
+            let size = policy.try_to_vec()?.len();
+            create_pda_account(
+                payer_info,
+                policy_info,
+                system_program,
+                size,
+                program_id,
+                &[&TICK_POLICY_PREFIX, &policy.tick, &[bump]],
+            )?;
+        }
+    }
+    policy.serialize(&mut &mut policy_info.data.borrow_mut()[..])?;
+    msg!("set tick policy: {:?}", policy);
+    Ok(())
+}
+
+pub fn get_amount(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let brc20_asset_info = next_account_info(account_info_iter)?;
+    let policy_info = next_account_info(account_info_iter)?;
+
+    let view = read_amount(program_id, brc20_asset_info, policy_info, &key, Clock::get()?.slot)?;
+    set_return_data(&view.try_to_vec()?);
+    msg!("get amount: {:?}", view);
+    Ok(())
+}
+
+pub fn attest_sat_range(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    header: MessageHeader,
+    attestation: SatRangeAttestation,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let committee_info = next_account_info(account_info_iter)?;
+    let sat_range_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+
+    if attestation.key.start >= attestation.key.end {
+        return Err(Brc20OracleError::InvalidSatRange.into());
+    }
+    let committee = load_committee(program_id, committee_info)?;
+    let (sat_range_address, bump) = find_sat_range_address(program_id, &attestation.key);
+    if sat_range_info.key != &sat_range_address {
+        return Err(Brc20OracleError::IncorrectSatRangePDA.into());
+    }
+    if SatRangeAttestation::try_from_slice(&sat_range_info.data.borrow()).is_ok() {
+        return Err(Brc20OracleError::DuplicateInsert.into());
+    }
+
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    let sign_msg = signing_message_for_sat_range(&attestation)?;
+    verify_carried_message(&ix, ix_sysvar_info, committee.address.as_ref(), &header, &sign_msg, &signature)?;
+
+    let size = attestation.try_to_vec()?.len();
+    create_pda_account(
+        payer_info,
+        sat_range_info,
+        system_program,
+        size,
+        program_id,
+        &[&SAT_RANGE_PREFIX, &borsh_seed(&attestation.key)?, &[bump]],
+    )?;
+    attestation.serialize(&mut &mut sat_range_info.data.borrow_mut()[..])?;
+    msg!("attest sat range: {:?}", attestation);
+    Ok(())
+}
+
+pub fn attest_height_range(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    header: MessageHeader,
+    attestation: HeightRangeAttestation,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let committee_info = next_account_info(account_info_iter)?;
+    let height_range_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+
+    if attestation.key.from > attestation.key.to {
+        return Err(Brc20OracleError::InvalidHeightRange.into());
+    }
+    check_amount(attestation.amount)?;
+    let committee = load_committee(program_id, committee_info)?;
+    let (height_range_address, bump) = find_height_range_address(program_id, &attestation.key);
+    if height_range_info.key != &height_range_address {
+        return Err(Brc20OracleError::IncorrectHeightRangePDA.into());
+    }
+    if HeightRangeAttestation::try_from_slice(&height_range_info.data.borrow()).is_ok() {
+        return Err(Brc20OracleError::DuplicateInsert.into());
+    }
+
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    let sign_msg = signing_message_for_height_range(&attestation)?;
+    verify_carried_message(&ix, ix_sysvar_info, committee.address.as_ref(), &header, &sign_msg, &signature)?;
+
+    let size = attestation.try_to_vec()?.len();
+    create_pda_account(
+        payer_info,
+        height_range_info,
+        system_program,
+        size,
+        program_id,
+        &[&HEIGHT_RANGE_PREFIX, &borsh_seed(&attestation.key)?, &[bump]],
+    )?;
+    attestation.serialize(&mut &mut height_range_info.data.borrow_mut()[..])?;
+    msg!("attest height range: {:?}", attestation);
+    Ok(())
+}
+
+pub fn insert_address_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    amount: Brc20Amount,
+    signature: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let committee_info = next_account_info(account_info_iter)?;
+    let address_map_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let ix_sysvar_info = next_account_info(account_info_iter)?;
+
+    check_key_version(&key)?;
+    check_key_canonical(&key)?;
+    check_key_network(&key)?;
+    check_amount(amount)?;
+    let committee = load_network_committee(program_id, committee_info, key.network())?;
+    let (address_map_address, bump) = find_address_map_address(program_id, &key.owner);
+    if address_map_info.key != &address_map_address {
+        return Err(Brc20OracleError::IncorrectAddressMapPDA.into());
+    }
+
+    let ix: Instruction = load_instruction_at_checked(0, ix_sysvar_info)?;
+    verify_ed25519_ix(&ix, committee.address.as_ref(), &signing_message_for_address_entry(&address_map_address, &key, amount)?, &signature)?;
+
+    let parse_map = AddressAssetMap::try_from_slice(&address_map_info.data.borrow());
+    let exists = parse_map.is_ok();
+    let mut map = parse_map.unwrap_or(AddressAssetMap { owner: key.owner.clone(), entries: vec![] });
+    let entry = AddressEntry {
+        tick: key.tick,
+        height: key.height,
+        amount,
+        attested_slot: Clock::get()?.slot,
+    };
+    match map.entries.binary_search_by(|e| e.tick.cmp(&key.tick)) {
+        Ok(i) => {
+            if map.entries[i].height > key.height {
+                return Err(Brc20OracleError::StaleAddressEntry.into());
+            }
+            map.entries[i] = entry;
+        }
+        Err(i) => map.entries.insert(i, entry),
+    }
+
+    let size = map.try_to_vec()?.len();
+    if !exists {
+        create_pda_account(
+            payer_info,
+            address_map_info,
+            system_program,
+            size,
+            program_id,
+            &[&ADDRESS_MAP_PREFIX, &owner_seed(&key.owner), &[bump]],
+        )?;
+    } else if size > address_map_info.data_len() {
+        // a new tick grows the account, the payer tops up the rent.
+        grow_account(payer_info, address_map_info, system_program, size)?;
+    }
+    map.serialize(&mut &mut address_map_info.data.borrow_mut()[..])?;
+    msg!("insert address entry: {:?} {:?}", key, amount);
+    Ok(())
+}
+
+/// Returns the running [ProgramVersion] as return data. The version account is written the first
+/// time this is called and again after every upgrade, so it can also be read without a transaction.
+pub fn get_version(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let version_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let (version_address, bump) = find_version_address(program_id);
+    if version_info.key != &version_address {
+        return Err(Brc20OracleError::IncorrectVersionPDA.into());
+    }
+
+    let version = program_version();
+    let parse_version = ProgramVersion::try_from_slice(&version_info.data.borrow());
+    match parse_version {
+        Ok(current) if current == version => {}
+        Ok(_) => version.serialize(&mut &mut version_info.data.borrow_mut()[..])?,
+        Err(_) => {
+            create_pda_account(
+                payer_info,
+                version_info,
+                system_program,
+                version.try_to_vec()?.len(),
+                program_id,
+                &[&VERSION_PREFIX, &[bump]],
+            )?;
+            version.serialize(&mut &mut version_info.data.borrow_mut()[..])?;
+        }
+    }
+    set_return_data(&version.try_to_vec()?);
+    Ok(())
+}
+
+/// Version baked into this build, see build.rs for the git hash.
+pub fn program_version() -> ProgramVersion {
+    let mut git_hash = [0u8; 40];
+    let baked = env!("BRC20_ORACLE_GIT_HASH").as_bytes();
+    let len = baked.len().min(git_hash.len());
+    git_hash[..len].copy_from_slice(&baked[..len]);
+    ProgramVersion {
+        major: env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or_default(),
+        minor: env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or_default(),
+        patch: env!("CARGO_PKG_VERSION_PATCH").parse().unwrap_or_default(),
+        git_hash,
+    }
+}
+
+/// `amount_view` of the asset and tick policy accounts of `key`, for `GetAmount` and for consumer
+/// programs reading the accounts directly instead of through CPI. Only accounts owned by
+/// `program_id` are read, anything else counts as missing.
+pub fn read_amount(
+    program_id: &Pubkey,
+    asset_info: &AccountInfo,
+    policy_info: &AccountInfo,
+    key: &Brc20Key,
+    current_slot: u64,
+) -> Result<AmountView, ProgramError> {
+    let (asset_address, _) = find_asset_address(program_id, key);
+    if &asset_address != asset_info.key {
+        return Err(Brc20OracleError::IncorrectAssetPDA.into());
+    }
+    let (policy_address, _) = find_tick_policy_address(program_id, &key.tick);
+    if policy_info.key != &policy_address {
+        return Err(Brc20OracleError::IncorrectTickPolicyPDA.into());
+    }
+
+    let asset = if asset_info.owner == program_id {
+        Brc20Asset::try_from_slice(&asset_info.data.borrow()).ok()
+    } else {
+        None
+    };
+    // ticks without a policy never go stale.
+    let policy = if policy_info.owner == program_id {
+        TickPolicy::try_from_slice(&policy_info.data.borrow()).ok()
+    } else {
+        None
+    };
+    Ok(amount_view(asset.as_ref(), policy.as_ref(), current_slot))
+}
+
+/// Shared by `GetAmount` and the client read helpers so both agree on staleness.
+pub fn amount_view(asset: Option<&Brc20Asset>, policy: Option<&TickPolicy>, current_slot: u64) -> AmountView {
+    match asset {
+        Some(asset) if asset.set && asset.revoked.is_some() => AmountView {
+            status: AmountStatus::Revoked,
+            amount: asset.amount,
+            attested_slot: asset.attested_slot,
+            confidence: asset.confidence,
+            source: asset.source,
+        },
+        Some(asset) if asset.set => {
+            let stale = match policy {
+                Some(policy) => current_slot.saturating_sub(asset.attested_slot) > policy.max_age,
+                None => false,
+            };
+            AmountView {
+                status: if stale { AmountStatus::Stale } else { AmountStatus::Fresh },
+                amount: asset.amount,
+                attested_slot: asset.attested_slot,
+                confidence: asset.confidence,
+                source: asset.source,
+            }
+        }
+        _ => AmountView {
+            status: AmountStatus::NotSet,
+            amount: Brc20Amount::ZERO,
+            attested_slot: 0,
+            confidence: None,
+            source: AttestationSource::default(),
+        },
+    }
+}
+
+/// Blinded form of `key` for `RequestBlinded`: keccak(borsh(key) || salt). Only the requester and the
+/// committee, who gets the salt off chain, can link the request to the key.
+pub fn blind_key(key: &Brc20Key, salt: &[u8; 32]) -> std::io::Result<[u8; 32]> {
+    Ok(hashv(&[&key.try_to_vec()?, salt]).to_bytes())
+}
+
+/// Index of the `OracleStats::latency_buckets` entry counting an insert `latency` slots after its request.
+pub fn latency_bucket(latency: u64) -> usize {
+    LATENCY_BUCKET_BOUNDS.iter().position(|bound| latency <= *bound).unwrap_or(LATENCY_BUCKET_BOUNDS.len())
+}
+
+/// Whether the committee sent a heartbeat within the last `HEARTBEAT_INTERVAL` slots.
+pub fn is_committee_alive(committee: &Committee, current_slot: u64) -> bool {
+    current_slot.saturating_sub(committee.last_heartbeat_slot) <= HEARTBEAT_INTERVAL
+}
+
+fn check_key_version(key: &Brc20Key) -> ProgramResult {
+    if key.version != BRC20_KEY_VERSION {
+        return Err(Brc20OracleError::UnsupportedKeyVersion.into());
+    }
+    Ok(())
+}
+
+fn check_key_canonical(key: &Brc20Key) -> ProgramResult {
+    if !key.is_canonical() {
+        return Err(Brc20OracleError::NonCanonicalKey.into());
+    }
+    Ok(())
+}
+
+// builds with `mainnet-strict` serve mainnet keys only.
+fn check_key_network(key: &Brc20Key) -> ProgramResult {
+    check_network(key.network())
+}
+
+fn check_network(network: BitcoinNetwork) -> ProgramResult {
+    if cfg!(feature = "mainnet-strict") && network != BitcoinNetwork::Mainnet {
+        return Err(Brc20OracleError::NetworkNotAllowed.into());
+    }
+    Ok(())
+}
+
+fn check_amount(amount: Brc20Amount) -> ProgramResult {
+    if !amount.is_valid() {
+        return Err(Brc20OracleError::InvalidAmount.into());
+    }
+    Ok(())
+}
+
+fn check_not_expired(asset: &Brc20Asset) -> ProgramResult {
+    if let Some(expire_slot) = asset.expire_slot {
+        if Clock::get()?.slot > expire_slot {
+            return Err(Brc20OracleError::RequestExpired.into());
+        }
+    }
+    Ok(())
+}
+
+fn check_not_rejected(asset: &Brc20Asset) -> ProgramResult {
+    if asset.rejected.is_some() {
+        return Err(Brc20OracleError::RequestRejected.into());
+    }
+    Ok(())
+}
+
+// `UpgradeableLoaderState::ProgramData`: variant (u32), slot (u64), then the optional authority.
+const PROGRAMDATA_VARIANT: u32 = 3;
+const PROGRAMDATA_AUTHORITY_OFFSET: usize = 4 + 8;
+
+// the admin is the upgrade authority of the program, an immutable program has none.
+fn check_admin(program_id: &Pubkey, admin_info: &AccountInfo, programdata_info: &AccountInfo) -> ProgramResult {
+    if !admin_info.is_signer {
+        return Err(Brc20OracleError::MissingAdminSignature.into());
+    }
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if programdata_info.key != &programdata_address || programdata_info.owner != &bpf_loader_upgradeable::id() {
+        return Err(Brc20OracleError::IncorrectProgramData.into());
+    }
+    let data = programdata_info.data.borrow();
+    match data.get(..PROGRAMDATA_AUTHORITY_OFFSET + 1 + 32) {
+        Some(meta)
+            if meta[..4] == PROGRAMDATA_VARIANT.to_le_bytes()
+                && meta[PROGRAMDATA_AUTHORITY_OFFSET] == 1
+                && &meta[PROGRAMDATA_AUTHORITY_OFFSET + 1..] == admin_info.key.as_ref() => Ok(()),
+        _ => Err(Brc20OracleError::IncorrectAdmin.into()),
+    }
+}
+
+fn load_committee(program_id: &Pubkey, committee_info: &AccountInfo) -> Result<Committee, ProgramError> {
+    load_network_committee(program_id, committee_info, BitcoinNetwork::Mainnet)
+}
+
+fn load_network_committee(
+    program_id: &Pubkey,
+    committee_info: &AccountInfo,
+    network: BitcoinNetwork,
+) -> Result<Committee, ProgramError> {
+    if committee_info.owner != program_id {
+        return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+    }
+    let data = committee_info.data.borrow();
+    let committee = match Committee::try_from_slice(&data) {
+        Ok(committee) => committee,
+        Err(_) if Committee::decode_any_version(&data).is_ok() => return Err(Brc20OracleError::LegacyLayout.into()),
+        Err(_) => return Err(Brc20OracleError::CommitteeNotSet.into()),
+    };
+    // the account is ours, so its stored bump can be trusted instead of searching for it.
+    match create_network_committee_address(program_id, network, committee.bump) {
+        Ok(committee_address) if committee_info.key == &committee_address => Ok(committee),
+        _ => Err(Brc20OracleError::IncorrectCommitteePDA.into()),
+    }
+}
+
+// existing asset of `key`, its address checked with the bump stored at creation.
+fn load_asset(program_id: &Pubkey, asset_info: &AccountInfo, key: &Brc20Key) -> Result<Brc20Asset, ProgramError> {
+    if asset_info.owner != program_id {
+        return Err(Brc20OracleError::NotOwnedByBrc20Oracle.into());
+    }
+    let data = asset_info.data.borrow();
+    let asset = match Brc20Asset::try_from_slice(&data) {
+        Ok(asset) => asset,
+        Err(_) if Brc20Asset::decode_any_version(&data).is_ok() => return Err(Brc20OracleError::LegacyLayout.into()),
+        Err(_) => return Err(Brc20OracleError::RequestNotInitialized.into()),
+    };
+    match create_asset_address(program_id, key, asset.bump) {
+        Ok(asset_address) if asset_info.key == &asset_address => Ok(asset),
+        _ => Err(Brc20OracleError::IncorrectAssetPDA.into()),
+    }
+}
+
+// keeps the (tick, owner) head pointing at the highest height inserted so far.
+fn update_head<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    head_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    key: &Brc20Key,
+    asset_address: &Pubkey,
+) -> ProgramResult {
+    let owner_hash = owner_seed(&key.owner);
+    let (head_address, bump) = find_head_address(program_id, &key.tick, &key.owner);
+    if head_info.key != &head_address {
+        return Err(Brc20OracleError::IncorrectHeadPDA.into());
+    }
+    let head = AssetHead { height: key.height, asset: *asset_address };
+    let parse_head = AssetHead::try_from_slice(&head_info.data.borrow());
+    match parse_head {
+        // heights can be inserted out of order, never move the head back.
+        Ok(current) if current.height > key.height => return Ok(()),
+        Ok(_) => {}
+        Err(_) => create_pda_account(
+            payer_info,
+            head_info,
+            system_program,
+            head.try_to_vec()?.len(),
+            program_id,
+            &[&HEAD_PREFIX, &key.tick, &owner_hash, &[bump]],
+        )?,
+    }
+    head.serialize(&mut &mut head_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// slots from the first request to the insert, `None` once that request record was closed.
+fn request_latency(
+    program_id: &Pubkey,
+    request_info: &AccountInfo,
+    asset_address: &Pubkey,
+    asset: &Brc20Asset,
+) -> Result<Option<u64>, ProgramError> {
+    let (request_address, _) = find_request_address(program_id, asset_address, &asset.requester);
+    if request_info.key != &request_address {
+        return Err(Brc20OracleError::IncorrectRequestPDA.into());
+    }
+    let latency = RequestRecord::try_from_slice(&request_info.data.borrow())
+        .ok()
+        .map(|record| asset.attested_slot.saturating_sub(record.created_slot));
+    Ok(latency)
+}
+
+fn record_insert<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    stats_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    latency: Option<u64>,
+) -> ProgramResult {
+    let (stats_address, bump) = find_stats_address(program_id);
+    if stats_info.key != &stats_address {
+        return Err(Brc20OracleError::IncorrectStatsPDA.into());
+    }
+    let parse_stats = OracleStats::try_from_slice(&stats_info.data.borrow());
+    let mut stats = match parse_stats {
+        Ok(stats) => stats,
+        Err(_) => {
+            let stats = OracleStats::default();
+            create_pda_account(
+                payer_info,
+                stats_info,
+                system_program,
+                stats.try_to_vec()?.len(),
+                program_id,
+                &[&STATS_PREFIX, &[bump]],
+            )?;
+            stats
+        }
+    };
+    stats.total_inserts = stats.total_inserts.saturating_add(1);
+    if let Some(latency) = latency {
+        let bucket = &mut stats.latency_buckets[latency_bucket(latency)];
+        *bucket = bucket.saturating_add(1);
+    }
+    stats.serialize(&mut &mut stats_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// counts the intervals between two heartbeats that had none, once an insert created the account.
+fn record_heartbeat(program_id: &Pubkey, stats_info: &AccountInfo, previous_slot: u64, slot: u64) -> ProgramResult {
+    if stats_info.key != &find_stats_address(program_id).0 {
+        return Err(Brc20OracleError::IncorrectStatsPDA.into());
+    }
+    let parse_stats = OracleStats::try_from_slice(&stats_info.data.borrow());
+    let mut stats = match parse_stats {
+        Ok(stats) => stats,
+        Err(_) => return Ok(()),
+    };
+    // nothing was expected before the first heartbeat.
+    if previous_slot != 0 {
+        let missed = (slot - previous_slot - 1) / HEARTBEAT_INTERVAL;
+        stats.missed_heartbeats = stats.missed_heartbeats.saturating_add(missed);
+    }
+    stats.serialize(&mut &mut stats_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+fn update_tick_registry<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    tick_registry_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    asset: &Brc20Asset,
+    asset_address: &Pubkey,
+) -> ProgramResult {
+    let tick = asset.key.tick;
+    let (tick_registry_address, bump) = find_tick_registry_address(program_id, &tick);
+    if tick_registry_info.key != &tick_registry_address {
+        return Err(Brc20OracleError::IncorrectTickRegistryPDA.into());
+    }
+    let parse_registry = TickRegistry::try_from_slice(&tick_registry_info.data.borrow());
+    let mut registry = match parse_registry {
+        Ok(registry) => registry,
+        Err(_) => {
+            let registry = TickRegistry { tick, count: 0, last_uid: 0, head: Pubkey::default() };
+            create_pda_account(
+                payer_info,
+                tick_registry_info,
+                system_program,
+                registry.try_to_vec()?.len(),
+                program_id,
+                &[&TICK_REGISTRY_PREFIX, &tick, &[bump]],
+            )?;
+            registry
+        }
+    };
+    registry.count += 1;
+    registry.last_uid = asset.uid;
+    registry.head = *asset_address;
+    registry.serialize(&mut &mut tick_registry_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+fn append_index_entry<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    index_page_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    uid: u64,
+    asset_address: &Pubkey,
+) -> ProgramResult {
+    let page = uid / INDEX_PAGE_CAPACITY;
+    let (index_page_address, bump) = find_index_page_address(program_id, page);
+    if index_page_info.key != &index_page_address {
+        return Err(Brc20OracleError::IncorrectIndexPagePDA.into());
+    }
+    let parse_page = IndexPage::try_from_slice(&index_page_info.data.borrow());
+    let exists = parse_page.is_ok();
+    let mut index_page = parse_page.unwrap_or(IndexPage {
+        page,
+        next: find_index_page_address(program_id, page + 1).0,
+        entries: vec![],
+    });
+    index_page.entries.push(IndexEntry { uid, asset: *asset_address });
+
+    let size = index_page.try_to_vec()?.len();
+    if !exists {
+        create_pda_account(
+            payer_info,
+            index_page_info,
+            system_program,
+            size,
+            program_id,
+            &[&INDEX_PAGE_PREFIX, &page.to_le_bytes(), &[bump]],
+        )?;
+    } else {
+        grow_account(payer_info, index_page_info, system_program, size)?;
+    }
+    index_page.serialize(&mut &mut index_page_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// clears the registry head if it is the expired `asset`, `count` keeps counting it.
+fn unlink_tick_registry(
+    program_id: &Pubkey,
+    tick_registry_info: &AccountInfo,
+    asset: &Brc20Asset,
+    asset_address: &Pubkey,
+) -> ProgramResult {
+    let (tick_registry_address, _) = find_tick_registry_address(program_id, &asset.key.tick);
+    if tick_registry_info.key != &tick_registry_address {
+        return Err(Brc20OracleError::IncorrectTickRegistryPDA.into());
+    }
+    // requested without the registry, it may not exist.
+    if tick_registry_info.owner != program_id {
+        return Ok(());
+    }
+    let mut registry = match TickRegistry::try_from_slice(&tick_registry_info.data.borrow()) {
+        Ok(registry) => registry,
+        Err(_) => return Ok(()),
+    };
+    if &registry.head == asset_address {
+        registry.head = Pubkey::default();
+        registry.serialize(&mut &mut tick_registry_info.data.borrow_mut()[..])?;
+    }
+    Ok(())
+}
+
+// drops the entry of the expired `asset` from its index page, the rent it took goes to `refund_info`.
+fn remove_index_entry(
+    program_id: &Pubkey,
+    index_page_info: &AccountInfo,
+    refund_info: &AccountInfo,
+    asset: &Brc20Asset,
+    asset_address: &Pubkey,
+) -> ProgramResult {
+    let (index_page_address, _) = find_index_page_address(program_id, asset.uid / INDEX_PAGE_CAPACITY);
+    if index_page_info.key != &index_page_address {
+        return Err(Brc20OracleError::IncorrectIndexPagePDA.into());
+    }
+    // requested without the index page, it may not exist.
+    if index_page_info.owner != program_id {
+        return Ok(());
+    }
+    let mut index_page = match IndexPage::try_from_slice(&index_page_info.data.borrow()) {
+        Ok(index_page) => index_page,
+        Err(_) => return Ok(()),
+    };
+    let len = index_page.entries.len();
+    index_page.entries.retain(|entry| &entry.asset != asset_address);
+    if index_page.entries.len() == len {
+        return Ok(());
+    }
+    let size = index_page.try_to_vec()?.len();
+    index_page_info.realloc(size, false)?;
+    index_page.serialize(&mut &mut index_page_info.data.borrow_mut()[..])?;
+    let excess = index_page_info.lamports().saturating_sub(Rent::get()?.minimum_balance(size));
+    **index_page_info.lamports.borrow_mut() -= excess;
+    **refund_info.lamports.borrow_mut() = refund_info.lamports().saturating_add(excess);
+    Ok(())
+}
+
+fn close_account(account_info: &AccountInfo, destination_info: &AccountInfo) {
+    let lamports = account_info.lamports();
+    **destination_info.lamports.borrow_mut() = destination_info.lamports().saturating_add(lamports);
+    **account_info.lamports.borrow_mut() = 0;
+    account_info.data.borrow_mut().fill(0);
+}
+
+// reallocs `account_info` to `size`, the payer topping up its rent.
+fn grow_account<'a>(
+    payer_info: &AccountInfo<'a>,
+    account_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    size: usize,
+) -> ProgramResult {
+    fund_account(payer_info, account_info, system_program, size)?;
+    account_info.realloc(size, false)
+}
+
+// tops up `account_info` to the rent of `size` bytes, the payer paying the difference.
+fn fund_account<'a>(
+    payer_info: &AccountInfo<'a>,
+    account_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    size: usize,
+) -> ProgramResult {
+    let top_up = Rent::get()?.minimum_balance(size).saturating_sub(account_info.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, account_info.key, top_up),
+            &[payer_info.clone(), account_info.clone(), system_program.clone()],
+        )?;
+    }
+    Ok(())
+}
+
+// size of `asset` once every optional field it can still gain is set. Assets are funded for it at
+// creation, so instructions without a payer can grow them in place with `write_asset`.
+fn max_asset_len(asset: &Brc20Asset) -> Result<usize, ProgramError> {
+    let mut asset = asset.clone();
+    asset.rejected = Some(RejectReason::Other(0));
+    asset.revoked = Some(RevokeReason::Other(0));
+    asset.confidence = Some(Brc20Amount::ZERO);
+    Ok(asset.try_to_vec()?.len())
+}
+
+// writes `asset` resized to its exact encoding, readers decode it with `try_from_slice`.
+fn write_asset(asset_info: &AccountInfo, asset: &Brc20Asset) -> ProgramResult {
+    let data = asset.try_to_vec()?;
+    if asset_info.data_len() != data.len() {
+        asset_info.realloc(data.len(), false)?;
+    }
+    asset_info.data.borrow_mut().copy_from_slice(&data);
+    Ok(())
+}
+
+// committee in any layout the program ever wrote, a legacy one is first rewritten in the current
+// layout with `bump`, the bump of its address.
+fn migrate_committee<'a>(
+    payer_info: &AccountInfo<'a>,
+    committee_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    bump: u8,
+) -> Result<Committee, ProgramError> {
+    let mut committee = match Committee::decode_any_version(&committee_info.data.borrow()) {
+        Ok(committee) => committee,
+        Err(_) => return Err(Brc20OracleError::CommitteeNotSet.into()),
+    };
+    let size = committee.try_to_vec()?.len();
+    if committee_info.data_len() < size {
+        committee.bump = bump;
+        grow_account(payer_info, committee_info, system_program, size)?;
+        committee.serialize(&mut &mut committee_info.data.borrow_mut()[..])?;
+        msg!("migrated committee: {:?}", committee);
+    }
+    Ok(committee)
+}
+
+// `create_account` refuses an address that already holds lamports, so anyone could block a PDA by
+// sending it some. Such an account is topped up, allocated and assigned instead.
+fn create_pda_account<'a>(
+    payer_info: &AccountInfo<'a>,
+    new_account_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    size: usize,
+    program_id: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    if new_account_info.lamports() > 0 {
+        fund_account(payer_info, new_account_info, system_program, size)?;
+        let accounts = [new_account_info.clone(), system_program.clone()];
+        invoke_signed(&system_instruction::allocate(new_account_info.key, size as u64), &accounts, &[signer_seeds])?;
+        return invoke_signed(&system_instruction::assign(new_account_info.key, program_id), &accounts, &[signer_seeds]);
+    }
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            new_account_info.key,
+            Rent::get()?.minimum_balance(size),
+            size as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), new_account_info.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+pub fn verify_ed25519_ix(ix: &Instruction, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> ProgramResult {
+    if ix.program_id       != ED25519_ID                   ||  // The program id we expect
+        !ix.accounts.is_empty()                            ||  // With no context accounts
+        ix.data.len()       != (16 + 64 + 32 + msg.len())      // And data of this size
+    {
+        return Err(Brc20OracleError::InvalidSigner.into());
+    }
+    check_ed25519_data(&ix.data, pubkey, msg, sig)?; // If that's not the case, check data
+    Ok(())
+}
+
+/// `verify_ed25519_ix` that also accepts a verify instruction reading the message from another
+/// instruction of the transaction, normally the oracle instruction carrying it, so a large message
+/// is only sent once. The referenced bytes must equal `msg`.
+pub fn verify_ed25519_ix_or_referenced(
+    ix: &Instruction,
+    ix_sysvar_info: &AccountInfo,
+    pubkey: &[u8],
+    msg: &[u8],
+    sig: &[u8],
+) -> ProgramResult {
+    if ix.data.len() != 16 + 64 + 32 || ix.data[14..16] == u16::MAX.to_le_bytes() {
+        return verify_ed25519_ix(ix, pubkey, msg, sig);
+    }
+    if ix.program_id != ED25519_ID || !ix.accounts.is_empty() {
+        return Err(Brc20OracleError::InvalidSigner.into());
+    }
+    let (index, offset) = check_referenced_ed25519_data(&ix.data, pubkey, msg.len(), sig)?;
+    let source = load_instruction_at_checked(index as usize, ix_sysvar_info)?;
+    if source.data.get(offset as usize..offset as usize + msg.len()) != Some(msg) {
+        return Err(Brc20OracleError::InvalidSigner.into());
+    }
+    Ok(())
+}
+
+// `verify_ed25519_ix_or_referenced` for a message the instruction carries as `header` and value.
+// The header is only signed when the verify instruction reads it from here, so it's checked either way.
+fn verify_carried_message(
+    ix: &Instruction,
+    ix_sysvar_info: &AccountInfo,
+    pubkey: &[u8],
+    header: &MessageHeader,
+    msg: &[u8],
+    sig: &[u8],
+) -> ProgramResult {
+    if !msg.starts_with(&header.try_to_vec()?) {
+        return Err(Brc20OracleError::InvalidMessageHeader.into());
+    }
+    verify_ed25519_ix_or_referenced(ix, ix_sysvar_info, pubkey, msg, sig)
+}
+
+pub fn check_ed25519_data(data: &[u8], pubkey: &[u8], msg: &[u8], sig: &[u8]) -> ProgramResult {
+    // According to this layout used by the Ed25519Program
+    // https://github.com/solana-labs/solana-web3.js/blob/master/src/ed25519-program.ts#L33
+
+    // "Deserializing" byte slices
+    let num_signatures = &[data[0]]; // Byte  0
+    let padding = &[data[1]]; // Byte  1
+    let signature_offset = &data[2..=3]; // Bytes 2,3
+    let signature_instruction_index = &data[4..=5]; // Bytes 4,5
+    let public_key_offset = &data[6..=7]; // Bytes 6,7
+    let public_key_instruction_index = &data[8..=9]; // Bytes 8,9
+    let message_data_offset = &data[10..=11]; // Bytes 10,11
+    let message_data_size = &data[12..=13]; // Bytes 12,13
+    let message_instruction_index = &data[14..=15]; // Bytes 14,15
+
+    let data_pubkey = &data[16..16 + 32]; // Bytes 16..16+32
+    let data_sig = &data[48..48 + 64]; // Bytes 48..48+64
+    let data_msg = &data[112..]; // Bytes 112..end
+
+    // Expected values
+    let exp_public_key_offset: u16 = 16; // 2*u8 + 7*u16
+    let exp_signature_offset: u16 = exp_public_key_offset + pubkey.len() as u16;
+    let exp_message_data_offset: u16 = exp_signature_offset + sig.len() as u16;
+    let exp_num_signatures: u8 = 1;
+    let exp_message_data_size: u16 = msg.len().try_into().unwrap();
+
+    // Header and Arg Checks
+    // Header
+    if num_signatures != &exp_num_signatures.to_le_bytes()
+        || padding != &[0]
+        || signature_offset != exp_signature_offset.to_le_bytes()
+        || signature_instruction_index != u16::MAX.to_le_bytes()
+        || public_key_offset != exp_public_key_offset.to_le_bytes()
+        || public_key_instruction_index != u16::MAX.to_le_bytes()
+        || message_data_offset != exp_message_data_offset.to_le_bytes()
+        || message_data_size != exp_message_data_size.to_le_bytes()
+        || message_instruction_index != u16::MAX.to_le_bytes()
+    {
+        return Err(Brc20OracleError::InvalidSigner.into());
+    }
+
+    // Arguments
+    if data_pubkey != pubkey || data_msg != msg || data_sig != sig {
+        return Err(Brc20OracleError::InvalidSigner.into());
+    }
+    Ok(())
+}
+
+/// Checks the header of a verify instruction whose message lives in instruction
+/// `message_instruction_index`, and returns that index with the message offset. Pubkey and
+/// signature stay in the verify instruction at the same offsets as `check_ed25519_data`.
+pub fn check_referenced_ed25519_data(data: &[u8], pubkey: &[u8], msg_len: usize, sig: &[u8]) -> Result<(u16, u16), ProgramError> {
+    let field = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let exp_message_data_size: u16 = msg_len.try_into().map_err(|_| Brc20OracleError::InvalidSigner)?;
+    if data.len() != 16 + 32 + 64
+        || data[0] != 1
+        || data[1] != 0
+        || field(2) != 48
+        || field(4) != u16::MAX
+        || field(6) != 16
+        || field(8) != u16::MAX
+        || field(12) != exp_message_data_size
+        || field(14) == u16::MAX
+    {
+        return Err(Brc20OracleError::InvalidSigner.into());
+    }
+    if &data[16..48] != pubkey || &data[48..112] != sig {
+        return Err(Brc20OracleError::InvalidSigner.into());
+    }
+    Ok((field(14), field(10)))
+}
@@ -0,0 +1,25 @@
+use borsh::BorshSerialize;
+use solana_program::keccak::hashv;
+use crate::types::{Brc20Amount, Brc20Key};
+
+// Domain separation so a leaf can never be passed off as an inner node.
+const LEAF_PREFIX: [u8; 1] = [0];
+const NODE_PREFIX: [u8; 1] = [1];
+
+/// Leaf of a snapshot merkle tree for one (key, amount) entry.
+pub fn leaf_hash(key: &Brc20Key, amount: Brc20Amount) -> std::io::Result<[u8; 32]> {
+    Ok(hashv(&[&LEAF_PREFIX, &key.try_to_vec()?, &amount.try_to_vec()?]).to_bytes())
+}
+
+/// Parent of two sibling nodes. Siblings are sorted first so proofs carry no direction bits.
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[&NODE_PREFIX, a, b]).to_bytes()
+    } else {
+        hashv(&[&NODE_PREFIX, b, a]).to_bytes()
+    }
+}
+
+pub fn verify_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof.iter().fold(leaf, |node, sibling| node_hash(&node, sibling)) == *root
+}
@@ -0,0 +1,246 @@
+use borsh::BorshSerialize;
+use solana_program::pubkey::Pubkey;
+use crate::types::{
+    AmountDelta, AttestationSource, BitcoinNetwork, BlindedAsset, Brc20Amount, Brc20Asset, Brc20Key, Heartbeat,
+    HeightRangeAttestation, MessageHeader, RejectReason, RevokeReason, SatRangeAttestation, Snapshot, TickPolicy,
+    VerifyingKey,
+};
+
+// Exact bytes the program verifies, so external signers (MPC/TSS, HSM) don't have to rebuild the borsh layout.
+
+/// Tag in front of every committee message, so a signature over one kind can't be replayed as another.
+/// Never reuse a value, new kinds take the next one.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageKind {
+    Insert = 1,
+    Reject = 2,
+    Revoke = 3,
+    Delta = 4,
+    AddressEntry = 5,
+    BlindedInsert = 6,
+    Committee = 7,
+    NetworkCommittee = 8,
+    Heartbeat = 9,
+    VerifyingKey = 10,
+    TickPolicy = 11,
+    Snapshot = 12,
+    SatRange = 13,
+    HeightRange = 14,
+    // never signed, hashed into the public inputs of `InsertWithProof`.
+    ProofInsert = 15,
+    Refresh = 16,
+}
+
+/// Offset of the bound account in a message built by `bound_message`.
+pub const BOUND_ACCOUNT_OFFSET: usize = 2;
+
+/// Binds `body` to one account and revision:
+/// `kind (u8) | 32 (u8) | account (32) | revision (u64 LE) | body length (u32 LE) | body`.
+/// The account sits at a fixed offset, so a signature can't be moved to another asset even if
+/// the borsh bodies happen to match.
+pub fn bound_message(kind: MessageKind, account: &Pubkey, revision: u64, body: &[u8]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(BOUND_ACCOUNT_OFFSET + 32 + 8 + 4 + body.len());
+    msg.push(kind as u8);
+    msg.push(32);
+    msg.extend_from_slice(account.as_ref());
+    msg.extend_from_slice(&revision.to_le_bytes());
+    msg.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    msg.extend_from_slice(body);
+    msg
+}
+
+/// Message not bound to an account: `kind (u8) | 0 (u8) | body length (u32 LE) | body`. The zero
+/// sits where `bound_message` puts the account length, so the two layouts can't be confused.
+pub fn typed_message(kind: MessageKind, body: &[u8]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(2 + 4 + body.len());
+    msg.push(kind as u8);
+    msg.push(0);
+    msg.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    msg.extend_from_slice(body);
+    msg
+}
+
+/// Header `typed_message` puts in front of a body of `body_len` bytes. Snapshots and range
+/// attestations carry it in their instruction, borsh encoding the two gives the signed message.
+pub fn typed_header(kind: MessageKind, body_len: usize) -> MessageHeader {
+    MessageHeader { kind: kind as u8, account_len: 0, body_len: body_len as u32 }
+}
+
+/// Message for `Insert`: the request as it will be stored once filled with `amount`, `confidence` and `source`,
+/// bound to the asset PDA at revision 0.
+pub fn signing_message_for_asset(
+    asset: &Pubkey,
+    request: &Brc20Asset,
+    amount: Brc20Amount,
+    confidence: Option<Brc20Amount>,
+    source: AttestationSource,
+) -> std::io::Result<Vec<u8>> {
+    let body = Brc20Asset {
+        set: true,
+        amount,
+        attested_slot: 0,
+        confidence,
+        source,
+        ..request.clone()
+    }
+    .try_to_vec()?;
+    Ok(bound_message(MessageKind::Insert, asset, 0, &body))
+}
+
+/// Message for `InsertBlinded`: the blinded request as it will be stored once filled with `amount`,
+/// bound to the blinded asset PDA at revision 0.
+pub fn signing_message_for_blinded_asset(
+    address: &Pubkey,
+    request: &BlindedAsset,
+    amount: Brc20Amount,
+) -> std::io::Result<Vec<u8>> {
+    let body = BlindedAsset {
+        set: true,
+        amount,
+        attested_slot: 0,
+        ..request.clone()
+    }
+    .try_to_vec()?;
+    Ok(bound_message(MessageKind::BlindedInsert, address, 0, &body))
+}
+
+/// Message for `Reject`: the pending request as it will be stored once rejected, bound to the asset PDA.
+pub fn signing_message_for_rejection(
+    asset: &Pubkey,
+    request: &Brc20Asset,
+    reason: RejectReason,
+) -> std::io::Result<Vec<u8>> {
+    let body = Brc20Asset {
+        rejected: Some(reason),
+        ..request.clone()
+    }
+    .try_to_vec()?;
+    Ok(bound_message(MessageKind::Reject, asset, 0, &body))
+}
+
+/// Message for `Revoke`: the filled asset as it will be stored once revoked, bound to the asset PDA
+/// at the `attested_slot` of the revoked attestation.
+pub fn signing_message_for_revocation(
+    address: &Pubkey,
+    asset: &Brc20Asset,
+    reason: RevokeReason,
+) -> std::io::Result<Vec<u8>> {
+    let body = Brc20Asset {
+        revoked: Some(reason),
+        ..asset.clone()
+    }
+    .try_to_vec()?;
+    Ok(bound_message(MessageKind::Revoke, address, asset.attested_slot, &body))
+}
+
+/// Message for `Refresh`: the asset as stored, bound to the asset PDA at its `attested_slot`, so the
+/// signature is spent once the refresh moved the slot.
+pub fn signing_message_for_refresh(address: &Pubkey, asset: &Brc20Asset) -> std::io::Result<Vec<u8>> {
+    Ok(bound_message(MessageKind::Refresh, address, asset.attested_slot, &asset.try_to_vec()?))
+}
+
+/// Message for `SetCommittee`, signed by the current committee: the new id and address.
+pub fn signing_message_for_committee(id: u8, address: &Pubkey) -> std::io::Result<Vec<u8>> {
+    Ok(typed_message(MessageKind::Committee, &(id, *address).try_to_vec()?))
+}
+
+/// Message for `SetNetworkCommittee`, signed by the current committee of `network`. The network is
+/// part of the message so a rotation of one network can't be replayed on another.
+pub fn signing_message_for_network_committee(
+    network: BitcoinNetwork,
+    id: u8,
+    address: &Pubkey,
+) -> std::io::Result<Vec<u8>> {
+    Ok(typed_message(MessageKind::NetworkCommittee, &(network, id, *address).try_to_vec()?))
+}
+
+/// Message for `Heartbeat`.
+pub fn signing_message_for_heartbeat(heartbeat: &Heartbeat) -> std::io::Result<Vec<u8>> {
+    Ok(typed_message(MessageKind::Heartbeat, &heartbeat.try_to_vec()?))
+}
+
+/// Message for `SetVerifyingKey`.
+pub fn signing_message_for_verifying_key(vk: &VerifyingKey) -> std::io::Result<Vec<u8>> {
+    Ok(typed_message(MessageKind::VerifyingKey, &vk.try_to_vec()?))
+}
+
+/// Message for `SetTickPolicy`.
+pub fn signing_message_for_tick_policy(policy: &TickPolicy) -> std::io::Result<Vec<u8>> {
+    Ok(typed_message(MessageKind::TickPolicy, &policy.try_to_vec()?))
+}
+
+/// Message for `PublishSnapshot`.
+pub fn signing_message_for_snapshot(snapshot: &Snapshot) -> std::io::Result<Vec<u8>> {
+    Ok(typed_message(MessageKind::Snapshot, &snapshot.try_to_vec()?))
+}
+
+/// Message for `AttestSatRange`.
+pub fn signing_message_for_sat_range(attestation: &SatRangeAttestation) -> std::io::Result<Vec<u8>> {
+    Ok(typed_message(MessageKind::SatRange, &attestation.try_to_vec()?))
+}
+
+/// Message for `AttestHeightRange`.
+pub fn signing_message_for_height_range(attestation: &HeightRangeAttestation) -> std::io::Result<Vec<u8>> {
+    Ok(typed_message(MessageKind::HeightRange, &attestation.try_to_vec()?))
+}
+
+/// Message for `InsertAddressEntry`: the key and amount, bound to the owner's address map PDA.
+pub fn signing_message_for_address_entry(
+    address_map: &Pubkey,
+    key: &Brc20Key,
+    amount: Brc20Amount,
+) -> std::io::Result<Vec<u8>> {
+    let body = (key.clone(), amount).try_to_vec()?;
+    Ok(bound_message(MessageKind::AddressEntry, address_map, 0, &body))
+}
+
+/// Message for `InsertDelta`: the pending request and the delta applied to its base height, bound to the asset PDA.
+pub fn signing_message_for_delta(
+    asset: &Pubkey,
+    request: &Brc20Asset,
+    delta: &AmountDelta,
+) -> std::io::Result<Vec<u8>> {
+    let body = (request.clone(), *delta).try_to_vec()?;
+    Ok(bound_message(MessageKind::Delta, asset, 0, &body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bound_message_layout() {
+        let account = Pubkey::new_unique();
+        let msg = bound_message(MessageKind::Revoke, &account, 42, b"body");
+        assert_eq!(msg[0], MessageKind::Revoke as u8);
+        assert_eq!(msg[1], 32);
+        assert_eq!(&msg[BOUND_ACCOUNT_OFFSET..BOUND_ACCOUNT_OFFSET + 32], account.as_ref());
+        assert_eq!(&msg[34..42], &42u64.to_le_bytes());
+        assert_eq!(&msg[42..46], &4u32.to_le_bytes());
+        assert_eq!(&msg[46..], b"body");
+
+        let other = bound_message(MessageKind::Revoke, &Pubkey::new_unique(), 42, b"body");
+        assert_ne!(msg, other);
+    }
+
+    #[test]
+    fn test_typed_message_layout() {
+        let msg = typed_message(MessageKind::Heartbeat, b"body");
+        assert_eq!(&msg[..6], &[MessageKind::Heartbeat as u8, 0, 4, 0, 0, 0]);
+        assert_eq!(&msg[6..], b"body");
+
+        // the same id and address signed for either rotation give different messages.
+        let address = Pubkey::new_unique();
+        let committee = signing_message_for_committee(1, &address).unwrap();
+        let network = signing_message_for_network_committee(BitcoinNetwork::Mainnet, 1, &address).unwrap();
+        assert_eq!(MessageKind::Committee as u8, committee[0]);
+        assert_eq!(MessageKind::NetworkCommittee as u8, network[0]);
+
+        // the header and value an instruction carries encode to the signed message.
+        let snapshot = Snapshot { height: 1, tick: *b"ordi", root: [7; 32] };
+        let body = snapshot.try_to_vec().unwrap();
+        let carried = (typed_header(MessageKind::Snapshot, body.len()), snapshot.clone()).try_to_vec().unwrap();
+        assert_eq!(signing_message_for_snapshot(&snapshot).unwrap(), carried);
+    }
+}
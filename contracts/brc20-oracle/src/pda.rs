@@ -0,0 +1,196 @@
+//! Seeds of every account owned by the program. The program, utils and tests all derive
+//! addresses through here so the schemes can't drift apart.
+
+use borsh::BorshSerialize;
+use solana_program::keccak::hash;
+use solana_program::pubkey::{Pubkey, PubkeyError};
+use crate::types::{BitcoinNetwork, Brc20Key, ConsumerTarget, HeightRangeKey, LegacyBrc20Key, SatRangeKey};
+
+pub const COMMITTEE_PREFIX: [u8; 9] = *b"Committee";
+pub const ASSET_PREFIX: [u8; 5] = *b"Asset";
+pub const SNAPSHOT_PREFIX: [u8; 8] = *b"Snapshot";
+pub const VERIFIER_PREFIX: [u8; 8] = *b"Verifier";
+pub const TICK_POLICY_PREFIX: [u8; 10] = *b"TickPolicy";
+pub const SAT_RANGE_PREFIX: [u8; 8] = *b"SatRange";
+pub const REQUEST_PREFIX: [u8; 7] = *b"Request";
+pub const ADDRESS_MAP_PREFIX: [u8; 10] = *b"AddressMap";
+pub const HEAD_PREFIX: [u8; 4] = *b"Head";
+pub const HEIGHT_RANGE_PREFIX: [u8; 11] = *b"HeightRange";
+pub const VERSION_PREFIX: [u8; 7] = *b"Version";
+pub const TICK_REGISTRY_PREFIX: [u8; 12] = *b"TickRegistry";
+pub const INDEX_PAGE_PREFIX: [u8; 9] = *b"IndexPage";
+pub const CONSUMER_PREFIX: [u8; 8] = *b"Consumer";
+pub const STATS_PREFIX: [u8; 5] = *b"Stats";
+pub const BLINDED_ASSET_PREFIX: [u8; 12] = *b"BlindedAsset";
+
+const PREFIXES: [&[u8]; 16] = [
+    &COMMITTEE_PREFIX,
+    &ASSET_PREFIX,
+    &SNAPSHOT_PREFIX,
+    &VERIFIER_PREFIX,
+    &TICK_POLICY_PREFIX,
+    &SAT_RANGE_PREFIX,
+    &REQUEST_PREFIX,
+    &ADDRESS_MAP_PREFIX,
+    &HEAD_PREFIX,
+    &HEIGHT_RANGE_PREFIX,
+    &VERSION_PREFIX,
+    &TICK_REGISTRY_PREFIX,
+    &INDEX_PAGE_PREFIX,
+    &CONSUMER_PREFIX,
+    &STATS_PREFIX,
+    &BLINDED_ASSET_PREFIX,
+];
+
+// no prefix may start another one, otherwise two seed schemes could derive the same address.
+const _: () = {
+    let mut i = 0;
+    while i < PREFIXES.len() {
+        let mut j = 0;
+        while j < PREFIXES.len() {
+            assert!(i == j || !starts_with(PREFIXES[j], PREFIXES[i]), "overlapping PDA prefixes");
+            j += 1;
+        }
+        i += 1;
+    }
+};
+
+const fn starts_with(data: &[u8], prefix: &[u8]) -> bool {
+    if prefix.len() > data.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < prefix.len() {
+        if data[i] != prefix[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Seed standing in for a borsh encoded key (`Brc20Key`, `SatRangeKey`, `HeightRangeKey`,
+/// `ConsumerTarget`): keccak256 of its borsh bytes. Keccak is the only hash in the program's
+/// seeds, off chain derivations must use this or `owner_seed` rather than hashing themselves.
+pub fn borsh_seed<T: BorshSerialize>(value: &T) -> std::io::Result<[u8; 32]> {
+    Ok(hash(&value.try_to_vec()?).to_bytes())
+}
+
+/// Seed standing in for a Bitcoin owner address: keccak256 of the address string as given, without
+/// the borsh length prefix `borsh_seed` would add.
+pub fn owner_seed(owner: &str) -> [u8; 32] {
+    hash(owner.as_bytes()).to_bytes()
+}
+
+pub fn find_committee_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&COMMITTEE_PREFIX], program_id)
+}
+
+pub fn find_blinded_asset_address(program_id: &Pubkey, blinded: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&BLINDED_ASSET_PREFIX, blinded], program_id)
+}
+
+pub fn find_stats_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&STATS_PREFIX], program_id)
+}
+
+/// Mainnet keeps the original committee account, other networks get their own.
+pub fn find_network_committee_address(program_id: &Pubkey, network: BitcoinNetwork) -> (Pubkey, u8) {
+    match network {
+        BitcoinNetwork::Mainnet => find_committee_address(program_id),
+        _ => Pubkey::find_program_address(&[&COMMITTEE_PREFIX, &[network as u8]], program_id),
+    }
+}
+
+/// `find_network_committee_address` for the bump stored in `Committee::bump`, without the bump search.
+pub fn create_network_committee_address(
+    program_id: &Pubkey,
+    network: BitcoinNetwork,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    match network {
+        BitcoinNetwork::Mainnet => Pubkey::create_program_address(&[&COMMITTEE_PREFIX, &[bump]], program_id),
+        _ => Pubkey::create_program_address(&[&COMMITTEE_PREFIX, &[network as u8], &[bump]], program_id),
+    }
+}
+
+/// `find_asset_address` for the bump stored in `Brc20Asset::bump`. Consumers checking an asset passed
+/// to them by CPI can use this instead of paying for `find_asset_address`.
+pub fn create_asset_address(program_id: &Pubkey, key: &Brc20Key, bump: u8) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[&ASSET_PREFIX, &asset_seed(key), &[bump]],
+        program_id,
+    )
+}
+
+pub fn find_asset_address(program_id: &Pubkey, key: &Brc20Key) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[&ASSET_PREFIX, &asset_seed(key)],
+        program_id,
+    )
+}
+
+// version 0 keys were hashed before `version` existed, their assets stay at that address.
+fn asset_seed(key: &Brc20Key) -> [u8; 32] {
+    match key.version {
+        0 => borsh_seed(&LegacyBrc20Key { height: key.height, tick: key.tick, owner: key.owner.clone() }).unwrap(),
+        _ => borsh_seed(key).unwrap(),
+    }
+}
+
+pub fn find_request_address(program_id: &Pubkey, asset: &Pubkey, requester: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&REQUEST_PREFIX, asset.as_ref(), requester.as_ref()], program_id)
+}
+
+pub fn find_snapshot_address(program_id: &Pubkey, height: u32, tick: &[u8; 4]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&SNAPSHOT_PREFIX, &height.to_le_bytes(), tick], program_id)
+}
+
+pub fn find_verifying_key_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&VERIFIER_PREFIX], program_id)
+}
+
+pub fn find_tick_policy_address(program_id: &Pubkey, tick: &[u8; 4]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&TICK_POLICY_PREFIX, tick], program_id)
+}
+
+pub fn find_sat_range_address(program_id: &Pubkey, key: &SatRangeKey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[&SAT_RANGE_PREFIX, &borsh_seed(key).unwrap()],
+        program_id,
+    )
+}
+
+pub fn find_address_map_address(program_id: &Pubkey, owner: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&ADDRESS_MAP_PREFIX, &owner_seed(owner)], program_id)
+}
+
+pub fn find_head_address(program_id: &Pubkey, tick: &[u8; 4], owner: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&HEAD_PREFIX, tick, &owner_seed(owner)], program_id)
+}
+
+pub fn find_height_range_address(program_id: &Pubkey, key: &HeightRangeKey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[&HEIGHT_RANGE_PREFIX, &borsh_seed(key).unwrap()],
+        program_id,
+    )
+}
+
+pub fn find_version_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&VERSION_PREFIX], program_id)
+}
+
+pub fn find_tick_registry_address(program_id: &Pubkey, tick: &[u8; 4]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&TICK_REGISTRY_PREFIX, tick], program_id)
+}
+
+pub fn find_index_page_address(program_id: &Pubkey, page: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&INDEX_PAGE_PREFIX, &page.to_le_bytes()], program_id)
+}
+
+pub fn find_consumer_address(program_id: &Pubkey, target: &ConsumerTarget, consumer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[&CONSUMER_PREFIX, &borsh_seed(target).unwrap(), consumer.as_ref()],
+        program_id,
+    )
+}
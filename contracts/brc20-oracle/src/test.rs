@@ -3,15 +3,32 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program_test::*;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::pubkey::Pubkey;
-use solana_program::{system_program, sysvar};
-use solana_program::keccak::hash;
+use solana_program::{bpf_loader_upgradeable, system_instruction, system_program, sysvar};
+use solana_program::clock::Clock;
+use solana_program::rent::Rent;
 use solana_sdk::ed25519_instruction::new_ed25519_instruction;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
 use solana_sdk::signers::Signers;
 use solana_sdk::transaction::Transaction;
-use crate::types::{Brc20Asset, Brc20Key, Brc20OracleInstruction, Committee};
-use crate::{COMMITTEE_PREFIX, ASSET_PREFIX};
+use crate::types::{AddressAssetMap, AmountDelta, AmountView, AssetHead, AttestationSource, BitcoinNetwork, BlindedAsset, Brc20Amount, Brc20Asset, Brc20Key, Brc20OracleInstruction, Committee, ConsumerRecord, ConsumerTarget, AmountStatus, Groth16Proof, Heartbeat, HeightRangeAttestation, HeightRangeKey, IndexPage, InscriptionId, LegacyBrc20Asset, LegacyBrc20Key, LegacyCommittee, LegacyHeartbeatCommittee, OracleStats, ProgramVersion, RejectReason, RequestRecord, RevokeReason, SatRangeAttestation, SatRangeKey, Snapshot, TickPolicy, TickRegistry, VerifyingKey, BRC20_KEY_VERSION};
+use crate::pda::{
+    create_network_committee_address, find_address_map_address, find_asset_address, find_blinded_asset_address,
+    find_committee_address, find_consumer_address, find_head_address, find_height_range_address,
+    find_index_page_address, find_network_committee_address, find_request_address, find_sat_range_address,
+    find_snapshot_address, find_stats_address, find_tick_policy_address, find_tick_registry_address,
+    find_verifying_key_address, find_version_address, ASSET_PREFIX,
+};
+use crate::merkle::{leaf_hash, node_hash};
+use crate::INDEX_PAGE_CAPACITY;
+use crate::message::{
+    signing_message_for_address_entry, signing_message_for_asset, signing_message_for_blinded_asset,
+    signing_message_for_committee, signing_message_for_delta, signing_message_for_heartbeat,
+    signing_message_for_height_range, signing_message_for_network_committee, signing_message_for_refresh,
+    signing_message_for_rejection, signing_message_for_revocation, signing_message_for_sat_range,
+    signing_message_for_snapshot, signing_message_for_tick_policy, signing_message_for_verifying_key, typed_header,
+    MessageKind,
+};
 
 const PROGRAM_ID: &str = "1111111QLbz7JHiBTspS962RLKV8GndWFwiEaqKM";
 
@@ -28,6 +45,22 @@ pub async fn process<T: Signers>(
     client.process_transaction(transaction).await
 }
 
+// `process`, returning the compute units the transaction consumed.
+pub async fn process_metered<T: Signers>(
+    client: &mut BanksClient,
+    payer: &Keypair,
+    signers: &T,
+    instructions: &[Instruction],
+) -> Result<u64, BanksClientError> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = client.get_latest_blockhash().await?;
+    transaction.sign(signers, recent_blockhash);
+
+    let result = client.process_transaction_with_metadata(transaction).await?;
+    result.result?;
+    Ok(result.metadata.map_or(0, |metadata| metadata.compute_units_consumed))
+}
+
 pub async fn query_data<T: BorshDeserialize>(
     banks_client: &mut BanksClient,
     account_id: Pubkey,
@@ -36,7 +69,7 @@ pub async fn query_data<T: BorshDeserialize>(
     T::try_from_slice(&account.data).unwrap()
 }
 
-pub async fn init_client() -> (BanksClient, Keypair) {
+pub fn init_program_test() -> ProgramTest {
     let mut program_test = ProgramTest::default();
     let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
     // load programs
@@ -45,10 +78,37 @@ pub async fn init_client() -> (BanksClient, Keypair) {
         program_id,
         processor!(crate::process_instruction),
     );
-    let (banks_client, payer, _) = program_test.start().await;
+    program_test
+}
+
+pub async fn init_client() -> (BanksClient, Keypair) {
+    let (banks_client, payer, _) = init_program_test().start().await;
     (banks_client, payer)
 }
 
+// `init_program_test` with a program data account naming `admin` as the upgrade authority,
+// programs added with `processor!` don't get one.
+pub fn init_program_test_with_admin(admin: &Pubkey) -> ProgramTest {
+    let mut program_test = init_program_test();
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    // `UpgradeableLoaderState::ProgramData` at slot 0.
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(admin.as_ref());
+    program_test.add_account(
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0,
+        solana_sdk::account::Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    program_test
+}
+
 pub async fn process_init_committee(
     banks_client: &mut BanksClient,
     payer: &Keypair,
@@ -56,20 +116,29 @@ pub async fn process_init_committee(
     new_committee: &Pubkey,
     id: u8,
 ) -> Pubkey {
+    try_init_committee(banks_client, payer, old_committee, new_committee, id).await.unwrap()
+}
+
+pub async fn try_init_committee(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    old_committee: &Keypair,
+    new_committee: &Pubkey,
+    id: u8,
+) -> Result<Pubkey, BanksClientError> {
     let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
 
-    let (committee_info_address, _) =
-        Pubkey::find_program_address(&[&COMMITTEE_PREFIX], &program_id);
+    let (committee_info_address, _) = find_committee_address(&program_id);
 
     let accounts = vec![
         AccountMeta::new(payer.pubkey(), true),
-        AccountMeta::new(committee_info_address.clone(), false),
+        AccountMeta::new(committee_info_address, false),
         AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new_readonly(sysvar::instructions::id(), false),
     ];
 
-    let new_committee = Committee { id, address: *new_committee, uid: 0 };
-    let sign_msg = new_committee.try_to_vec().unwrap();
+    let new_committee = Committee { id, address: *new_committee, uid: 0, last_heartbeat_slot: 0, bump: 0 };
+    let sign_msg = signing_message_for_committee(new_committee.id, &new_committee.address).unwrap();
 
     let verify_instruction = new_ed25519_instruction(
         &ed25519_dalek::Keypair::from_bytes(&old_committee.to_bytes()).unwrap(),
@@ -82,36 +151,101 @@ pub async fn process_init_committee(
         accounts,
         data,
     };
-    process(banks_client, payer, &[payer], &[verify_instruction, instruction]).await.unwrap();
-    committee_info_address
+    process(banks_client, payer, &[payer], &[verify_instruction, instruction]).await?;
+    Ok(committee_info_address)
 }
 
-pub async fn process_query(
+pub async fn process_init_network_committee(
     banks_client: &mut BanksClient,
     payer: &Keypair,
-    key: Brc20Key,
+    network: BitcoinNetwork,
+    old_committee: &Keypair,
+    new_committee: &Pubkey,
+    id: u8,
 ) -> Pubkey {
+    try_init_network_committee(banks_client, payer, network, old_committee, new_committee, id).await.unwrap()
+}
+
+pub async fn try_init_network_committee(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    network: BitcoinNetwork,
+    old_committee: &Keypair,
+    new_committee: &Pubkey,
+    id: u8,
+) -> Result<Pubkey, BanksClientError> {
     let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
 
-    let (committee_info_address, _) =
-        Pubkey::find_program_address(&[&COMMITTEE_PREFIX], &program_id);
-    let (asset_address, _) =
-        Pubkey::find_program_address(&[&ASSET_PREFIX, hash(key.try_to_vec().unwrap().as_slice()).as_ref()], &program_id);
+    let (committee_info_address, _) = find_network_committee_address(&program_id, network);
+
     let accounts = vec![
         AccountMeta::new(payer.pubkey(), true),
-        AccountMeta::new_readonly(committee_info_address, false),
-        AccountMeta::new(asset_address, false),
+        AccountMeta::new(committee_info_address, false),
         AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
     ];
 
-    let data = Brc20OracleInstruction::Request(key).try_to_vec().unwrap();
+    let new_committee = Committee { id, address: *new_committee, uid: 0, last_heartbeat_slot: 0, bump: 0 };
+    let sign_msg = signing_message_for_network_committee(network, new_committee.id, &new_committee.address).unwrap();
+
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&old_committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = old_committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::SetNetworkCommittee(network, new_committee, signature).try_to_vec().unwrap();
     let instruction = Instruction {
         program_id,
         accounts,
         data,
     };
-    process(banks_client, payer, &[payer], &[instruction]).await.unwrap();
-    asset_address
+    process(banks_client, payer, &[payer], &[verify_instruction, instruction]).await?;
+    Ok(committee_info_address)
+}
+
+pub async fn process_query(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    key: Brc20Key,
+    ttl: Option<u64>,
+) -> Result<Pubkey, BanksClientError> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let instructions = query_instructions(banks_client, payer, key, ttl).await;
+    process(banks_client, payer, &[payer], &instructions).await?;
+    Ok(asset_address)
+}
+
+pub async fn query_instructions(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    key: Brc20Key,
+    ttl: Option<u64>,
+) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (committee_info_address, _) = find_committee_address(&program_id);
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let (request_address, _) = find_request_address(&program_id, &asset_address, &payer.pubkey());
+    let (tick_registry_address, _) = find_tick_registry_address(&program_id, &key.tick);
+    let committee: Committee = query_data(banks_client, committee_info_address).await;
+    let (index_page_address, _) = find_index_page_address(&program_id, committee.uid / INDEX_PAGE_CAPACITY);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new(committee_info_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new(request_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(tick_registry_address, false),
+        AccountMeta::new(index_page_address, false),
+    ];
+
+    let data = Brc20OracleInstruction::Request(key, ttl).try_to_vec().unwrap();
+    vec![Instruction {
+        program_id,
+        accounts,
+        data,
+    }]
 }
 
 pub async fn process_insert(
@@ -119,30 +253,56 @@ pub async fn process_insert(
     payer: &Keypair,
     committee: &Keypair,
     committee_info: Pubkey,
-    uid: u64,
     key: Brc20Key,
-    amount: u128,
+    amount: Brc20Amount,
 ) -> Pubkey {
     let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let instructions = insert_instructions(banks_client, payer, committee, committee_info, key, amount).await;
+    process(banks_client, payer, &[payer], &instructions).await.unwrap();
+    asset_address
+}
+
+pub async fn insert_instructions(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    committee: &Keypair,
+    committee_info: Pubkey,
+    key: Brc20Key,
+    amount: Brc20Amount,
+) -> Vec<Instruction> {
+    confident_insert_instructions(banks_client, payer, committee, committee_info, key, amount, None).await
+}
+
+pub async fn confident_insert_instructions(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    committee: &Keypair,
+    committee_info: Pubkey,
+    key: Brc20Key,
+    amount: Brc20Amount,
+    confidence: Option<Brc20Amount>,
+) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
 
-    let (asset_address, _) =
-        Pubkey::find_program_address(&[&ASSET_PREFIX, hash(key.try_to_vec().unwrap().as_slice()).as_ref()], &program_id);
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let (head_address, _) = find_head_address(&program_id, &key.tick, &key.owner);
+    let request: Brc20Asset = query_data(banks_client, asset_address).await;
+    let (request_address, _) = find_request_address(&program_id, &asset_address, &request.requester);
 
     let accounts = vec![
-        AccountMeta::new_readonly(committee_info.clone(), false),
+        AccountMeta::new_readonly(committee_info, false),
         AccountMeta::new(asset_address, false),
         AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new(head_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(find_stats_address(&program_id).0, false),
+        AccountMeta::new_readonly(request_address, false),
     ];
-    let asset = Brc20Asset {
-        prefix: ASSET_PREFIX,
-        uid,
-        set: true,
-        key: key.clone(),
-        amount,
-    };
-    let asset_msg = asset.try_to_vec().unwrap();
+    let asset_msg = signing_message_for_asset(&asset_address, &request, amount, confidence, AttestationSource::default()).unwrap();
     let signature = committee.sign_message(&asset_msg).as_ref().to_vec();
-    let data = Brc20OracleInstruction::Insert(key, amount, signature).try_to_vec().unwrap();
+    let data = Brc20OracleInstruction::Insert(key, amount, confidence, AttestationSource::default(), signature).try_to_vec().unwrap();
 
     let verify_instruction = new_ed25519_instruction(
         &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
@@ -153,49 +313,1573 @@ pub async fn process_insert(
         accounts,
         data,
     };
+    vec![verify_instruction, instruction]
+}
+
+pub async fn process_insert_delta(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+    delta: AmountDelta,
+) -> Result<Pubkey, BanksClientError> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (committee_info_address, _) = find_committee_address(&program_id);
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let base_key = Brc20Key { height: delta.base_height, ..key.clone() };
+    let (base_address, _) = find_asset_address(&program_id, &base_key);
+    let (head_address, _) = find_head_address(&program_id, &key.tick, &key.owner);
+    let accounts = vec![
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new_readonly(base_address, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new(head_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let request: Brc20Asset = query_data(banks_client, asset_address).await;
+    let sign_msg = signing_message_for_delta(&asset_address, &request, &delta).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::InsertDelta(key, delta, signature).try_to_vec().unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data,
+    };
+    process(banks_client, payer, &[payer], &[verify_instruction, instruction]).await?;
+    Ok(asset_address)
+}
+
+pub async fn process_publish_snapshot(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    committee: &Keypair,
+    snapshot: Snapshot,
+) -> Pubkey {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (committee_info_address, _) = find_committee_address(&program_id);
+    let (snapshot_address, _) = find_snapshot_address(&program_id, snapshot.height, &snapshot.tick);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(snapshot_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_snapshot(&snapshot).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let header = typed_header(MessageKind::Snapshot, snapshot.try_to_vec().unwrap().len());
+    let data = Brc20OracleInstruction::PublishSnapshot(header, snapshot, signature).try_to_vec().unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data,
+    };
     process(banks_client, payer, &[payer], &[verify_instruction, instruction]).await.unwrap();
-    asset_address
+    snapshot_address
+}
+
+pub async fn process_materialize(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    key: Brc20Key,
+    amount: Brc20Amount,
+    proof: Vec<[u8; 32]>,
+) -> Result<Pubkey, BanksClientError> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (committee_info_address, _) = find_network_committee_address(&program_id, key.network());
+    let (snapshot_address, _) = find_snapshot_address(&program_id, key.height, &key.tick);
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new(committee_info_address, false),
+        AccountMeta::new_readonly(snapshot_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let data = Brc20OracleInstruction::MaterializeEntry(key, amount, proof).try_to_vec().unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data,
+    };
+    process(banks_client, payer, &[payer], &[instruction]).await?;
+    Ok(asset_address)
+}
+
+pub async fn process_expire(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    key: Brc20Key,
+    requester: Pubkey,
+) -> Result<(), BanksClientError> {
+    let asset: Brc20Asset = query_data(banks_client, find_asset_address(&Pubkey::from_str(PROGRAM_ID).unwrap(), &key).0).await;
+    process(banks_client, payer, &[payer], &[expire_instruction(key, requester, asset.uid, &[])]).await
+}
+
+// `Expire` of the asset with `uid`, also closing the records of `requesters`.
+pub fn expire_instruction(key: Brc20Key, requester: Pubkey, uid: u64, requesters: &[Pubkey]) -> Instruction {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let mut accounts = vec![
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new(requester, false),
+        AccountMeta::new(find_tick_registry_address(&program_id, &key.tick).0, false),
+        AccountMeta::new(find_index_page_address(&program_id, uid / INDEX_PAGE_CAPACITY).0, false),
+    ];
+    for requester in requesters {
+        accounts.push(AccountMeta::new(find_request_address(&program_id, &asset_address, requester).0, false));
+        accounts.push(AccountMeta::new(*requester, false));
+    }
+
+    let data = Brc20OracleInstruction::Expire(key).try_to_vec().unwrap();
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub async fn process_insert_address_entry(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+    amount: Brc20Amount,
+) -> Result<Pubkey, BanksClientError> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (committee_info_address, _) = find_committee_address(&program_id);
+    let (address_map_address, _) = find_address_map_address(&program_id, &key.owner);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(address_map_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_address_entry(&address_map_address, &key, amount).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::InsertAddressEntry(key, amount, signature).try_to_vec().unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data,
+    };
+    process(banks_client, payer, &[payer], &[verify_instruction, instruction]).await?;
+    Ok(address_map_address)
 }
 
 #[tokio::test]
-async fn test_brc20_oracle() {
+async fn test_address_map() {
     let (mut banks_client, payer) = init_client().await;
-    println!("payer: {:?}", payer.pubkey());
-    let init_committee_pair = Keypair::new();
-    let new_committee_pair = Keypair::new();
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
 
-    // initialize committee
-    let committee_info_address = process_init_committee(&mut banks_client, &payer, &init_committee_pair, &init_committee_pair.pubkey(), 0).await;
-    let committee: Committee = query_data(&mut banks_client, committee_info_address).await;
-    assert_eq!(committee.id, 0);
-    assert_eq!(committee.address, init_committee_pair.pubkey());
+    let ordi = Brc20Key { version: BRC20_KEY_VERSION, height: 10, tick: *b"ordi", owner: "12345".to_string() };
+    let sats = Brc20Key { tick: *b"sats", ..ordi.clone() };
+    let map_address = process_insert_address_entry(&mut banks_client, &payer, &committee_pair, sats, Brc20Amount(5)).await.unwrap();
+    process_insert_address_entry(&mut banks_client, &payer, &committee_pair, ordi.clone(), Brc20Amount(1)).await.unwrap();
+    let newer = Brc20Key { height: 11, ..ordi.clone() };
+    process_insert_address_entry(&mut banks_client, &payer, &committee_pair, newer, Brc20Amount(2)).await.unwrap();
 
-    // change committee
-    let committee_info_address = process_init_committee(&mut banks_client, &payer, &init_committee_pair, &new_committee_pair.pubkey(), 1).await;
-    let committee: Committee = query_data(&mut banks_client, committee_info_address).await;
-    assert_eq!(committee.id, 1);
-    assert_eq!(committee.address, new_committee_pair.pubkey());
+    // entries stay sorted by tick and an update replaces the tick in place.
+    let map: AddressAssetMap = query_data(&mut banks_client, map_address).await;
+    assert_eq!(2, map.entries.len());
+    assert_eq!(*b"ordi", map.entries[0].tick);
+    assert_eq!(Brc20Amount(2), map.get(b"ordi").unwrap().amount);
+    assert_eq!(Brc20Amount(5), map.get(b"sats").unwrap().amount);
 
-    // query brc20 amount
-    let key = Brc20Key { height: 1, tick: [1, 2, 3, 4], owner: "12345".to_string() };
-    let asset_address = process_query(&mut banks_client, &payer, key.clone()).await;
-    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
-    assert_eq!(key, asset.key);
-    assert_eq!(0, asset.amount);
-    assert_eq!(0, asset.uid);
-    assert_eq!(false, asset.set);
+    // an older height can't overwrite a newer one.
+    assert!(process_insert_address_entry(&mut banks_client, &payer, &committee_pair, ordi, Brc20Amount(3)).await.is_err());
+}
 
-    // insert brc20 amount
-    let asset_address = process_insert(
-        &mut banks_client,
-        &payer,
-        &new_committee_pair,
-        committee_info_address,
-        0,
-        key,
-        1000,
-    ).await;
-    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
-    assert_eq!(1000, asset.amount);
-    assert_eq!(true, asset.set);
+#[tokio::test]
+async fn test_request_expire() {
+    let mut context = init_program_test().start_with_context().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut context.banks_client, &context.payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut context.banks_client, &context.payer, key.clone(), Some(10)).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut context.banks_client, asset_address).await;
+    assert_eq!(context.payer.pubkey(), asset.requester);
+    assert!(asset.expire_slot.is_some());
+
+    // can't expire before the ttl elapsed.
+    let requester = context.payer.pubkey();
+    assert!(process_expire(&mut context.banks_client, &context.payer, key.clone(), requester).await.is_err());
+
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 100).unwrap();
+    process_expire(&mut context.banks_client, &context.payer, key.clone(), requester).await.unwrap();
+    assert!(context.banks_client.get_account(asset_address).await.unwrap().is_none());
+
+    // nothing is left pointing at the closed asset.
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (head_address, _) = find_head_address(&program_id, &key.tick, &key.owner);
+    assert!(context.banks_client.get_account(head_address).await.unwrap().is_none());
+    let registry: TickRegistry = query_data(&mut context.banks_client, find_tick_registry_address(&program_id, &key.tick).0).await;
+    assert_eq!(1, registry.count);
+    assert_eq!(Pubkey::default(), registry.head);
+    let page: IndexPage = query_data(&mut context.banks_client, find_index_page_address(&program_id, asset.uid / INDEX_PAGE_CAPACITY).0).await;
+    assert!(page.entries.is_empty());
+}
+
+pub async fn process_reject(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+    reason: RejectReason,
+) -> Result<(), BanksClientError> {
+    let instructions = reject_instructions(banks_client, committee, key, reason).await;
+    process(banks_client, payer, &[payer], &instructions).await
+}
+
+pub async fn reject_instructions(
+    banks_client: &mut BanksClient,
+    committee: &Keypair,
+    key: Brc20Key,
+    reason: RejectReason,
+) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (committee_info_address, _) = find_committee_address(&program_id);
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let accounts = vec![
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let request: Brc20Asset = query_data(banks_client, asset_address).await;
+    let sign_msg = signing_message_for_rejection(&asset_address, &request, reason).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::Reject(key, reason, signature).try_to_vec().unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data,
+    };
+    vec![verify_instruction, instruction]
+}
+
+#[tokio::test]
+async fn test_stats() {
+    let mut context = init_program_test().start_with_context().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut context.banks_client, &context.payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    process_query(&mut context.banks_client, &context.payer, key.clone(), None).await.unwrap();
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 100).unwrap();
+    process_insert(&mut context.banks_client, &context.payer, &committee_pair, committee_info, key, Brc20Amount(1000)).await;
+
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let stats: OracleStats = query_data(&mut context.banks_client, find_stats_address(&program_id).0).await;
+    assert_eq!(1, stats.total_inserts);
+    assert_eq!(0, stats.missed_heartbeats);
+    // fulfilled between 50 and 150 slots after the request.
+    assert_eq!([0, 0, 1, 0, 0, 0], stats.latency_buckets);
+    assert_eq!(5, crate::latency_bucket(3001));
+}
+
+#[tokio::test]
+async fn test_alternative_rent() {
+    // SVM chains price rent differently, accounts must be funded from the Rent sysvar, not mainnet's numbers.
+    let context = init_program_test().start_with_context().await;
+    let rent = Rent { lamports_per_byte_year: Rent::default().lamports_per_byte_year * 10, ..Rent::default() };
+    context.set_sysvar(&rent);
+    let mut banks_client = context.banks_client.clone();
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &context.payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut banks_client, &context.payer, key, None).await.unwrap();
+    let account = banks_client.get_account(asset_address).await.unwrap().unwrap();
+    assert!(account.lamports >= rent.minimum_balance(account.data.len()));
+    assert!(account.lamports > Rent::default().minimum_balance(account.data.len()));
+}
+
+#[tokio::test]
+async fn test_reject() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+
+    // only the committee can reject.
+    let other = Keypair::new();
+    assert!(process_reject(&mut banks_client, &payer, &other, key.clone(), RejectReason::InvalidTick).await.is_err());
+
+    process_reject(&mut banks_client, &payer, &committee_pair, key.clone(), RejectReason::HeightTooOld).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(Some(RejectReason::HeightTooOld), asset.rejected);
+
+    // a rejected request can be closed right away even without a ttl.
+    process_expire(&mut banks_client, &payer, key, payer.pubkey()).await.unwrap();
+    assert!(banks_client.get_account(asset_address).await.unwrap().is_none());
+}
+
+pub async fn process_revoke(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+    revision: u64,
+    reason: RevokeReason,
+) -> Result<(), BanksClientError> {
+    let instructions = revoke_instructions(banks_client, committee, key, revision, reason).await;
+    process(banks_client, payer, &[payer], &instructions).await
+}
+
+pub async fn revoke_instructions(
+    banks_client: &mut BanksClient,
+    committee: &Keypair,
+    key: Brc20Key,
+    revision: u64,
+    reason: RevokeReason,
+) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (committee_info_address, _) = find_committee_address(&program_id);
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let accounts = vec![
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let asset: Brc20Asset = query_data(banks_client, asset_address).await;
+    let sign_msg = signing_message_for_revocation(&asset_address, &asset, reason).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::Revoke(key, revision, reason, signature).try_to_vec().unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data,
+    };
+    vec![verify_instruction, instruction]
+}
+
+#[tokio::test]
+async fn test_compute_budgets() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let rejected = Brc20Key { height: 2, ..key.clone() };
+
+    // the first request of a tick also creates its registry and index page, the costliest path.
+    let instructions = query_instructions(&mut banks_client, &payer, key.clone(), None).await;
+    let units = process_metered(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+    assert!(units <= crate::REQUEST_COMPUTE_BUDGET as u64, "request used {} compute units", units);
+
+    // the first insert also creates the head and stats accounts.
+    let instructions = insert_instructions(&mut banks_client, &payer, &committee_pair, committee_info, key.clone(), Brc20Amount(1000)).await;
+    let units = process_metered(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+    assert!(units <= crate::INSERT_COMPUTE_BUDGET as u64, "insert used {} compute units", units);
+
+    let asset: Brc20Asset = query_data(&mut banks_client, find_asset_address(&Pubkey::from_str(PROGRAM_ID).unwrap(), &key).0).await;
+    let instructions = revoke_instructions(&mut banks_client, &committee_pair, key, asset.attested_slot, RevokeReason::IndexerBug).await;
+    let units = process_metered(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+    assert!(units <= crate::REVOKE_COMPUTE_BUDGET as u64, "revoke used {} compute units", units);
+
+    process_query(&mut banks_client, &payer, rejected.clone(), None).await.unwrap();
+    let instructions = reject_instructions(&mut banks_client, &committee_pair, rejected, RejectReason::InvalidTick).await;
+    let units = process_metered(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+    assert!(units <= crate::REJECT_COMPUTE_BUDGET as u64, "reject used {} compute units", units);
+}
+
+#[tokio::test]
+async fn test_revoke() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    // nothing to revoke before the insert.
+    assert!(process_revoke(&mut banks_client, &payer, &committee_pair, key.clone(), 0, RevokeReason::IndexerBug).await.is_err());
+
+    process_insert(&mut banks_client, &payer, &committee_pair, committee_info, key.clone(), Brc20Amount(1000)).await;
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    let revision = asset.attested_slot;
+    assert!(process_revoke(&mut banks_client, &payer, &committee_pair, key.clone(), revision + 1, RevokeReason::IndexerBug).await.is_err());
+
+    process_revoke(&mut banks_client, &payer, &committee_pair, key, revision, RevokeReason::IndexerBug).await.unwrap();
+    let revoked: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(Some(RevokeReason::IndexerBug), revoked.revoked);
+    // the amount stays for the record.
+    assert_eq!(Brc20Amount(1000), revoked.amount);
+    assert_eq!(AmountStatus::Revoked, crate::amount_view(Some(&revoked), None, revision).status);
+}
+
+#[tokio::test]
+async fn test_insert_with_confidence() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    let instructions = confident_insert_instructions(
+        &mut banks_client, &payer, &committee_pair, committee_info, key.clone(), Brc20Amount(1000), Some(Brc20Amount(900)),
+    ).await;
+    process(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(Brc20Amount(1000), asset.amount);
+    assert_eq!(Some(Brc20Amount(900)), asset.confidence);
+
+    // still room for the revocation.
+    process_revoke(&mut banks_client, &payer, &committee_pair, key, asset.attested_slot, RevokeReason::IndexerBug).await.unwrap();
+    let revoked: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(Some(Brc20Amount(900)), revoked.confidence);
+    assert_eq!(Some(RevokeReason::IndexerBug), revoked.revoked);
+}
+
+// refresh of `asset` as it is passed, which may be older than the stored one.
+pub fn refresh_instructions(committee: &Keypair, asset: &Brc20Asset) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let (committee_info_address, _) = find_committee_address(&program_id);
+    let (asset_address, _) = find_asset_address(&program_id, &asset.key);
+    let accounts = vec![
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_refresh(&asset_address, asset).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::Refresh(asset.key.clone(), asset.attested_slot, signature).try_to_vec().unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data,
+    };
+    vec![verify_instruction, instruction]
+}
+
+#[tokio::test]
+async fn test_refresh() {
+    let mut context = init_program_test().start_with_context().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut context.banks_client, &context.payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut context.banks_client, &context.payer, key.clone(), None).await.unwrap();
+    // nothing to refresh before the insert.
+    let request: Brc20Asset = query_data(&mut context.banks_client, asset_address).await;
+    let instructions = refresh_instructions(&committee_pair, &request);
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &instructions).await.is_err());
+
+    process_insert(&mut context.banks_client, &context.payer, &committee_pair, committee_info, key, Brc20Amount(1000)).await;
+    let inserted: Brc20Asset = query_data(&mut context.banks_client, asset_address).await;
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 100).unwrap();
+
+    // only the committee can refresh.
+    let instructions = refresh_instructions(&Keypair::new(), &inserted);
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &instructions).await.is_err());
+
+    let instructions = refresh_instructions(&committee_pair, &inserted);
+    process(&mut context.banks_client, &context.payer, &[&context.payer], &instructions).await.unwrap();
+    let refreshed: Brc20Asset = query_data(&mut context.banks_client, asset_address).await;
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    assert_eq!(clock.slot, refreshed.attested_slot);
+    assert!(refreshed.attested_slot > inserted.attested_slot);
+    assert_eq!(Brc20Asset { attested_slot: inserted.attested_slot, ..refreshed.clone() }, inserted);
+
+    // the signature was bound to the old attested_slot, replaying it in a later slot fails.
+    context.warp_to_slot(clock.slot + 100).unwrap();
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &instructions).await.is_err());
+    let replayed: Brc20Asset = query_data(&mut context.banks_client, asset_address).await;
+    assert_eq!(refreshed, replayed);
+}
+
+#[tokio::test]
+async fn test_register_consumer() {
+    let (mut banks_client, payer) = init_client().await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let consumer = Keypair::new();
+    let target = ConsumerTarget::Tick(*b"ordi");
+    let (consumer_address, _) = find_consumer_address(&program_id, &target, &consumer.pubkey());
+
+    let register = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(consumer.pubkey(), true),
+            AccountMeta::new(consumer_address, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: Brc20OracleInstruction::RegisterConsumer(target.clone()).try_to_vec().unwrap(),
+    };
+    process(&mut banks_client, &payer, &[&payer, &consumer], std::slice::from_ref(&register)).await.unwrap();
+    let record: ConsumerRecord = query_data(&mut banks_client, consumer_address).await;
+    assert_eq!(target, record.target);
+    assert_eq!(consumer.pubkey(), record.consumer);
+
+    // one record per consumer and target, the transfer keeps the transaction distinct.
+    let transfer = system_instruction::transfer(&payer.pubkey(), &consumer.pubkey(), 1);
+    assert!(process(&mut banks_client, &payer, &[&payer, &consumer], &[transfer, register]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_insert_delta() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let base = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    process_query(&mut banks_client, &payer, base.clone(), None).await.unwrap();
+    process_insert(&mut banks_client, &payer, &committee_pair, committee_info, base.clone(), Brc20Amount(1000)).await;
+
+    let key = Brc20Key { height: 5, ..base };
+    process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    // can't go below zero.
+    let overdraw = AmountDelta { base_height: 1, amount_change: -1001 };
+    assert!(process_insert_delta(&mut banks_client, &payer, &committee_pair, key.clone(), overdraw).await.is_err());
+
+    let delta = AmountDelta { base_height: 1, amount_change: -300 };
+    let asset_address = process_insert_delta(&mut banks_client, &payer, &committee_pair, key, delta).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(Brc20Amount(700), asset.amount);
+    assert!(asset.set);
+}
+
+#[tokio::test]
+async fn test_canonical_key() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ORDI", owner: " BC1QXY2KGDYGJRSQTZQ2N0YRF2493P83KKFJHX0WLH".to_string() };
+    assert!(!key.is_canonical());
+    assert!(process_query(&mut banks_client, &payer, key.clone(), None).await.is_err());
+
+    let canonical = key.canonicalize();
+    assert_eq!(*b"ordi", canonical.tick);
+    assert_eq!("bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh", canonical.owner);
+    process_query(&mut banks_client, &payer, canonical, None).await.unwrap();
+
+    // base58 addresses are case sensitive and stay as they are.
+    let base58 = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2".to_string() };
+    assert!(base58.is_canonical());
+}
+
+#[tokio::test]
+async fn test_shared_request() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+
+    let second = Keypair::new();
+    let fund = system_instruction::transfer(&payer.pubkey(), &second.pubkey(), 1_000_000_000);
+    process(&mut banks_client, &payer, &[&payer], &[fund]).await.unwrap();
+
+    // a second requester joins the same asset instead of failing.
+    assert_eq!(asset_address, process_query(&mut banks_client, &second, key.clone(), None).await.unwrap());
+    let shared: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(asset, shared);
+
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (request_address, _) = find_request_address(&program_id, &asset_address, &second.pubkey());
+    let record: RequestRecord = query_data(&mut banks_client, request_address).await;
+    assert_eq!(second.pubkey(), record.requester);
+
+    // joining doesn't count as a new asset for the tick.
+    let (tick_registry_address, _) = find_tick_registry_address(&program_id, &key.tick);
+    let registry: TickRegistry = query_data(&mut banks_client, tick_registry_address).await;
+    assert_eq!(1, registry.count);
+    assert_eq!(asset.uid, registry.last_uid);
+    assert_eq!(asset_address, registry.head);
+
+    let index_page: IndexPage = query_data(&mut banks_client, find_index_page_address(&program_id, 0).0).await;
+    assert_eq!(1, index_page.entries.len());
+    assert_eq!(asset_address, index_page.entries[0].asset);
+    assert_eq!(find_index_page_address(&program_id, 1).0, index_page.next);
+
+    // but the same requester can't ask twice.
+    assert!(process_query(&mut banks_client, &second, key, Some(10)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_shared_request_lifecycle() {
+    let mut context = init_program_test().start_with_context().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut context.banks_client, &context.payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let second = Keypair::new();
+    let fund = system_instruction::transfer(&context.payer.pubkey(), &second.pubkey(), 1_000_000_000);
+    process(&mut context.banks_client, &context.payer, &[&context.payer], &[fund]).await.unwrap();
+
+    // an answered request can't be joined.
+    let filled = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    process_query(&mut context.banks_client, &context.payer, filled.clone(), None).await.unwrap();
+    process_insert(&mut context.banks_client, &context.payer, &committee_pair, committee_info, filled.clone(), Brc20Amount(1000)).await;
+    assert!(process_query(&mut context.banks_client, &second, filled, None).await.is_err());
+
+    // nor a rejected one.
+    let rejected = Brc20Key { version: BRC20_KEY_VERSION, height: 2, tick: *b"ordi", owner: "12345".to_string() };
+    process_query(&mut context.banks_client, &context.payer, rejected.clone(), None).await.unwrap();
+    process_reject(&mut context.banks_client, &context.payer, &committee_pair, rejected.clone(), RejectReason::InvalidTick).await.unwrap();
+    assert!(process_query(&mut context.banks_client, &second, rejected, None).await.is_err());
+
+    // a pending one can, until it expires.
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 3, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut context.banks_client, &context.payer, key.clone(), Some(10)).await.unwrap();
+    process_query(&mut context.banks_client, &second, key.clone(), None).await.unwrap();
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 100).unwrap();
+    let third = Keypair::new();
+    let fund = system_instruction::transfer(&context.payer.pubkey(), &third.pubkey(), 1_000_000_000);
+    process(&mut context.banks_client, &context.payer, &[&context.payer], &[fund]).await.unwrap();
+    assert!(process_query(&mut context.banks_client, &third, key.clone(), None).await.is_err());
+
+    // a record of another asset can't be closed through this one.
+    let other = Brc20Key { height: 1, ..key.clone() };
+    let other_record = find_request_address(&program_id, &find_asset_address(&program_id, &other).0, &context.payer.pubkey()).0;
+    let uid = query_data::<Brc20Asset>(&mut context.banks_client, asset_address).await.uid;
+    let mut wrong = expire_instruction(key.clone(), context.payer.pubkey(), uid, &[]);
+    wrong.accounts.push(AccountMeta::new(other_record, false));
+    wrong.accounts.push(AccountMeta::new(context.payer.pubkey(), false));
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &[wrong]).await.is_err());
+
+    // expiring closes both records, each requester gets its own rent back.
+    let second_record = find_request_address(&program_id, &asset_address, &second.pubkey()).0;
+    let record_rent = context.banks_client.get_account(second_record).await.unwrap().unwrap().lamports;
+    let second_balance = context.banks_client.get_balance(second.pubkey()).await.unwrap();
+    let expire = expire_instruction(key, context.payer.pubkey(), uid, &[context.payer.pubkey(), second.pubkey()]);
+    process(&mut context.banks_client, &context.payer, &[&context.payer], &[expire]).await.unwrap();
+    assert!(context.banks_client.get_account(asset_address).await.unwrap().is_none());
+    assert!(context.banks_client.get_account(second_record).await.unwrap().is_none());
+    let first_record = find_request_address(&program_id, &asset_address, &context.payer.pubkey()).0;
+    assert!(context.banks_client.get_account(first_record).await.unwrap().is_none());
+    assert_eq!(second_balance + record_rent, context.banks_client.get_balance(second.pubkey()).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_get_version() {
+    let (mut banks_client, payer) = init_client().await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (version_address, _) = find_version_address(&program_id);
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(version_address, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: Brc20OracleInstruction::GetVersion.try_to_vec().unwrap(),
+    };
+    process(&mut banks_client, &payer, &[&payer], &[instruction]).await.unwrap();
+    let version: ProgramVersion = query_data(&mut banks_client, version_address).await;
+    assert_eq!(crate::program_version(), version);
+}
+
+#[tokio::test]
+async fn test_snapshot_materialize() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let key_a = Brc20Key { version: BRC20_KEY_VERSION, height: 10, tick: *b"ordi", owner: "a".to_string() };
+    let key_b = Brc20Key { version: BRC20_KEY_VERSION, height: 10, tick: *b"ordi", owner: "b".to_string() };
+    let leaf_a = leaf_hash(&key_a, Brc20Amount(100)).unwrap();
+    let leaf_b = leaf_hash(&key_b, Brc20Amount(200)).unwrap();
+    let snapshot = Snapshot { height: 10, tick: *b"ordi", root: node_hash(&leaf_a, &leaf_b) };
+    process_publish_snapshot(&mut banks_client, &payer, &committee_pair, snapshot.clone()).await;
+
+    // a proof for the wrong amount is rejected.
+    assert!(process_materialize(&mut banks_client, &payer, key_a.clone(), Brc20Amount(101), vec![leaf_b]).await.is_err());
+
+    let asset_address = process_materialize(&mut banks_client, &payer, key_a.clone(), Brc20Amount(100), vec![leaf_b]).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(key_a, asset.key);
+    assert_eq!(Brc20Amount(100), asset.amount);
+    assert!(asset.set);
+
+    // a testnet entry is materialized through the testnet committee, so it needs one.
+    let key_t = Brc20Key { version: BRC20_KEY_VERSION, height: 11, tick: *b"ordi", owner: "tb1qtestowner".to_string() };
+    let leaf_t = leaf_hash(&key_t, Brc20Amount(300)).unwrap();
+    let snapshot = Snapshot { height: 11, tick: *b"ordi", root: node_hash(&leaf_t, &leaf_a) };
+    process_publish_snapshot(&mut banks_client, &payer, &committee_pair, snapshot).await;
+    assert!(process_materialize(&mut banks_client, &payer, key_t.clone(), Brc20Amount(300), vec![leaf_a]).await.is_err());
+    let testnet_pair = Keypair::new();
+    process_init_network_committee(&mut banks_client, &payer, BitcoinNetwork::Testnet, &testnet_pair, &testnet_pair.pubkey(), 0).await;
+    let asset_address = process_materialize(&mut banks_client, &payer, key_t.clone(), Brc20Amount(300), vec![leaf_a]).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(key_t, asset.key);
+    assert_eq!(Brc20Amount(300), asset.amount);
+}
+
+// verify instruction reading its message from instruction 1 at `offset`.
+fn referenced_ed25519_instruction(committee: &Keypair, sign_msg: &[u8], offset: u16) -> Instruction {
+    let mut data = vec![1, 0];
+    for field in [48u16, u16::MAX, 16, u16::MAX, offset, sign_msg.len() as u16, 1] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(committee.pubkey().as_ref());
+    data.extend_from_slice(committee.sign_message(sign_msg).as_ref());
+    Instruction { program_id: solana_program::ed25519_program::id(), accounts: vec![], data }
+}
+
+#[tokio::test]
+async fn test_snapshot_referenced_message() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let snapshot = Snapshot { height: 10, tick: *b"ordi", root: [7; 32] };
+    let (snapshot_address, _) = find_snapshot_address(&program_id, snapshot.height, &snapshot.tick);
+    let sign_msg = signing_message_for_snapshot(&snapshot).unwrap();
+    let signature = committee_pair.sign_message(&sign_msg).as_ref().to_vec();
+    let header = typed_header(MessageKind::Snapshot, snapshot.try_to_vec().unwrap().len());
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(find_committee_address(&program_id).0, false),
+            AccountMeta::new(snapshot_address, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: Brc20OracleInstruction::PublishSnapshot(header, snapshot.clone(), signature).try_to_vec().unwrap(),
+    };
+
+    // the referenced bytes must be the message the program expects, not just any signed bytes.
+    let misplaced = referenced_ed25519_instruction(&committee_pair, &instruction.data[..sign_msg.len()], 0);
+    assert!(process(&mut banks_client, &payer, &[&payer], &[misplaced, instruction.clone()]).await.is_err());
+
+    let verify_instruction = referenced_ed25519_instruction(&committee_pair, &sign_msg, 1);
+    assert_eq!(112, verify_instruction.data.len());
+    process(&mut banks_client, &payer, &[&payer], &[verify_instruction, instruction]).await.unwrap();
+    let published: Snapshot = query_data(&mut banks_client, snapshot_address).await;
+    assert_eq!(snapshot, published);
+}
+
+#[tokio::test]
+async fn test_blinded_request() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let blinded = crate::blind_key(&key, &[9; 32]).unwrap();
+    assert_ne!(blinded, crate::blind_key(&key, &[8; 32]).unwrap());
+    let (blinded_asset_address, _) = find_blinded_asset_address(&program_id, &blinded);
+    let request = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(blinded_asset_address, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: Brc20OracleInstruction::RequestBlinded(blinded).try_to_vec().unwrap(),
+    };
+    process(&mut banks_client, &payer, &[&payer], &[request]).await.unwrap();
+    let asset: BlindedAsset = query_data(&mut banks_client, blinded_asset_address).await;
+    assert!(!asset.set);
+
+    let sign_msg = signing_message_for_blinded_asset(&blinded_asset_address, &asset, Brc20Amount(1000)).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee_pair.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee_pair.sign_message(&sign_msg).as_ref().to_vec();
+    let insert = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(find_committee_address(&program_id).0, false),
+            AccountMeta::new(blinded_asset_address, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: Brc20OracleInstruction::InsertBlinded(blinded, Brc20Amount(1000), signature).try_to_vec().unwrap(),
+    };
+    process(&mut banks_client, &payer, &[&payer], &[verify_instruction, insert]).await.unwrap();
+    let asset: BlindedAsset = query_data(&mut banks_client, blinded_asset_address).await;
+    assert!(asset.set);
+    assert_eq!(Brc20Amount(1000), asset.amount);
+}
+
+#[tokio::test]
+async fn test_brc20_oracle() {
+    let (mut banks_client, payer) = init_client().await;
+    println!("payer: {:?}", payer.pubkey());
+    let init_committee_pair = Keypair::new();
+    let new_committee_pair = Keypair::new();
+
+    // initialize committee
+    let committee_info_address = process_init_committee(&mut banks_client, &payer, &init_committee_pair, &init_committee_pair.pubkey(), 0).await;
+    let committee: Committee = query_data(&mut banks_client, committee_info_address).await;
+    assert_eq!(committee.id, 0);
+    assert_eq!(committee.address, init_committee_pair.pubkey());
+
+    // change committee
+    let committee_info_address = process_init_committee(&mut banks_client, &payer, &init_committee_pair, &new_committee_pair.pubkey(), 1).await;
+    let committee: Committee = query_data(&mut banks_client, committee_info_address).await;
+    assert_eq!(committee.id, 1);
+    assert_eq!(committee.address, new_committee_pair.pubkey());
+
+    // query brc20 amount
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: [1, 2, 3, 4], owner: "12345".to_string() };
+    let asset_address = process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(key, asset.key);
+    assert_eq!(Brc20Amount::ZERO, asset.amount);
+    assert_eq!(0, asset.uid);
+    assert!(!asset.set);
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    assert_eq!(find_asset_address(&program_id, &key).1, asset.bump);
+    assert_eq!(find_committee_address(&program_id).1, committee.bump);
+
+    // insert brc20 amount
+    let asset_address = process_insert(
+        &mut banks_client,
+        &payer,
+        &new_committee_pair,
+        committee_info_address,
+        key.clone(),
+        Brc20Amount(1000),
+    ).await;
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(Brc20Amount(1000), asset.amount);
+    assert!(asset.set);
+
+    // the head follows the inserted asset.
+    let (head_address, _) = find_head_address(&program_id, &key.tick, &key.owner);
+    let head: AssetHead = query_data(&mut banks_client, head_address).await;
+    assert_eq!(key.height, head.height);
+    assert_eq!(asset_address, head.asset);
+}
+
+#[tokio::test]
+async fn test_prefunded_head() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let (head_address, _) = find_head_address(&program_id, &key.tick, &key.owner);
+    // lamports sent to the head address before its first insert don't block it.
+    let fund = system_instruction::transfer(&payer.pubkey(), &head_address, Rent::default().minimum_balance(0));
+    process(&mut banks_client, &payer, &[&payer], &[fund]).await.unwrap();
+
+    process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    let asset_address = process_insert(&mut banks_client, &payer, &committee_pair, committee_info, key.clone(), Brc20Amount(1000)).await;
+    let head: AssetHead = query_data(&mut banks_client, head_address).await;
+    assert_eq!(AssetHead { height: key.height, asset: asset_address }, head);
+}
+
+#[tokio::test]
+async fn test_forged_rotation() {
+    let (mut banks_client, payer) = init_client().await;
+    let (committee_pair, next, attacker) = (Keypair::new(), Keypair::new(), Keypair::new());
+    let committee_info = process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    // the committee exists, a second first committee is rejected.
+    assert!(try_init_committee(&mut banks_client, &payer, &attacker, &attacker.pubkey(), 0).await.is_err());
+
+    // wrong signer.
+    assert!(try_init_committee(&mut banks_client, &payer, &attacker, &attacker.pubkey(), 1).await.is_err());
+    // right signer, ids that don't follow the current one.
+    assert!(try_init_committee(&mut banks_client, &payer, &committee_pair, &next.pubkey(), 2).await.is_err());
+    assert!(try_init_committee(&mut banks_client, &payer, &committee_pair, &next.pubkey(), 0).await.is_err());
+
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &next.pubkey(), 1).await;
+    // the same signed rotation sent again, from another payer so it isn't deduplicated as a transaction.
+    let other_payer = Keypair::new();
+    let fund = system_instruction::transfer(&payer.pubkey(), &other_payer.pubkey(), 1_000_000_000);
+    process(&mut banks_client, &payer, &[&payer], &[fund]).await.unwrap();
+    assert!(try_init_committee(&mut banks_client, &other_payer, &committee_pair, &next.pubkey(), 1).await.is_err());
+
+    let committee: Committee = query_data(&mut banks_client, committee_info).await;
+    assert_eq!((1, next.pubkey()), (committee.id, committee.address));
+}
+
+#[cfg(not(feature = "mainnet-strict"))]
+#[tokio::test]
+async fn test_network_committee() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let testnet_pair = Keypair::new();
+    let testnet_info =
+        process_init_network_committee(&mut banks_client, &payer, BitcoinNetwork::Testnet, &testnet_pair, &testnet_pair.pubkey(), 0).await;
+    assert_ne!(committee_info, testnet_info);
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "tb1qtestowner".to_string() };
+    assert_eq!(BitcoinNetwork::Testnet, key.network());
+    let asset_address = process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    process_insert(&mut banks_client, &payer, &testnet_pair, testnet_info, key, Brc20Amount(1000)).await;
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(Brc20Amount(1000), asset.amount);
+
+    // the testnet committee rotates on its own, the mainnet one is untouched.
+    let next = Keypair::new();
+    process_init_network_committee(&mut banks_client, &payer, BitcoinNetwork::Testnet, &testnet_pair, &next.pubkey(), 1).await;
+    let committee: Committee = query_data(&mut banks_client, testnet_info).await;
+    assert_eq!(next.pubkey(), committee.address);
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    assert_eq!(find_network_committee_address(&program_id, BitcoinNetwork::Testnet).1, committee.bump);
+    let mainnet: Committee = query_data(&mut banks_client, committee_info).await;
+    assert_eq!(committee_pair.pubkey(), mainnet.address);
+}
+
+#[tokio::test]
+async fn test_verify_committee_signature() {
+    let (mut banks_client, payer) = init_client().await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    let message = signing_message_for_committee(1, &Keypair::new().pubkey()).unwrap();
+    let verify = |signer: &Keypair| {
+        let verify_instruction = new_ed25519_instruction(&ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap(), &message);
+        let signature = signer.sign_message(&message).as_ref().to_vec();
+        let data = Brc20OracleInstruction::VerifyCommitteeSignature(BitcoinNetwork::Mainnet, message.clone(), signature)
+            .try_to_vec()
+            .unwrap();
+        let accounts = vec![
+            AccountMeta::new_readonly(committee_info, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ];
+        [verify_instruction, Instruction { program_id, accounts, data }]
+    };
+
+    process(&mut banks_client, &payer, &[&payer], &verify(&committee_pair)).await.unwrap();
+    // a valid signature from anyone but the committee is rejected.
+    assert!(process(&mut banks_client, &payer, &[&payer], &verify(&Keypair::new())).await.is_err());
+    // nothing changed.
+    let committee: Committee = query_data(&mut banks_client, committee_info).await;
+    assert_eq!(0, committee.id);
+}
+
+// program owned account holding `data` as an earlier program version wrote it.
+fn legacy_account(data: Vec<u8>) -> solana_sdk::account::Account {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    solana_sdk::account::Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn migrate_instruction(payer: &Pubkey, account: Pubkey, key: Option<Brc20Key>) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    let data = Brc20OracleInstruction::Migrate(key).try_to_vec().unwrap();
+    Instruction { program_id: Pubkey::from_str(PROGRAM_ID).unwrap(), accounts, data }
+}
+
+#[tokio::test]
+async fn test_legacy_committee_migration() {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (committee_info, bump) = find_committee_address(&program_id);
+    let committee_pair = Keypair::new();
+    // the committee as first deployed, before heartbeats and stored bumps.
+    let legacy = LegacyCommittee { id: 0, address: committee_pair.pubkey(), uid: 5 }.try_to_vec().unwrap();
+    assert_eq!(41, legacy.len());
+
+    // a request migrates it on the way, older clients don't pass the registry and index page.
+    let mut program_test = init_program_test();
+    program_test.add_account(committee_info, legacy_account(legacy.clone()));
+    let (mut banks_client, payer, _) = program_test.start().await;
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let (asset_address, _) = find_asset_address(&program_id, &key);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new(committee_info, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new(find_request_address(&program_id, &asset_address, &payer.pubkey()).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    let data = Brc20OracleInstruction::Request(key, None).try_to_vec().unwrap();
+    process(&mut banks_client, &payer, &[&payer], &[Instruction { program_id, accounts, data }]).await.unwrap();
+    let committee: Committee = query_data(&mut banks_client, committee_info).await;
+    assert_eq!(Committee { id: 0, address: committee_pair.pubkey(), uid: 6, last_heartbeat_slot: 0, bump }, committee);
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(5, asset.uid);
+
+    // so does a rotation, signed by the committee stored in the legacy layout.
+    let mut program_test = init_program_test();
+    program_test.add_account(committee_info, legacy_account(legacy));
+    let (mut banks_client, payer, _) = program_test.start().await;
+    let next = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &next.pubkey(), 1).await;
+    let committee: Committee = query_data(&mut banks_client, committee_info).await;
+    assert_eq!((1, next.pubkey(), 5, bump), (committee.id, committee.address, committee.uid, committee.bump));
+
+    // the layout with heartbeats but no bump, through `Migrate`.
+    let legacy = LegacyHeartbeatCommittee { id: 2, address: committee_pair.pubkey(), uid: 7, last_heartbeat_slot: 3 };
+    let mut program_test = init_program_test();
+    program_test.add_account(committee_info, legacy_account(legacy.try_to_vec().unwrap()));
+    let (mut banks_client, payer, _) = program_test.start().await;
+    let migrate = migrate_instruction(&payer.pubkey(), committee_info, None);
+    process(&mut banks_client, &payer, &[&payer], &[migrate]).await.unwrap();
+    let committee: Committee = query_data(&mut banks_client, committee_info).await;
+    assert_eq!(Committee { id: 2, address: committee_pair.pubkey(), uid: 7, last_heartbeat_slot: 3, bump }, committee);
+}
+
+#[tokio::test]
+async fn test_legacy_asset_migration() {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let legacy_key = LegacyBrc20Key { height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let key = Brc20Key::from(legacy_key.clone());
+    let (asset_address, bump) = find_asset_address(&program_id, &key);
+    // version 0 keys keep the address derived before keys had a version.
+    let seed = solana_program::keccak::hash(&legacy_key.try_to_vec().unwrap()).to_bytes();
+    assert_eq!(Pubkey::find_program_address(&[&ASSET_PREFIX, &seed], &program_id).0, asset_address);
+
+    let legacy = LegacyBrc20Asset { prefix: ASSET_PREFIX, set: true, uid: 3, key: legacy_key, amount: 1000 };
+    let mut program_test = init_program_test();
+    program_test.add_account(asset_address, legacy_account(legacy.try_to_vec().unwrap()));
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    let other = Brc20Key { height: 2, ..key.clone() };
+    let wrong_key = migrate_instruction(&payer.pubkey(), asset_address, Some(other));
+    assert!(process(&mut banks_client, &payer, &[&payer], &[wrong_key]).await.is_err());
+    let migrate = migrate_instruction(&payer.pubkey(), asset_address, Some(key.clone()));
+    process(&mut banks_client, &payer, &[&payer], &[migrate]).await.unwrap();
+    let asset: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!((true, 3, key, Brc20Amount(1000), bump), (asset.set, asset.uid, asset.key, asset.amount, asset.bump));
+    assert_eq!((Pubkey::default(), None, 0), (asset.requester, asset.expire_slot, asset.attested_slot));
+}
+
+pub fn set_tick_policy_instructions(payer: &Pubkey, admin: &Pubkey, committee: &Keypair, policy: TickPolicy) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(find_committee_address(&program_id).0, false),
+        AccountMeta::new(find_tick_policy_address(&program_id, &policy.tick).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new_readonly(programdata_address, false),
+    ];
+    let sign_msg = signing_message_for_tick_policy(&policy).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::SetTickPolicy(policy, signature).try_to_vec().unwrap();
+    vec![verify_instruction, Instruction { program_id, accounts, data }]
+}
+
+#[tokio::test]
+async fn test_set_tick_policy_needs_admin() {
+    let admin = Keypair::new();
+    let (mut banks_client, payer, _) = init_program_test_with_admin(&admin.pubkey()).start().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let policy = TickPolicy { tick: *b"ordi", max_age: 100, nonce: 1 };
+
+    // a committee signature alone isn't enough, the admin must be the upgrade authority.
+    let impostor = Keypair::new();
+    let instructions = set_tick_policy_instructions(&payer.pubkey(), &impostor.pubkey(), &committee_pair, policy.clone());
+    assert!(process(&mut banks_client, &payer, &[&payer, &impostor], &instructions).await.is_err());
+
+    // and must sign.
+    let mut unsigned = set_tick_policy_instructions(&payer.pubkey(), &admin.pubkey(), &committee_pair, policy.clone());
+    unsigned[1].accounts[5].is_signer = false;
+    assert!(process(&mut banks_client, &payer, &[&payer], &unsigned).await.is_err());
+
+    let instructions = set_tick_policy_instructions(&payer.pubkey(), &admin.pubkey(), &committee_pair, policy.clone());
+    process(&mut banks_client, &payer, &[&payer, &admin], &instructions).await.unwrap();
+    let stored: TickPolicy = query_data(&mut banks_client, find_tick_policy_address(&program_id, &policy.tick).0).await;
+    assert_eq!(policy, stored);
+}
+
+// `GetAmount` of `key`, read from the simulation's return data.
+pub async fn query_amount(banks_client: &mut BanksClient, payer: &Keypair, key: Brc20Key) -> AmountView {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(find_asset_address(&program_id, &key).0, false),
+            AccountMeta::new_readonly(find_tick_policy_address(&program_id, &key.tick).0, false),
+        ],
+        data: Brc20OracleInstruction::GetAmount(key).try_to_vec().unwrap(),
+    };
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    transaction.sign(&[payer], banks_client.get_latest_blockhash().await.unwrap());
+    let simulation = banks_client.simulate_transaction(transaction).await.unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    AmountView::try_from_slice(&return_data.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_tick_policy_staleness() {
+    let admin = Keypair::new();
+    let mut context = init_program_test_with_admin(&admin.pubkey()).start_with_context().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut context.banks_client, &context.payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let payer = context.payer.pubkey();
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    process_query(&mut context.banks_client, &context.payer, key.clone(), None).await.unwrap();
+    process_insert(&mut context.banks_client, &context.payer, &committee_pair, committee_info, key.clone(), Brc20Amount(1000)).await;
+
+    // the admin can't set a policy the committee didn't sign.
+    let policy = TickPolicy { tick: key.tick, max_age: 100, nonce: 1 };
+    let instructions = set_tick_policy_instructions(&payer, &admin.pubkey(), &Keypair::new(), policy.clone());
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer, &admin], &instructions).await.is_err());
+
+    let instructions = set_tick_policy_instructions(&payer, &admin.pubkey(), &committee_pair, policy.clone());
+    process(&mut context.banks_client, &context.payer, &[&context.payer, &admin], &instructions).await.unwrap();
+    // nor one that doesn't move the nonce.
+    let loosened = TickPolicy { max_age: 1_000_000, ..policy.clone() };
+    let instructions = set_tick_policy_instructions(&payer, &admin.pubkey(), &committee_pair, loosened);
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer, &admin], &instructions).await.is_err());
+
+    let fresh = query_amount(&mut context.banks_client, &context.payer, key.clone()).await;
+    assert_eq!(AmountStatus::Fresh, fresh.status);
+    assert_eq!(Brc20Amount(1000), fresh.amount);
+
+    context.warp_to_slot(fresh.attested_slot + policy.max_age + 1).unwrap();
+    let stale = query_amount(&mut context.banks_client, &context.payer, key).await;
+    assert_eq!(AmountStatus::Stale, stale.status);
+    assert_eq!(fresh.amount, stale.amount);
+}
+
+pub fn heartbeat_instructions(committee: &Keypair, heartbeat: Heartbeat) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let accounts = vec![
+        AccountMeta::new(find_committee_address(&program_id).0, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    let sign_msg = signing_message_for_heartbeat(&heartbeat).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::Heartbeat(heartbeat, signature).try_to_vec().unwrap();
+    vec![verify_instruction, Instruction { program_id, accounts, data }]
+}
+
+#[tokio::test]
+async fn test_heartbeat() {
+    let mut context = init_program_test().start_with_context().await;
+    let committee_pair = Keypair::new();
+    let committee_info = process_init_committee(&mut context.banks_client, &context.payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let genesis: Committee = query_data(&mut context.banks_client, committee_info).await;
+    let start = genesis.last_heartbeat_slot;
+    context.warp_to_slot(start + 50).unwrap();
+
+    // a slot that isn't newer than the last heartbeat, or that hasn't happened yet, is stale.
+    for slot in [start, start + 60] {
+        let instructions = heartbeat_instructions(&committee_pair, Heartbeat { id: 0, slot });
+        assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &instructions).await.is_err());
+    }
+    // so is a heartbeat of another committee id or signed by another key.
+    let instructions = heartbeat_instructions(&committee_pair, Heartbeat { id: 1, slot: start + 40 });
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &instructions).await.is_err());
+    let instructions = heartbeat_instructions(&Keypair::new(), Heartbeat { id: 0, slot: start + 40 });
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &instructions).await.is_err());
+
+    let heartbeat = heartbeat_instructions(&committee_pair, Heartbeat { id: 0, slot: start + 40 });
+    process(&mut context.banks_client, &context.payer, &[&context.payer], &heartbeat).await.unwrap();
+    let committee: Committee = query_data(&mut context.banks_client, committee_info).await;
+    assert_eq!(Committee { last_heartbeat_slot: start + 40, ..genesis.clone() }, committee);
+
+    // replayed in a later block, and an older slot after a newer one.
+    context.warp_to_slot(start + 100).unwrap();
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &heartbeat).await.is_err());
+    let instructions = heartbeat_instructions(&committee_pair, Heartbeat { id: 0, slot: start + 30 });
+    assert!(process(&mut context.banks_client, &context.payer, &[&context.payer], &instructions).await.is_err());
+    let committee: Committee = query_data(&mut context.banks_client, committee_info).await;
+    assert_eq!(start + 40, committee.last_heartbeat_slot);
+}
+
+// bn254 generators, G2 in the EIP-197 order (x.c1, x.c0, y.c1, y.c0) the syscalls take.
+const G1: [u8; 64] = {
+    let mut point = [0u8; 64];
+    point[31] = 1;
+    point[63] = 2;
+    point
+};
+const G2: [u8; 128] = [
+    0x19, 0x8e, 0x93, 0x93, 0x92, 0x0d, 0x48, 0x3a, 0x72, 0x60, 0xbf, 0xb7, 0x31, 0xfb, 0x5d, 0x25,
+    0xf1, 0xaa, 0x49, 0x33, 0x35, 0xa9, 0xe7, 0x12, 0x97, 0xe4, 0x85, 0xb7, 0xae, 0xf3, 0x12, 0xc2,
+    0x18, 0x00, 0xde, 0xef, 0x12, 0x1f, 0x1e, 0x76, 0x42, 0x6a, 0x00, 0x66, 0x5e, 0x5c, 0x44, 0x79,
+    0x67, 0x43, 0x22, 0xd4, 0xf7, 0x5e, 0xda, 0xdd, 0x46, 0xde, 0xbd, 0x5c, 0xd9, 0x92, 0xf6, 0xed,
+    0x09, 0x06, 0x89, 0xd0, 0x58, 0x5f, 0xf0, 0x75, 0xec, 0x9e, 0x99, 0xad, 0x69, 0x0c, 0x33, 0x95,
+    0xbc, 0x4b, 0x31, 0x33, 0x70, 0xb3, 0x8e, 0xf3, 0x55, 0xac, 0xda, 0xdc, 0xd1, 0x22, 0x97, 0x5b,
+    0x12, 0xc8, 0x5e, 0xa5, 0xdb, 0x8c, 0x6d, 0xeb, 0x4a, 0xab, 0x71, 0x80, 0x8d, 0xcb, 0x40, 0x8f,
+    0xe3, 0xd1, 0xe7, 0x69, 0x0c, 0x43, 0xd3, 0x7b, 0x4c, 0xe6, 0xcc, 0x01, 0x66, 0xfa, 0x7d, 0xaa,
+];
+
+// generators everywhere, so a proof can be built from the public inputs alone.
+fn test_verifying_key() -> VerifyingKey {
+    VerifyingKey { alpha_g1: G1, beta_g2: G2, gamma_g2: G2, delta_g2: G2, ic: vec![G1; 3] }
+}
+
+pub fn set_verifying_key_instructions(payer: &Pubkey, committee: &Keypair, vk: VerifyingKey) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(find_committee_address(&program_id).0, false),
+        AccountMeta::new(find_verifying_key_address(&program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    let sign_msg = signing_message_for_verifying_key(&vk).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = committee.sign_message(&sign_msg).as_ref().to_vec();
+    let data = Brc20OracleInstruction::SetVerifyingKey(vk, signature).try_to_vec().unwrap();
+    vec![verify_instruction, Instruction { program_id, accounts, data }]
+}
+
+pub fn insert_with_proof_instruction(payer: &Pubkey, key: Brc20Key, amount: Brc20Amount, proof: Groth16Proof) -> Instruction {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let accounts = vec![
+        AccountMeta::new_readonly(find_verifying_key_address(&program_id).0, false),
+        AccountMeta::new(find_asset_address(&program_id, &key).0, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_head_address(&program_id, &key.tick, &key.owner).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(find_stats_address(&program_id).0, false),
+    ];
+    let data = Brc20OracleInstruction::InsertWithProof(key, amount, proof).try_to_vec().unwrap();
+    Instruction { program_id, accounts, data }
+}
+
+#[cfg(not(feature = "no-alt-bn128"))]
+#[tokio::test]
+async fn test_insert_with_proof() {
+    use solana_program::alt_bn128::prelude::{alt_bn128_addition, alt_bn128_multiplication};
+    // bn254 group order minus one, multiplying by it negates a point.
+    const R_MINUS_ONE: [u8; 32] = [
+        0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+        0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x00,
+    ];
+    let add = |p: &[u8], q: &[u8]| -> [u8; 64] { alt_bn128_addition(&[p, q].concat()).unwrap().try_into().unwrap() };
+    let mul = |p: &[u8], s: &[u8]| -> [u8; 64] { alt_bn128_multiplication(&[p, s].concat()).unwrap().try_into().unwrap() };
+
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let vk = test_verifying_key();
+    let instructions = set_verifying_key_instructions(&payer.pubkey(), &committee_pair, vk.clone());
+    process(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    let asset_address = process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    let request: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    let filled = Brc20Asset { set: true, amount: Brc20Amount(1000), ..request };
+
+    // with every point a generator, e(A, G2) e(G1, G2) e(vk_x, G2) e(0, G2) == 1 for A = -(G1 + vk_x).
+    let inputs = crate::groth16::asset_public_inputs(&asset_address, &filled).unwrap();
+    let mut vk_x = vk.ic[0];
+    for (input, ic) in inputs.iter().zip(&vk.ic[1..]) {
+        vk_x = add(&mul(ic, input), &vk_x);
+    }
+    let proof = Groth16Proof { a: mul(&add(&G1, &vk_x), &R_MINUS_ONE), b: G2, c: [0; 64] };
+
+    let invalid = Groth16Proof { a: G1, ..proof.clone() };
+    let instruction = insert_with_proof_instruction(&payer.pubkey(), key.clone(), Brc20Amount(1000), invalid);
+    assert!(process(&mut banks_client, &payer, &[&payer], &[instruction]).await.is_err());
+    // the proof is bound to the amount.
+    let instruction = insert_with_proof_instruction(&payer.pubkey(), key.clone(), Brc20Amount(1001), proof.clone());
+    assert!(process(&mut banks_client, &payer, &[&payer], &[instruction]).await.is_err());
+
+    let instruction = insert_with_proof_instruction(&payer.pubkey(), key.clone(), Brc20Amount(1000), proof);
+    process(&mut banks_client, &payer, &[&payer], &[instruction]).await.unwrap();
+    let inserted: Brc20Asset = query_data(&mut banks_client, asset_address).await;
+    assert_eq!(Brc20Asset { attested_slot: inserted.attested_slot, ..filled }, inserted);
+    // head and stats move like for a committee insert.
+    let head: AssetHead = query_data(&mut banks_client, find_head_address(&program_id, &key.tick, &key.owner).0).await;
+    assert_eq!(AssetHead { height: key.height, asset: asset_address }, head);
+    let stats: OracleStats = query_data(&mut banks_client, find_stats_address(&program_id).0).await;
+    assert_eq!(1, stats.total_inserts);
+}
+
+#[cfg(feature = "no-alt-bn128")]
+#[tokio::test]
+async fn test_proofs_unsupported() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let instructions = set_verifying_key_instructions(&payer.pubkey(), &committee_pair, test_verifying_key());
+    assert!(process(&mut banks_client, &payer, &[&payer], &instructions).await.is_err());
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+    process_query(&mut banks_client, &payer, key.clone(), None).await.unwrap();
+    let proof = Groth16Proof { a: G1, b: G2, c: G1 };
+    let instruction = insert_with_proof_instruction(&payer.pubkey(), key, Brc20Amount(1000), proof);
+    assert!(process(&mut banks_client, &payer, &[&payer], &[instruction]).await.is_err());
+}
+
+// `signer` stands in for the committee, pass another key to get a bad signature.
+pub fn sat_range_instructions(payer: &Pubkey, signer: &Keypair, attestation: SatRangeAttestation) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(find_committee_address(&program_id).0, false),
+        AccountMeta::new(find_sat_range_address(&program_id, &attestation.key).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    let sign_msg = signing_message_for_sat_range(&attestation).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = signer.sign_message(&sign_msg).as_ref().to_vec();
+    let header = typed_header(MessageKind::SatRange, attestation.try_to_vec().unwrap().len());
+    let data = Brc20OracleInstruction::AttestSatRange(header, attestation, signature).try_to_vec().unwrap();
+    vec![verify_instruction, Instruction { program_id, accounts, data }]
+}
+
+#[tokio::test]
+async fn test_attest_sat_range() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let inscription = InscriptionId { txid: [3; 32], index: 0 };
+    let attestation = SatRangeAttestation {
+        key: SatRangeKey { height: 10, start: 100, end: 200 },
+        inscriptions: vec![inscription.clone()],
+    };
+
+    // inverted and empty ranges.
+    for (start, end) in [(200, 100), (100, 100)] {
+        let inverted = SatRangeAttestation { key: SatRangeKey { height: 10, start, end }, ..attestation.clone() };
+        let instructions = sat_range_instructions(&payer.pubkey(), &committee_pair, inverted);
+        assert!(process(&mut banks_client, &payer, &[&payer], &instructions).await.is_err());
+    }
+    let instructions = sat_range_instructions(&payer.pubkey(), &Keypair::new(), attestation.clone());
+    assert!(process(&mut banks_client, &payer, &[&payer], &instructions).await.is_err());
+
+    let instructions = sat_range_instructions(&payer.pubkey(), &committee_pair, attestation.clone());
+    process(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+    let sat_range_address = find_sat_range_address(&program_id, &attestation.key).0;
+    let stored: SatRangeAttestation = query_data(&mut banks_client, sat_range_address).await;
+    assert_eq!(attestation, stored);
+
+    // a second attestation over the same range can't replace the first.
+    let conflicting = SatRangeAttestation { inscriptions: vec![], ..attestation.clone() };
+    let instructions = sat_range_instructions(&payer.pubkey(), &committee_pair, conflicting);
+    assert!(process(&mut banks_client, &payer, &[&payer], &instructions).await.is_err());
+    let stored: SatRangeAttestation = query_data(&mut banks_client, sat_range_address).await;
+    assert_eq!(vec![inscription], stored.inscriptions);
+}
+
+pub fn height_range_instructions(payer: &Pubkey, signer: &Keypair, attestation: HeightRangeAttestation) -> Vec<Instruction> {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(find_committee_address(&program_id).0, false),
+        AccountMeta::new(find_height_range_address(&program_id, &attestation.key).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    let sign_msg = signing_message_for_height_range(&attestation).unwrap();
+    let verify_instruction = new_ed25519_instruction(
+        &ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap(),
+        &sign_msg,
+    );
+    let signature = signer.sign_message(&sign_msg).as_ref().to_vec();
+    let header = typed_header(MessageKind::HeightRange, attestation.try_to_vec().unwrap().len());
+    let data = Brc20OracleInstruction::AttestHeightRange(header, attestation, signature).try_to_vec().unwrap();
+    vec![verify_instruction, Instruction { program_id, accounts, data }]
+}
+
+#[tokio::test]
+async fn test_attest_height_range() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let key = HeightRangeKey { tick: *b"ordi", owner: "12345".to_string(), from: 10, to: 20 };
+    let attestation = HeightRangeAttestation { key: key.clone(), amount: Brc20Amount(1000) };
+
+    let inverted = HeightRangeAttestation { key: HeightRangeKey { from: 20, to: 10, ..key.clone() }, ..attestation.clone() };
+    let instructions = height_range_instructions(&payer.pubkey(), &committee_pair, inverted);
+    assert!(process(&mut banks_client, &payer, &[&payer], &instructions).await.is_err());
+    let instructions = height_range_instructions(&payer.pubkey(), &Keypair::new(), attestation.clone());
+    assert!(process(&mut banks_client, &payer, &[&payer], &instructions).await.is_err());
+
+    let instructions = height_range_instructions(&payer.pubkey(), &committee_pair, attestation.clone());
+    process(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+    let height_range_address = find_height_range_address(&program_id, &key).0;
+    let stored: HeightRangeAttestation = query_data(&mut banks_client, height_range_address).await;
+    assert_eq!(attestation, stored);
+    assert!(stored.covers(10) && stored.covers(20) && !stored.covers(21));
+
+    // a single height is a valid range.
+    let single = HeightRangeAttestation { key: HeightRangeKey { from: 21, to: 21, ..key.clone() }, ..attestation.clone() };
+    let instructions = height_range_instructions(&payer.pubkey(), &committee_pair, single);
+    process(&mut banks_client, &payer, &[&payer], &instructions).await.unwrap();
+
+    // the same range attested again with another amount can't replace the first.
+    let conflicting = HeightRangeAttestation { amount: Brc20Amount(2000), ..attestation.clone() };
+    let instructions = height_range_instructions(&payer.pubkey(), &committee_pair, conflicting);
+    assert!(process(&mut banks_client, &payer, &[&payer], &instructions).await.is_err());
+    let stored: HeightRangeAttestation = query_data(&mut banks_client, height_range_address).await;
+    assert_eq!(Brc20Amount(1000), stored.amount);
+}
+
+#[tokio::test]
+async fn test_head_out_of_order() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    let committee_info_address =
+        process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let newer = Brc20Key { version: BRC20_KEY_VERSION, height: 20, tick: *b"ordi", owner: "12345".to_string() };
+    let older = Brc20Key { height: 10, ..newer.clone() };
+    let (head_address, _) = find_head_address(&program_id, &newer.tick, &newer.owner);
+
+    process_query(&mut banks_client, &payer, newer.clone(), None).await.unwrap();
+    process_query(&mut banks_client, &payer, older.clone(), None).await.unwrap();
+    let newer_address =
+        process_insert(&mut banks_client, &payer, &committee_pair, committee_info_address, newer.clone(), Brc20Amount(2000)).await;
+    let older_address =
+        process_insert(&mut banks_client, &payer, &committee_pair, committee_info_address, older, Brc20Amount(1000)).await;
+
+    // the older insert lands but doesn't move the head back.
+    let older_asset: Brc20Asset = query_data(&mut banks_client, older_address).await;
+    assert!(older_asset.set);
+    let head: AssetHead = query_data(&mut banks_client, head_address).await;
+    assert_eq!(AssetHead { height: newer.height, asset: newer_address }, head);
+}
+
+#[tokio::test]
+async fn test_index_page_rollover() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+
+    // one past a full page, the last request starts page 1.
+    let mut asset_addresses = vec![];
+    for height in 0..=INDEX_PAGE_CAPACITY as u32 {
+        let key = Brc20Key { version: BRC20_KEY_VERSION, height, tick: *b"ordi", owner: "12345".to_string() };
+        asset_addresses.push(process_query(&mut banks_client, &payer, key, None).await.unwrap());
+    }
+
+    let first: IndexPage = query_data(&mut banks_client, find_index_page_address(&program_id, 0).0).await;
+    assert_eq!(0, first.page);
+    assert_eq!(INDEX_PAGE_CAPACITY as usize, first.entries.len());
+    assert_eq!(find_index_page_address(&program_id, 1).0, first.next);
+    let second: IndexPage = query_data(&mut banks_client, first.next).await;
+    assert_eq!(1, second.page);
+    assert_eq!(1, second.entries.len());
+    assert_eq!(INDEX_PAGE_CAPACITY, second.entries[0].uid);
+    assert_eq!(asset_addresses[INDEX_PAGE_CAPACITY as usize], second.entries[0].asset);
+    assert_eq!(find_index_page_address(&program_id, 2).0, second.next);
+    let uids: Vec<u64> = first.entries.iter().chain(&second.entries).map(|entry| entry.uid).collect();
+    assert_eq!((0..=INDEX_PAGE_CAPACITY).collect::<Vec<_>>(), uids);
+
+    // the next uid belongs on page 1, page 0 is refused.
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1000, tick: *b"ordi", owner: "12345".to_string() };
+    let mut instructions = query_instructions(&mut banks_client, &payer, key, None).await;
+    instructions[0].accounts[6].pubkey = find_index_page_address(&program_id, 0).0;
+    assert!(process(&mut banks_client, &payer, &[&payer], &instructions).await.is_err());
+}
+
+#[cfg(feature = "mainnet-strict")]
+#[tokio::test]
+async fn test_mainnet_strict() {
+    let (mut banks_client, payer) = init_client().await;
+    let committee_pair = Keypair::new();
+    process_init_committee(&mut banks_client, &payer, &committee_pair, &committee_pair.pubkey(), 0).await;
+
+    // neither a testnet committee nor a testnet key gets in.
+    let testnet_pair = Keypair::new();
+    assert!(try_init_network_committee(&mut banks_client, &payer, BitcoinNetwork::Testnet, &testnet_pair, &testnet_pair.pubkey(), 0)
+        .await
+        .is_err());
+    for owner in ["tb1qtestowner", "bcrt1qtestowner", "mtestowner", "2testowner"] {
+        let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: owner.to_string() };
+        assert_eq!(BitcoinNetwork::Testnet, key.network());
+        assert!(process_query(&mut banks_client, &payer, key, None).await.is_err());
+    }
+
+    let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "bc1qmainowner".to_string() };
+    process_query(&mut banks_client, &payer, key, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stored_bump_checked() {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (committee_address, bump) = find_committee_address(&program_id);
+    // another bump that still derives a valid PDA, just not this one.
+    let wrong_bump = (0..bump)
+        .rev()
+        .find(|&wrong| create_network_committee_address(&program_id, BitcoinNetwork::Mainnet, wrong).is_ok())
+        .unwrap();
+    assert_ne!(committee_address, create_network_committee_address(&program_id, BitcoinNetwork::Mainnet, wrong_bump).unwrap());
+
+    let committee_pair = Keypair::new();
+    let attestation = SatRangeAttestation { key: SatRangeKey { height: 10, start: 100, end: 200 }, inscriptions: vec![] };
+    for (stored_bump, accepted) in [(wrong_bump, false), (bump, true)] {
+        let committee = Committee { id: 0, address: committee_pair.pubkey(), uid: 0, last_heartbeat_slot: 0, bump: stored_bump };
+        let mut program_test = init_program_test();
+        program_test.add_account(committee_address, legacy_account(committee.try_to_vec().unwrap()));
+        let (mut banks_client, payer, _) = program_test.start().await;
+        let instructions = sat_range_instructions(&payer.pubkey(), &committee_pair, attestation.clone());
+        assert_eq!(accepted, process(&mut banks_client, &payer, &[&payer], &instructions).await.is_ok());
+    }
 }
@@ -4,8 +4,48 @@ use solana_program::pubkey::Pubkey;
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
 pub enum Brc20OracleInstruction {
     SetCommittee(Committee, Vec<u8>),
-    Request(Brc20Key),
-    Insert(Brc20Key, u128, Vec<u8>),
+    // key and optional ttl in slots after which an unfilled request can be expired.
+    Request(Brc20Key, Option<u64>),
+    Insert(Brc20Key, Brc20Amount, Option<Brc20Amount>, AttestationSource, Vec<u8>),
+    PublishSnapshot(MessageHeader, Snapshot, Vec<u8>),
+    MaterializeEntry(Brc20Key, Brc20Amount, Vec<[u8; 32]>),
+    SetVerifyingKey(VerifyingKey, Vec<u8>),
+    InsertWithProof(Brc20Key, Brc20Amount, Groth16Proof),
+    Expire(Brc20Key),
+    Heartbeat(Heartbeat, Vec<u8>),
+    SetTickPolicy(TickPolicy, Vec<u8>),
+    // view, sets an `AmountView` as return data.
+    GetAmount(Brc20Key),
+    AttestSatRange(MessageHeader, SatRangeAttestation, Vec<u8>),
+    CloseRequest(Brc20Key),
+    InsertAddressEntry(Brc20Key, Brc20Amount, Vec<u8>),
+    Reject(Brc20Key, RejectReason, Vec<u8>),
+    AttestHeightRange(MessageHeader, HeightRangeAttestation, Vec<u8>),
+    InsertDelta(Brc20Key, AmountDelta, Vec<u8>),
+    GetVersion,
+    // key, attested_slot of the attestation being revoked, reason, signature.
+    Revoke(Brc20Key, u64, RevokeReason, Vec<u8>),
+    RegisterConsumer(ConsumerTarget),
+    SetNetworkCommittee(BitcoinNetwork, Committee, Vec<u8>),
+    // network, message, signature. Checks the signature like any committee-signed instruction, changes nothing.
+    VerifyCommitteeSignature(BitcoinNetwork, Vec<u8>, Vec<u8>),
+    // `blind_key` of the key, the salt goes to the committee off chain.
+    RequestBlinded([u8; 32]),
+    InsertBlinded([u8; 32], Brc20Amount, Vec<u8>),
+    // rewrites the committee (None) or the asset of a key from a legacy layout, see `decode_any_version`.
+    Migrate(Option<Brc20Key>),
+    // key, attested_slot being refreshed, signature. Moves attested_slot of an unchanged asset, nothing else.
+    Refresh(Brc20Key, u64, Vec<u8>),
+}
+
+// `message::typed_message` header of the signed value following it in the instruction, so the whole
+// signed message is one slice of the instruction data a verify instruction can point at.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MessageHeader {
+    pub kind: u8,
+    // always 0, where bound messages put the account length.
+    pub account_len: u8,
+    pub body_len: u32,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
@@ -15,15 +55,200 @@ pub struct Committee {
     pub address: Pubkey,
     // counter for requests(assets)
     pub uid: u64,
+    // slot signed in the latest heartbeat, see `is_committee_alive`.
+    pub last_heartbeat_slot: u64,
+    // bump of the committee PDA, set by the program.
+    pub bump: u8,
+}
+
+// Committee layout before `last_heartbeat_slot` and `bump` were added.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct LegacyCommittee {
+    pub id: u8,
+    pub address: Pubkey,
+    pub uid: u64,
 }
 
+// Committee layout before `bump` was added.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct LegacyHeartbeatCommittee {
+    pub id: u8,
+    pub address: Pubkey,
+    pub uid: u64,
+    pub last_heartbeat_slot: u64,
+}
+
+impl Committee {
+    /// Decodes a committee account written with any known layout. Legacy layouts have no bump and
+    /// decode with `bump` 0, the program sets it when migrating the account.
+    pub fn decode_any_version(data: &[u8]) -> std::io::Result<Committee> {
+        if let Ok(committee) = Committee::try_from_slice(data) {
+            return Ok(committee);
+        }
+        if let Ok(committee) = LegacyHeartbeatCommittee::try_from_slice(data) {
+            return Ok(Committee {
+                id: committee.id,
+                address: committee.address,
+                uid: committee.uid,
+                last_heartbeat_slot: committee.last_heartbeat_slot,
+                bump: 0,
+            });
+        }
+        LegacyCommittee::try_from_slice(data).map(|committee| Committee {
+            id: committee.id,
+            address: committee.address,
+            uid: committee.uid,
+            last_heartbeat_slot: 0,
+            bump: 0,
+        })
+    }
+}
+
+// bitcoin network a committee attests for, see `Brc20Key::network`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BitcoinNetwork {
+    Mainnet,
+    // testnet, signet and regtest.
+    Testnet,
+}
+
+// reliability counters kept by the program so integrators don't have to take uptime on trust.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct OracleStats {
+    pub total_inserts: u64,
+    // heartbeat intervals that passed without a heartbeat.
+    pub missed_heartbeats: u64,
+    // inserts by slots since the request, split at `LATENCY_BUCKET_BOUNDS`, the last one is everything above.
+    pub latency_buckets: [u64; 6],
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Heartbeat {
+    // id of the committee sending it.
+    pub id: u8,
+    pub slot: u64,
+}
+
+// Raw BRC-20 balance. Borsh encodes it exactly like the bare u128 it wraps.
+// u128::MAX is reserved as a sentinel and never a valid amount.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Brc20Amount(pub u128);
+
+impl Brc20Amount {
+    pub const ZERO: Brc20Amount = Brc20Amount(0);
+    pub const MAX: Brc20Amount = Brc20Amount(u128::MAX - 1);
+
+    pub fn new(value: u128) -> Option<Brc20Amount> {
+        let amount = Brc20Amount(value);
+        if amount.is_valid() {
+            Some(amount)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_valid(self) -> bool {
+        self <= Brc20Amount::MAX
+    }
+
+    pub fn checked_add(self, other: Brc20Amount) -> Option<Brc20Amount> {
+        self.0.checked_add(other.0).and_then(Brc20Amount::new)
+    }
+
+    pub fn checked_sub(self, other: Brc20Amount) -> Option<Brc20Amount> {
+        self.0.checked_sub(other.0).and_then(Brc20Amount::new)
+    }
+
+    pub fn checked_apply(self, change: i128) -> Option<Brc20Amount> {
+        if change >= 0 {
+            self.0.checked_add(change as u128).and_then(Brc20Amount::new)
+        } else {
+            self.0.checked_sub(change.unsigned_abs()).and_then(Brc20Amount::new)
+        }
+    }
+
+    pub fn to_u64(self) -> Option<u64> {
+        u64::try_from(self.0).ok()
+    }
+}
+
+impl From<u64> for Brc20Amount {
+    fn from(value: u64) -> Self {
+        Brc20Amount(value as u128)
+    }
+}
+
+pub const BRC20_KEY_VERSION: u8 = 1;
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
 pub struct Brc20Key {
+    // layout version, part of the hashed PDA seed so a new key shape never aliases an old PDA.
+    pub version: u8,
+    pub height: u32,
+    pub tick: [u8; 4],
+    pub owner: String,
+}
+
+// Brc20Key layout before `version` was added, decoded as version 0.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct LegacyBrc20Key {
     pub height: u32,
     pub tick: [u8; 4],
     pub owner: String,
 }
 
+impl From<LegacyBrc20Key> for Brc20Key {
+    fn from(key: LegacyBrc20Key) -> Self {
+        Brc20Key { version: 0, height: key.height, tick: key.tick, owner: key.owner }
+    }
+}
+
+impl Brc20Key {
+    /// Decodes a key serialized with any known layout.
+    pub fn decode_any_version(data: &[u8]) -> std::io::Result<Brc20Key> {
+        match Brc20Key::try_from_slice(data) {
+            Ok(key) if key.version == BRC20_KEY_VERSION => Ok(key),
+            _ => LegacyBrc20Key::try_from_slice(data).map(Brc20Key::from),
+        }
+    }
+
+    /// The one encoding `Request` accepts for this key, so equal keys always map to the same PDA.
+    /// Ticks are case insensitive and lowercased. The owner is trimmed, and lowercased for bech32
+    /// addresses which are case insensitive too, base58 addresses keep their case.
+    pub fn canonicalize(&self) -> Brc20Key {
+        let owner = self.owner.trim();
+        let lower = owner.to_ascii_lowercase();
+        let owner = if BECH32_HRPS.iter().any(|hrp| lower.starts_with(hrp)) { lower } else { owner.to_string() };
+        Brc20Key {
+            version: self.version,
+            height: self.height,
+            tick: self.tick.map(|b| b.to_ascii_lowercase()),
+            owner,
+        }
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        *self == self.canonicalize()
+    }
+
+    /// Network of the owner address, which picks the committee attesting this key. Addresses
+    /// only tell mainnet and test networks apart, anything unrecognised is treated as mainnet.
+    pub fn network(&self) -> BitcoinNetwork {
+        let lower = self.owner.to_ascii_lowercase();
+        if TEST_BECH32_HRPS.iter().any(|hrp| lower.starts_with(hrp)) || self.owner.starts_with(TEST_BASE58_PREFIXES) {
+            BitcoinNetwork::Testnet
+        } else {
+            BitcoinNetwork::Mainnet
+        }
+    }
+}
+
+// human readable parts of mainnet, testnet/signet and regtest segwit addresses.
+const BECH32_HRPS: [&str; 3] = ["bc1", "tb1", "bcrt1"];
+const TEST_BECH32_HRPS: [&str; 2] = ["tb1", "bcrt1"];
+// leading characters of testnet p2pkh (m, n) and p2sh (2) addresses.
+const TEST_BASE58_PREFIXES: [char; 3] = ['m', 'n', '2'];
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
 pub struct Brc20Asset {
     // To filter this account easily by client, we set same prefix.
@@ -32,5 +257,288 @@ pub struct Brc20Asset {
     pub set: bool,
     pub uid: u64,
     pub key: Brc20Key,
+    pub amount: Brc20Amount,
+    // payer of the request, refunded when it expires.
+    pub requester: Pubkey,
+    // slot after which an unfilled request can be closed by anyone.
+    pub expire_slot: Option<u64>,
+    // slot the amount was written at, 0 until set. Not covered by the committee signature.
+    pub attested_slot: u64,
+    // set by the committee when the request can never be answered.
+    pub rejected: Option<RejectReason>,
+    // committee signed uncertainty, the true balance is within amount +/- confidence. None when exact.
+    pub confidence: Option<Brc20Amount>,
+    // indexer implementation and ruleset version that produced the amount.
+    pub source: AttestationSource,
+    // set by the committee when the attested amount turned out wrong, the amount is kept for the record.
+    pub revoked: Option<RevokeReason>,
+    // bump of the asset PDA, see `create_asset_address`.
+    pub bump: u8,
+}
+
+// Brc20Asset layout before requesters, expiry and the attestation metadata were added. Its key is
+// a `LegacyBrc20Key`, the asset lives at the address of that key decoded as version 0.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct LegacyBrc20Asset {
+    pub prefix: [u8; 5],
+    pub set: bool,
+    pub uid: u64,
+    pub key: LegacyBrc20Key,
     pub amount: u128,
 }
+
+impl Brc20Asset {
+    /// Decodes an asset account written with any known layout. Fields a legacy layout lacks are
+    /// left empty: no requester, no expiry, attested at slot 0 and `bump` 0 until migrated.
+    pub fn decode_any_version(data: &[u8]) -> std::io::Result<Brc20Asset> {
+        if let Ok(asset) = Brc20Asset::try_from_slice(data) {
+            return Ok(asset);
+        }
+        LegacyBrc20Asset::try_from_slice(data).map(|asset| Brc20Asset {
+            prefix: asset.prefix,
+            set: asset.set,
+            uid: asset.uid,
+            key: asset.key.into(),
+            amount: Brc20Amount(asset.amount),
+            requester: Pubkey::default(),
+            expire_slot: None,
+            attested_slot: 0,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        })
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RejectReason {
+    InvalidTick,
+    UnsupportedAddress,
+    HeightTooOld,
+    Other(u8),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RevokeReason {
+    IndexerBug,
+    Reorg,
+    Other(u8),
+}
+
+// latest inserted height of one (tick, owner), so readers don't need to know which heights were requested.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct AssetHead {
+    pub height: u32,
+    // the Brc20Asset PDA at `height`.
+    pub asset: Pubkey,
+}
+
+// request made by `blind_key` only, readers who know the key and salt recompute the address.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct BlindedAsset {
+    pub blinded: [u8; 32],
+    pub set: bool,
+    pub amount: Brc20Amount,
+    pub requester: Pubkey,
+    // slot the amount was written at, 0 until set. Not covered by the committee signature.
+    pub attested_slot: u64,
+}
+
+// one per (asset, requester), lets several requesters wait on the same asset.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct RequestRecord {
+    pub asset: Pubkey,
+    pub requester: Pubkey,
+    pub created_slot: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct TickPolicy {
+    pub tick: [u8; 4],
+    // attestations older than this many slots are reported as stale.
+    pub max_age: u64,
+    // must increase on every update so an older policy can't be replayed.
+    pub nonce: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AmountStatus {
+    NotSet,
+    Fresh,
+    Stale,
+    Revoked,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct AmountView {
+    pub status: AmountStatus,
+    pub amount: Brc20Amount,
+    pub attested_slot: u64,
+    pub confidence: Option<Brc20Amount>,
+    pub source: AttestationSource,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Snapshot {
+    pub height: u32,
+    pub tick: [u8; 4],
+    // merkle root over every (key, amount) entry of this tick at this height.
+    pub root: [u8; 32],
+}
+
+// Groth16 verifying key over bn254, points encoded as expected by the alt_bn128 syscalls.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct VerifyingKey {
+    pub alpha_g1: [u8; 64],
+    pub beta_g2: [u8; 128],
+    pub gamma_g2: [u8; 128],
+    pub delta_g2: [u8; 128],
+    // one point per public input plus the constant term.
+    pub ic: Vec<[u8; 64]>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Groth16Proof {
+    // proof.a must already be negated by the prover.
+    pub a: [u8; 64],
+    pub b: [u8; 128],
+    pub c: [u8; 64],
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct SatRangeKey {
+    pub height: u32,
+    // sat range [start, end), a single sat is [sat, sat + 1).
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct InscriptionId {
+    pub txid: [u8; 32],
+    pub index: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct SatRangeAttestation {
+    pub key: SatRangeKey,
+    // inscriptions carried by sats in the range at `key.height`.
+    pub inscriptions: Vec<InscriptionId>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AddressEntry {
+    pub tick: [u8; 4],
+    pub height: u32,
+    pub amount: Brc20Amount,
+    pub attested_slot: u64,
+}
+
+// every tick of one owner in a single account, sorted by tick. Cheaper than one Brc20Asset per tick.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct AddressAssetMap {
+    pub owner: String,
+    pub entries: Vec<AddressEntry>,
+}
+
+impl AddressAssetMap {
+    pub fn get(&self, tick: &[u8; 4]) -> Option<&AddressEntry> {
+        self.entries
+            .binary_search_by(|e| e.tick.cmp(tick))
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct HeightRangeKey {
+    pub tick: [u8; 4],
+    pub owner: String,
+    // inclusive height range [from, to].
+    pub from: u32,
+    pub to: u32,
+}
+
+// one amount for every height in the range, the balance didn't change across it.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct HeightRangeAttestation {
+    pub key: HeightRangeKey,
+    pub amount: Brc20Amount,
+}
+
+impl HeightRangeAttestation {
+    pub fn covers(&self, height: u32) -> bool {
+        self.key.from <= height && height <= self.key.to
+    }
+}
+
+// change of a balance relative to the same tick and owner at an earlier, already filled height.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AmountDelta {
+    pub base_height: u32,
+    pub amount_change: i128,
+}
+
+// identifies which indexer implementation and ruleset version produced an amount, so consumers can
+// distrust amounts from an indexer version found to be faulty. zero means unspecified.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AttestationSource {
+    pub indexer_id: u8,
+    pub source_version: u8,
+}
+
+// code revision of the running program, written to the version account by `GetVersion`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ProgramVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    // hex git commit hash as ascii, zero filled when unknown.
+    pub git_hash: [u8; 40],
+}
+
+// per tick bookkeeping updated by `Request` when given, lets crank shards partition work by tick and
+// skip ticks that saw no new request since their last cycle.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct TickRegistry {
+    pub tick: [u8; 4],
+    // assets ever requested for the tick, expired ones included.
+    pub count: u64,
+    pub last_uid: u64,
+    // the most recently requested asset, default once `Expire` closed it.
+    pub head: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct IndexEntry {
+    pub uid: u64,
+    pub asset: Pubkey,
+}
+
+// one page of the asset index, page n holds the assets requested with uid in [n * INDEX_PAGE_CAPACITY, (n + 1) * INDEX_PAGE_CAPACITY).
+// lets light clients enumerate assets without getProgramAccounts.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct IndexPage {
+    pub page: u64,
+    // address of page + 1, which may not exist yet.
+    pub next: Pubkey,
+    pub entries: Vec<IndexEntry>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub enum ConsumerTarget {
+    Tick([u8; 4]),
+    Key(Brc20Key),
+}
+
+// opt-in record of a program or user reading a feed, the committee keeps the most watched ones fresh.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ConsumerRecord {
+    pub prefix: [u8; 8],
+    // kept right after the prefix so records of one target can be found with a memcmp filter.
+    pub target: ConsumerTarget,
+    pub consumer: Pubkey,
+    pub registered_slot: u64,
+}
@@ -0,0 +1,131 @@
+//! Reference consumer of the oracle, not meant to be deployed as is. `CheckBalance` only succeeds
+//! while a key holds at least a minimum amount according to an attestation this program considers
+//! fresh, and returns the amount it relied on. Anything else reverts.
+
+#[cfg(test)]
+pub mod test;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::cpi::get_amount;
+use brc20_oracle::read_amount;
+use brc20_oracle::types::{AmountStatus, AmountView, Brc20Amount, Brc20Key};
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::clock::Clock;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program::set_return_data;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+use solana_program::{msg, pubkey};
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+// the oracle deployment this program trusts, here the id the program-tests load it at. Taking the
+// oracle from the accounts passed in would accept an amount from any program returning the same layout.
+pub const ORACLE_PROGRAM_ID: Pubkey = pubkey!("1111111QLbz7JHiBTspS962RLKV8GndWFwiEaqKM");
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Freshness {
+    // the oracle's tick policy decides, stale amounts revert.
+    TickPolicy,
+    // fall back to any amount attested within this many slots, whatever the tick policy says.
+    MaxAge(u64),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub enum ConsumerInstruction {
+    // oracle program, asset, tick policy. Asks the oracle with `GetAmount`.
+    CheckBalance(Brc20Key, Brc20Amount, Freshness),
+    // asset, tick policy. Reads the oracle accounts in place, cheaper than the CPI.
+    CheckBalanceInPlace(Brc20Key, Brc20Amount, Freshness),
+}
+
+#[derive(Clone, Copy, Debug, Eq, thiserror::Error, PartialEq)]
+pub enum ConsumerError {
+    #[error("Not the oracle this program trusts")]
+    UntrustedOracle,
+    #[error("Balance not attested")]
+    NotAttested,
+    #[error("Attestation revoked")]
+    Revoked,
+    #[error("Attestation too old")]
+    Stale,
+    #[error("Balance below the minimum")]
+    InsufficientBalance,
+}
+
+impl From<ConsumerError> for ProgramError {
+    fn from(e: ConsumerError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let instruction = ConsumerInstruction::try_from_slice(instruction_data)?;
+    match instruction {
+        ConsumerInstruction::CheckBalance(key, min_amount, freshness) => {
+            check_balance(program_id, accounts, key, min_amount, freshness)
+        }
+        ConsumerInstruction::CheckBalanceInPlace(key, min_amount, freshness) => {
+            check_balance_in_place(program_id, accounts, key, min_amount, freshness)
+        }
+    }
+}
+
+pub fn check_balance(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    min_amount: Brc20Amount,
+    freshness: Freshness,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let oracle_program = next_account_info(account_info_iter)?;
+    let asset_info = next_account_info(account_info_iter)?;
+    let policy_info = next_account_info(account_info_iter)?;
+
+    if oracle_program.key != &ORACLE_PROGRAM_ID {
+        return Err(ConsumerError::UntrustedOracle.into());
+    }
+    let view = get_amount(oracle_program, asset_info, policy_info, &key)?;
+    rely_on(&view, min_amount, freshness)
+}
+
+pub fn check_balance_in_place(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: Brc20Key,
+    min_amount: Brc20Amount,
+    freshness: Freshness,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let asset_info = next_account_info(account_info_iter)?;
+    let policy_info = next_account_info(account_info_iter)?;
+
+    // read_amount checks both addresses and ignores accounts the oracle doesn't own.
+    let view = read_amount(&ORACLE_PROGRAM_ID, asset_info, policy_info, &key, Clock::get()?.slot)?;
+    rely_on(&view, min_amount, freshness)
+}
+
+/// The amount `view` lets this program rely on at `current_slot`, or why it can't.
+pub fn usable_amount(view: &AmountView, freshness: Freshness, current_slot: u64) -> Result<Brc20Amount, ConsumerError> {
+    match (view.status, freshness) {
+        (AmountStatus::NotSet, _) => Err(ConsumerError::NotAttested),
+        (AmountStatus::Revoked, _) => Err(ConsumerError::Revoked),
+        (AmountStatus::Fresh, Freshness::TickPolicy) => Ok(view.amount),
+        (AmountStatus::Stale, Freshness::TickPolicy) => Err(ConsumerError::Stale),
+        (_, Freshness::MaxAge(max_age)) if current_slot.saturating_sub(view.attested_slot) <= max_age => Ok(view.amount),
+        (_, Freshness::MaxAge(_)) => Err(ConsumerError::Stale),
+    }
+}
+
+fn rely_on(view: &AmountView, min_amount: Brc20Amount, freshness: Freshness) -> ProgramResult {
+    let amount = usable_amount(view, freshness, Clock::get()?.slot)?;
+    if amount < min_amount {
+        return Err(ConsumerError::InsufficientBalance.into());
+    }
+    msg!("relying on {} attested at slot {}", amount.0, view.attested_slot);
+    set_return_data(&amount.try_to_vec()?);
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::pda::{find_asset_address, find_tick_policy_address};
+use brc20_oracle::types::{AttestationSource, Brc20Amount, Brc20Asset, Brc20Key, RevokeReason, TickPolicy, BRC20_KEY_VERSION};
+use brc20_oracle::ASSET_PREFIX;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::account::Account;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use crate::{ConsumerError, ConsumerInstruction, Freshness, ORACLE_PROGRAM_ID};
+
+const CONSUMER_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+// attested at slot 10, stale for the tick policy from slot 111 on.
+const ATTESTED_SLOT: u64 = 10;
+const MAX_AGE: u64 = 100;
+
+fn key(owner: &str) -> Brc20Key {
+    Brc20Key { version: BRC20_KEY_VERSION, height: 840_000, tick: *b"ordi", owner: owner.to_string() }
+}
+
+fn filled(key: Brc20Key, amount: u128, revoked: Option<RevokeReason>) -> Brc20Asset {
+    Brc20Asset {
+        prefix: ASSET_PREFIX,
+        set: true,
+        uid: 0,
+        bump: find_asset_address(&ORACLE_PROGRAM_ID, &key).1,
+        key,
+        amount: Brc20Amount(amount),
+        requester: Pubkey::new_unique(),
+        expire_slot: None,
+        attested_slot: ATTESTED_SLOT,
+        rejected: None,
+        confidence: None,
+        source: AttestationSource::default(),
+        revoked,
+    }
+}
+
+fn oracle_account(data: Vec<u8>) -> Account {
+    Account { lamports: 1_000_000_000, data, owner: ORACLE_PROGRAM_ID, executable: false, rent_epoch: 0 }
+}
+
+// both programs, with the given assets already filled by the committee and an `ordi` tick policy.
+fn init_program_test(assets: &[Brc20Asset]) -> ProgramTest {
+    let mut program_test = ProgramTest::default();
+    program_test.add_program("brc20_oracle", ORACLE_PROGRAM_ID, processor!(brc20_oracle::process_instruction));
+    program_test.add_program("example_consumer", CONSUMER_ID, processor!(crate::process_instruction));
+    for asset in assets {
+        let address = find_asset_address(&ORACLE_PROGRAM_ID, &asset.key).0;
+        program_test.add_account(address, oracle_account(asset.try_to_vec().unwrap()));
+    }
+    let policy = TickPolicy { tick: *b"ordi", max_age: MAX_AGE, nonce: 1 };
+    program_test.add_account(find_tick_policy_address(&ORACLE_PROGRAM_ID, b"ordi").0, oracle_account(policy.try_to_vec().unwrap()));
+    program_test
+}
+
+fn check_balance_ix(oracle: Pubkey, instruction: ConsumerInstruction) -> Instruction {
+    let key = match &instruction {
+        ConsumerInstruction::CheckBalance(key, _, _) | ConsumerInstruction::CheckBalanceInPlace(key, _, _) => key,
+    };
+    let mut accounts = vec![
+        AccountMeta::new_readonly(find_asset_address(&ORACLE_PROGRAM_ID, key).0, false),
+        AccountMeta::new_readonly(find_tick_policy_address(&ORACLE_PROGRAM_ID, &key.tick).0, false),
+    ];
+    if let ConsumerInstruction::CheckBalance(..) = instruction {
+        accounts.insert(0, AccountMeta::new_readonly(oracle, false));
+    }
+    Instruction { program_id: CONSUMER_ID, accounts, data: instruction.try_to_vec().unwrap() }
+}
+
+// the amount the consumer relied on, from its return data.
+async fn simulate(context: &mut ProgramTestContext, instruction: Instruction) -> Result<Brc20Amount, TransactionError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&context.payer.pubkey()), &[&context.payer], blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.result.unwrap()?;
+    // the runtime drops trailing zeros from recorded return data.
+    let mut data = simulation.simulation_details.unwrap().return_data.map(|data| data.data).unwrap_or_default();
+    data.resize(16, 0);
+    Ok(Brc20Amount::try_from_slice(&data).unwrap())
+}
+
+fn consumer_error(error: ConsumerError) -> Result<Brc20Amount, TransactionError> {
+    Err(TransactionError::InstructionError(0, InstructionError::Custom(error as u32)))
+}
+
+#[tokio::test]
+async fn test_check_balance_freshness() {
+    let mut context = init_program_test(&[filled(key("a"), 1000, None)]).start_with_context().await;
+    context.warp_to_slot(ATTESTED_SLOT + MAX_AGE).unwrap();
+
+    for in_place in [false, true] {
+        let check = |min: u128, freshness: Freshness| {
+            let instruction = match in_place {
+                true => ConsumerInstruction::CheckBalanceInPlace(key("a"), Brc20Amount(min), freshness),
+                false => ConsumerInstruction::CheckBalance(key("a"), Brc20Amount(min), freshness),
+            };
+            check_balance_ix(ORACLE_PROGRAM_ID, instruction)
+        };
+        assert_eq!(Ok(Brc20Amount(1000)), simulate(&mut context, check(1000, Freshness::TickPolicy)).await);
+        assert_eq!(consumer_error(ConsumerError::InsufficientBalance), simulate(&mut context, check(1001, Freshness::TickPolicy)).await);
+        // a consumer can be stricter than the tick policy.
+        assert_eq!(consumer_error(ConsumerError::Stale), simulate(&mut context, check(1, Freshness::MaxAge(MAX_AGE - 1))).await);
+    }
+
+    // stale for the tick policy, still usable by a consumer falling back to its own bound.
+    context.warp_to_slot(ATTESTED_SLOT + MAX_AGE + 1).unwrap();
+    let check = |freshness| check_balance_ix(ORACLE_PROGRAM_ID, ConsumerInstruction::CheckBalance(key("a"), Brc20Amount(1), freshness));
+    assert_eq!(consumer_error(ConsumerError::Stale), simulate(&mut context, check(Freshness::TickPolicy)).await);
+    assert_eq!(Ok(Brc20Amount(1000)), simulate(&mut context, check(Freshness::MaxAge(10 * MAX_AGE))).await);
+}
+
+#[tokio::test]
+async fn test_check_balance_unusable() {
+    let revoked = filled(key("b"), 1000, Some(RevokeReason::Reorg));
+    let mut context = init_program_test(&[filled(key("a"), 1000, None), revoked]).start_with_context().await;
+    context.warp_to_slot(ATTESTED_SLOT + 1).unwrap();
+
+    let check = |owner: &str| ConsumerInstruction::CheckBalance(key(owner), Brc20Amount(1), Freshness::TickPolicy);
+    assert_eq!(consumer_error(ConsumerError::Revoked), simulate(&mut context, check_balance_ix(ORACLE_PROGRAM_ID, check("b"))).await);
+    assert_eq!(consumer_error(ConsumerError::NotAttested), simulate(&mut context, check_balance_ix(ORACLE_PROGRAM_ID, check("c"))).await);
+
+    // another program answering GetAmount is never asked.
+    let untrusted = check_balance_ix(CONSUMER_ID, check("a"));
+    assert_eq!(consumer_error(ConsumerError::UntrustedOracle), simulate(&mut context, untrusted).await);
+}
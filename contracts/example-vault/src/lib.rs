@@ -0,0 +1,206 @@
+//! Reference vault minting a test SPL token against attested BRC-20 balances, not meant to be
+//! deployed as is. Each (tick, owner) is one position, bound to the Solana account minting first,
+//! which can mint up to `LOAN_TO_VALUE_PERCENT` of a fresh attested balance. Proving that this
+//! account controls the Bitcoin owner, and liquidating when the balance drops, are out of scope.
+
+#[cfg(test)]
+pub mod test;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::cpi::get_amount;
+use brc20_oracle::pda::owner_seed;
+use brc20_oracle::types::{AmountStatus, Brc20Amount, Brc20Key};
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program::{invoke, invoke_signed};
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::sysvar::Sysvar;
+use solana_program::{msg, pubkey, system_instruction};
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+// the oracle deployment this program trusts, here the id the program-tests load it at.
+pub const ORACLE_PROGRAM_ID: Pubkey = pubkey!("1111111QLbz7JHiBTspS962RLKV8GndWFwiEaqKM");
+
+pub const MINT_PREFIX: &[u8] = b"Mint";
+pub const AUTHORITY_PREFIX: &[u8] = b"Authority";
+pub const POSITION_PREFIX: &[u8] = b"Position";
+
+// share of the attested balance that can be minted, in raw units of both.
+pub const LOAN_TO_VALUE_PERCENT: u128 = 50;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub enum VaultInstruction {
+    // payer, mint, system program, token program. Creates the test token, minted by the vault only.
+    InitMint,
+    // owner, position, oracle program, asset, tick policy, mint, destination token account,
+    // mint authority, token program, system program.
+    Mint(Brc20Key, u64),
+}
+
+// one per (tick, owner), whatever height the collateral was attested at.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Position {
+    pub owner: Pubkey,
+    pub minted: u64,
+}
+
+impl Position {
+    pub const LEN: usize = 32 + 8;
+}
+
+#[derive(Clone, Copy, Debug, Eq, thiserror::Error, PartialEq)]
+pub enum VaultError {
+    #[error("Not the oracle this program trusts")]
+    UntrustedOracle,
+    #[error("Collateral is not freshly attested")]
+    CollateralUnusable,
+    #[error("Mint would exceed the collateral")]
+    ExceedsCollateral,
+    #[error("Position belongs to another account")]
+    WrongOwner,
+    #[error("Incorrect vault PDA")]
+    IncorrectPDA,
+}
+
+impl From<VaultError> for ProgramError {
+    fn from(e: VaultError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+pub fn find_mint_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MINT_PREFIX], program_id)
+}
+
+pub fn find_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AUTHORITY_PREFIX], program_id)
+}
+
+pub fn find_position_address(program_id: &Pubkey, tick: &[u8; 4], owner: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POSITION_PREFIX, tick.as_ref(), &owner_seed(owner)], program_id)
+}
+
+/// Most that may be minted in total against `collateral`.
+pub fn max_mint(collateral: Brc20Amount) -> u128 {
+    collateral.0 / 100 * LOAN_TO_VALUE_PERCENT
+}
+
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let instruction = VaultInstruction::try_from_slice(instruction_data)?;
+    match instruction {
+        VaultInstruction::InitMint => init_mint(program_id, accounts),
+        VaultInstruction::Mint(key, amount) => mint(program_id, accounts, key, amount),
+    }
+}
+
+pub fn init_mint(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    let (mint_address, bump) = find_mint_address(program_id);
+    if mint_info.key != &mint_address {
+        return Err(VaultError::IncorrectPDA.into());
+    }
+    if token_program.key != &spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let len = spl_token::state::Mint::LEN;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            mint_info.key,
+            Rent::get()?.minimum_balance(len),
+            len as u64,
+            &spl_token::id(),
+        ),
+        &[payer_info.clone(), mint_info.clone(), system_program.clone()],
+        &[&[MINT_PREFIX, &[bump]]],
+    )?;
+    let authority = find_authority_address(program_id).0;
+    invoke(
+        &spl_token::instruction::initialize_mint2(&spl_token::id(), mint_info.key, &authority, None, 0)?,
+        &[mint_info.clone(), token_program.clone()],
+    )?;
+    msg!("init mint {}", mint_info.key);
+    Ok(())
+}
+
+pub fn mint(program_id: &Pubkey, accounts: &[AccountInfo], key: Brc20Key, amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let position_info = next_account_info(account_info_iter)?;
+    let oracle_program = next_account_info(account_info_iter)?;
+    let asset_info = next_account_info(account_info_iter)?;
+    let policy_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if oracle_program.key != &ORACLE_PROGRAM_ID {
+        return Err(VaultError::UntrustedOracle.into());
+    }
+    if token_program.key != &spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (position_address, position_bump) = find_position_address(program_id, &key.tick, &key.owner);
+    let (authority_address, authority_bump) = find_authority_address(program_id);
+    if position_info.key != &position_address
+        || authority_info.key != &authority_address
+        || mint_info.key != &find_mint_address(program_id).0
+    {
+        return Err(VaultError::IncorrectPDA.into());
+    }
+
+    // stale, revoked and missing attestations can't back new tokens.
+    let view = get_amount(oracle_program, asset_info, policy_info, &key)?;
+    if view.status != AmountStatus::Fresh {
+        return Err(VaultError::CollateralUnusable.into());
+    }
+
+    let mut position = if position_info.owner == program_id {
+        Position::try_from_slice(&position_info.data.borrow())?
+    } else {
+        let len = Position::LEN;
+        invoke_signed(
+            &system_instruction::create_account(
+                owner_info.key,
+                position_info.key,
+                Rent::get()?.minimum_balance(len),
+                len as u64,
+                program_id,
+            ),
+            &[owner_info.clone(), position_info.clone(), system_program.clone()],
+            &[&[POSITION_PREFIX, key.tick.as_ref(), &owner_seed(&key.owner), &[position_bump]]],
+        )?;
+        Position { owner: *owner_info.key, minted: 0 }
+    };
+    if &position.owner != owner_info.key {
+        return Err(VaultError::WrongOwner.into());
+    }
+    position.minted = position.minted.checked_add(amount).ok_or(VaultError::ExceedsCollateral)?;
+    if u128::from(position.minted) > max_mint(view.amount) {
+        return Err(VaultError::ExceedsCollateral.into());
+    }
+    position.serialize(&mut &mut position_info.data.borrow_mut()[..])?;
+
+    invoke_signed(
+        &spl_token::instruction::mint_to(&spl_token::id(), mint_info.key, destination_info.key, authority_info.key, &[], amount)?,
+        &[mint_info.clone(), destination_info.clone(), authority_info.clone(), token_program.clone()],
+        &[&[AUTHORITY_PREFIX, &[authority_bump]]],
+    )?;
+    msg!("minted {} against {} attested at slot {}", amount, view.amount.0, view.attested_slot);
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::pda::{find_asset_address, find_tick_policy_address};
+use brc20_oracle::types::{AttestationSource, Brc20Amount, Brc20Asset, Brc20Key, TickPolicy, BRC20_KEY_VERSION};
+use brc20_oracle::ASSET_PREFIX;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::{system_instruction, system_program};
+use solana_program_test::*;
+use solana_sdk::account::Account;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use crate::{
+    find_authority_address, find_mint_address, find_position_address, Position, VaultError, VaultInstruction,
+    ORACLE_PROGRAM_ID,
+};
+
+const VAULT_ID: Pubkey = Pubkey::new_from_array([9; 32]);
+const ATTESTED_SLOT: u64 = 10;
+const MAX_AGE: u64 = 100;
+
+fn key(owner: &str, height: u32) -> Brc20Key {
+    Brc20Key { version: BRC20_KEY_VERSION, height, tick: *b"ordi", owner: owner.to_string() }
+}
+
+fn oracle_account(data: Vec<u8>) -> Account {
+    Account { lamports: 1_000_000_000, data, owner: ORACLE_PROGRAM_ID, executable: false, rent_epoch: 0 }
+}
+
+// the oracle with `assets` filled by the committee at `ATTESTED_SLOT`, and the vault.
+fn init_program_test(assets: &[(Brc20Key, u128)]) -> ProgramTest {
+    let mut program_test = ProgramTest::default();
+    program_test.add_program("brc20_oracle", ORACLE_PROGRAM_ID, processor!(brc20_oracle::process_instruction));
+    program_test.add_program("example_vault", VAULT_ID, processor!(crate::process_instruction));
+    for (key, amount) in assets {
+        let (address, bump) = find_asset_address(&ORACLE_PROGRAM_ID, key);
+        let asset = Brc20Asset {
+            prefix: ASSET_PREFIX,
+            set: true,
+            uid: 0,
+            key: key.clone(),
+            amount: Brc20Amount(*amount),
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: ATTESTED_SLOT,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump,
+        };
+        program_test.add_account(address, oracle_account(asset.try_to_vec().unwrap()));
+    }
+    let policy = TickPolicy { tick: *b"ordi", max_age: MAX_AGE, nonce: 1 };
+    program_test.add_account(find_tick_policy_address(&ORACLE_PROGRAM_ID, b"ordi").0, oracle_account(policy.try_to_vec().unwrap()));
+    program_test
+}
+
+async fn process(context: &mut ProgramTestContext, signers: &[&Keypair], instructions: &[Instruction]) -> Result<(), TransactionError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(transaction).await.map_err(|e| e.unwrap())
+}
+
+// creates the vault mint and a token account of it for `owner`, who is funded to pay for positions.
+async fn init_mint(context: &mut ProgramTestContext, owner: &Pubkey) -> Pubkey {
+    let mint = find_mint_address(&VAULT_ID).0;
+    let init = Instruction {
+        program_id: VAULT_ID,
+        accounts: vec![
+            AccountMeta::new(context.payer.pubkey(), true),
+            AccountMeta::new(mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: VaultInstruction::InitMint.try_to_vec().unwrap(),
+    };
+    let token_account = Keypair::new();
+    let len = spl_token::state::Account::LEN;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let create = system_instruction::create_account(
+        &context.payer.pubkey(),
+        &token_account.pubkey(),
+        rent.minimum_balance(len),
+        len as u64,
+        &spl_token::id(),
+    );
+    let initialize = spl_token::instruction::initialize_account3(&spl_token::id(), &token_account.pubkey(), &mint, owner).unwrap();
+    let fund = system_instruction::transfer(&context.payer.pubkey(), owner, 1_000_000_000);
+    process(context, &[&token_account], &[init, create, initialize, fund]).await.unwrap();
+    token_account.pubkey()
+}
+
+fn mint_ix(owner: &Pubkey, destination: &Pubkey, key: &Brc20Key, amount: u64) -> Instruction {
+    Instruction {
+        program_id: VAULT_ID,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(find_position_address(&VAULT_ID, &key.tick, &key.owner).0, false),
+            AccountMeta::new_readonly(ORACLE_PROGRAM_ID, false),
+            AccountMeta::new_readonly(find_asset_address(&ORACLE_PROGRAM_ID, key).0, false),
+            AccountMeta::new_readonly(find_tick_policy_address(&ORACLE_PROGRAM_ID, &key.tick).0, false),
+            AccountMeta::new(find_mint_address(&VAULT_ID).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(find_authority_address(&VAULT_ID).0, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: VaultInstruction::Mint(key.clone(), amount).try_to_vec().unwrap(),
+    }
+}
+
+async fn token_balance(context: &mut ProgramTestContext, token_account: &Pubkey) -> u64 {
+    let account = context.banks_client.get_account(*token_account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+fn vault_error(error: VaultError) -> Result<(), TransactionError> {
+    Err(TransactionError::InstructionError(0, InstructionError::Custom(error as u32)))
+}
+
+#[tokio::test]
+async fn test_mint_against_attestation() {
+    let assets = [(key("a", 840_000), 1000), (key("a", 840_001), 1000), (key("b", 840_000), 1000)];
+    let mut context = init_program_test(&assets).start_with_context().await;
+    context.warp_to_slot(ATTESTED_SLOT + 1).unwrap();
+    let owner = Keypair::new();
+    let destination = init_mint(&mut context, &owner.pubkey()).await;
+
+    process(&mut context, &[&owner], &[mint_ix(&owner.pubkey(), &destination, &assets[0].0, 300)]).await.unwrap();
+    process(&mut context, &[&owner], &[mint_ix(&owner.pubkey(), &destination, &assets[0].0, 200)]).await.unwrap();
+    assert_eq!(500, token_balance(&mut context, &destination).await);
+    let position = context.banks_client.get_account(find_position_address(&VAULT_ID, b"ordi", "a").0).await.unwrap().unwrap();
+    assert_eq!(Position { owner: owner.pubkey(), minted: 500 }, Position::try_from_slice(&position.data).unwrap());
+
+    // the same balance attested at another height is still the same position.
+    let again = mint_ix(&owner.pubkey(), &destination, &assets[1].0, 1);
+    assert_eq!(vault_error(VaultError::ExceedsCollateral), process(&mut context, &[&owner], &[again]).await);
+
+    let other = Keypair::new();
+    let taken = mint_ix(&other.pubkey(), &destination, &assets[0].0, 0);
+    assert_eq!(vault_error(VaultError::WrongOwner), process(&mut context, &[&other], &[taken]).await);
+
+    // nothing is minted against a missing or stale attestation.
+    let missing = mint_ix(&owner.pubkey(), &destination, &key("c", 840_000), 1);
+    assert_eq!(vault_error(VaultError::CollateralUnusable), process(&mut context, &[&owner], &[missing]).await);
+    context.warp_to_slot(ATTESTED_SLOT + MAX_AGE + 1).unwrap();
+    let stale = mint_ix(&owner.pubkey(), &destination, &assets[2].0, 1);
+    assert_eq!(vault_error(VaultError::CollateralUnusable), process(&mut context, &[&owner], &[stale]).await);
+    assert_eq!(500, token_balance(&mut context, &destination).await);
+}
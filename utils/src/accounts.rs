@@ -0,0 +1,83 @@
+use brc20_oracle::is_committee_alive;
+use brc20_oracle::types::{Brc20Asset, Brc20Key, Committee, RejectReason, RevokeReason};
+use solana_client::client_error::{ClientErrorKind, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use crate::instruction::{find_asset_address, find_committee_address};
+
+/// Decoded committee PDA together with where and when it was read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitteeAccount {
+    pub pubkey: Pubkey,
+    pub bump: u8,
+    // slot of the RPC response the data was read at.
+    pub slot: u64,
+    pub committee: Committee,
+}
+
+impl CommitteeAccount {
+    pub async fn fetch(client: &RpcClient, program_id: &Pubkey) -> Result<Self> {
+        let (pubkey, bump) = find_committee_address(program_id);
+        let (slot, data) = fetch_data(client, &pubkey).await?;
+        Ok(Self { pubkey, bump, slot, committee: Committee::decode_any_version(&data)? })
+    }
+
+    pub fn is_alive(&self) -> bool {
+        is_committee_alive(&self.committee, self.slot)
+    }
+}
+
+/// Decoded asset PDA together with where and when it was read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetAccount {
+    pub pubkey: Pubkey,
+    pub bump: u8,
+    // slot of the RPC response the data was read at.
+    pub slot: u64,
+    pub asset: Brc20Asset,
+}
+
+impl AssetAccount {
+    pub async fn fetch(client: &RpcClient, program_id: &Pubkey, key: &Brc20Key) -> Result<Self> {
+        let (pubkey, bump) = find_asset_address(program_id, key);
+        let (slot, data) = fetch_data(client, &pubkey).await?;
+        Ok(Self { pubkey, bump, slot, asset: Brc20Asset::decode_any_version(&data)? })
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.asset.set
+    }
+
+    pub fn rejected(&self) -> Option<RejectReason> {
+        self.asset.rejected
+    }
+
+    pub fn revoked(&self) -> Option<RevokeReason> {
+        self.asset.revoked
+    }
+
+    /// Slots between the attestation and the fetch, `None` while the request is unfilled.
+    pub fn age_in_slots(&self) -> Option<u64> {
+        if self.asset.set {
+            Some(self.slot.saturating_sub(self.asset.attested_slot))
+        } else {
+            None
+        }
+    }
+
+    pub fn btc_address_string(&self) -> &str {
+        &self.asset.key.owner
+    }
+
+    pub fn tick_string(&self) -> String {
+        String::from_utf8_lossy(&self.asset.key.tick).to_string()
+    }
+}
+
+async fn fetch_data(client: &RpcClient, pubkey: &Pubkey) -> Result<(u64, Vec<u8>)> {
+    let response = client.get_account_with_commitment(pubkey, client.commitment()).await?;
+    match response.value {
+        Some(account) => Ok((response.context.slot, account.data)),
+        None => Err(ClientErrorKind::Custom(format!("account {} not found", pubkey)).into()),
+    }
+}
@@ -0,0 +1,142 @@
+//! Finds requests left unfilled past their SLA and a lapsed committee heartbeat. There is no
+//! notifier here, callers forward the alerts to whatever pages them.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use borsh::BorshDeserialize;
+use brc20_oracle::is_committee_alive;
+use brc20_oracle::types::{Brc20Asset, Brc20Key, Committee, RequestRecord};
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_program::pubkey::Pubkey;
+use crate::call_process::{get_assets_by_tick, get_committee, REQUEST_RECORD_LEN};
+
+#[derive(Clone, Debug, Default)]
+pub struct SlaConfig {
+    // slots a request may stay unfilled before it is reported.
+    pub max_pending_slots: u64,
+    // overrides of `max_pending_slots` per tick.
+    pub per_tick: HashMap<[u8; 4], u64>,
+}
+
+impl SlaConfig {
+    pub fn max_pending_slots(&self, tick: &[u8; 4]) -> u64 {
+        self.per_tick.get(tick).copied().unwrap_or(self.max_pending_slots)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Alert {
+    // pending for `pending_slots`, counted from its oldest request.
+    StaleRequest { asset: Pubkey, key: Brc20Key, pending_slots: u64 },
+    CommitteeDown { last_heartbeat_slot: u64 },
+}
+
+/// Alerts for `assets` still pending past their tick's SLA, oldest first, and for the committee if
+/// its heartbeat lapsed. Rejected and expired requests are not pending.
+pub fn check_alerts(
+    committee: &Committee,
+    assets: &[(Pubkey, Brc20Asset)],
+    requests: &[RequestRecord],
+    current_slot: u64,
+    sla: &SlaConfig,
+) -> Vec<Alert> {
+    let mut requested_at: HashMap<Pubkey, u64> = HashMap::new();
+    for record in requests {
+        let slot = requested_at.entry(record.asset).or_insert(record.created_slot);
+        *slot = (*slot).min(record.created_slot);
+    }
+
+    let mut stale = vec![];
+    for (address, asset) in assets {
+        if asset.set || asset.rejected.is_some() || matches!(asset.expire_slot, Some(slot) if current_slot > slot) {
+            continue;
+        }
+        let created_slot = match requested_at.get(address) {
+            Some(slot) => slot,
+            None => continue,
+        };
+        let pending_slots = current_slot.saturating_sub(*created_slot);
+        if pending_slots > sla.max_pending_slots(&asset.key.tick) {
+            stale.push((pending_slots, Alert::StaleRequest { asset: *address, key: asset.key.clone(), pending_slots }));
+        }
+    }
+    stale.sort_by_key(|(pending_slots, _)| Reverse(*pending_slots));
+
+    let mut alerts: Vec<Alert> = stale.into_iter().map(|(_, alert)| alert).collect();
+    if !is_committee_alive(committee, current_slot) {
+        alerts.push(Alert::CommitteeDown { last_heartbeat_slot: committee.last_heartbeat_slot });
+    }
+    alerts
+}
+
+/// `check_alerts` over every asset of `ticks`. Scans the whole program, meant for a periodic monitor.
+pub async fn fetch_alerts(client: &RpcClient, program_id: &Pubkey, ticks: &[[u8; 4]], sla: &SlaConfig) -> Result<Vec<Alert>> {
+    let committee = get_committee(client, program_id).await?;
+    let mut assets = vec![];
+    for tick in ticks {
+        assets.extend(get_assets_by_tick(client, program_id, tick).await?);
+    }
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::DataSize(REQUEST_RECORD_LEN)]),
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(client.commitment()),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let requests: Vec<RequestRecord> = client
+        .get_program_accounts_with_config(program_id, config)
+        .await?
+        .into_iter()
+        .filter_map(|(_, account)| RequestRecord::try_from_slice(&account.data).ok())
+        .collect();
+    let current_slot = client.get_slot().await?;
+    Ok(check_alerts(&committee, &assets, &requests, current_slot, sla))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use brc20_oracle::types::{AttestationSource, Brc20Amount, BRC20_KEY_VERSION};
+    use brc20_oracle::HEARTBEAT_INTERVAL;
+
+    fn pending(tick: [u8; 4]) -> Brc20Asset {
+        Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set: false,
+            uid: 0,
+            key: Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick, owner: "12345".to_string() },
+            amount: Brc20Amount(0),
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: 0,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_alerts() {
+        let committee = Committee { id: 0, address: Pubkey::new_unique(), uid: 0, last_heartbeat_slot: 1000, bump: 0 };
+        let (ordi, sats) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let assets = vec![(ordi, pending(*b"ordi")), (sats, pending(*b"sats"))];
+        let record = |asset, created_slot| RequestRecord { asset, requester: Pubkey::new_unique(), created_slot };
+        let requests = vec![record(ordi, 900), record(ordi, 950), record(sats, 900)];
+        let sla = SlaConfig { max_pending_slots: 50, per_tick: HashMap::from([(*b"sats", 500)]) };
+
+        // ordi counts from its oldest request, sats has a longer SLA.
+        let alerts = check_alerts(&committee, &assets, &requests, 1000, &sla);
+        assert_eq!(vec![Alert::StaleRequest { asset: ordi, key: assets[0].1.key.clone(), pending_slots: 100 }], alerts);
+
+        let late = 1000 + HEARTBEAT_INTERVAL + 1;
+        let alerts = check_alerts(&committee, &assets, &requests, late, &sla);
+        assert_eq!(3, alerts.len());
+        assert_eq!(Alert::CommitteeDown { last_heartbeat_slot: 1000 }, alerts[2]);
+    }
+}
@@ -0,0 +1,94 @@
+use brc20_oracle::types::Brc20Amount;
+use thiserror::Error;
+
+// 10^38 is the largest power of ten below u128::MAX.
+const MAX_DECIMALS: u8 = 38;
+
+#[derive(Debug, Eq, Error, PartialEq)]
+pub enum AmountError {
+    #[error("unsupported decimals {0}")]
+    Decimals(u8),
+    #[error("invalid amount {0:?}")]
+    Invalid(String),
+    #[error("too many fractional digits for {0} decimals")]
+    Precision(u8),
+    #[error("amount out of range")]
+    Overflow,
+}
+
+/// Renders a raw on-chain amount as a decimal string, e.g. `(1_500_000, 6)` -> `"1.5"`.
+pub fn format_amount(amount: Brc20Amount, decimals: u8) -> Result<String, AmountError> {
+    let unit = unit(decimals)?;
+    let integer = amount.0 / unit;
+    let fraction = amount.0 % unit;
+    if fraction == 0 {
+        return Ok(integer.to_string());
+    }
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    Ok(format!("{}.{}", integer, fraction.trim_end_matches('0')))
+}
+
+/// Inverse of `format_amount`, rejecting more fractional digits than `decimals` and overflow.
+pub fn parse_amount(value: &str, decimals: u8) -> Result<Brc20Amount, AmountError> {
+    let unit = unit(decimals)?;
+    let (integer, fraction) = match value.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (value, ""),
+    };
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if integer.is_empty() || !is_digits(integer) || !is_digits(fraction) {
+        return Err(AmountError::Invalid(value.to_string()));
+    }
+    if fraction.len() > decimals as usize {
+        return Err(AmountError::Precision(decimals));
+    }
+
+    let integer: u128 = integer.parse().map_err(|_| AmountError::Overflow)?;
+    let fraction: u128 = if fraction.is_empty() {
+        0
+    } else {
+        let padded = format!("{:0<width$}", fraction, width = decimals as usize);
+        padded.parse().map_err(|_| AmountError::Overflow)?
+    };
+    integer
+        .checked_mul(unit)
+        .and_then(|amount| amount.checked_add(fraction))
+        .and_then(Brc20Amount::new)
+        .ok_or(AmountError::Overflow)
+}
+
+fn unit(decimals: u8) -> Result<u128, AmountError> {
+    if decimals > MAX_DECIMALS {
+        return Err(AmountError::Decimals(decimals));
+    }
+    Ok(10u128.pow(decimals as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_parse_roundtrip() {
+        assert_eq!("1.5", format_amount(Brc20Amount(1_500_000), 6).unwrap());
+        assert_eq!("0.000001", format_amount(Brc20Amount(1), 6).unwrap());
+        assert_eq!("42", format_amount(Brc20Amount(42), 0).unwrap());
+        assert_eq!(Brc20Amount(1_500_000), parse_amount("1.5", 6).unwrap());
+        assert_eq!(Brc20Amount(1), parse_amount("0.000001", 6).unwrap());
+        for amount in [Brc20Amount::ZERO, Brc20Amount(1), Brc20Amount(123_456_789), Brc20Amount::MAX] {
+            assert_eq!(amount, parse_amount(&format_amount(amount, 18).unwrap(), 18).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_input() {
+        assert_eq!(Err(AmountError::Precision(2)), parse_amount("1.001", 2));
+        assert_eq!(Err(AmountError::Overflow), parse_amount(&u128::MAX.to_string(), 1));
+        // u128::MAX is the reserved sentinel.
+        assert_eq!(Err(AmountError::Overflow), parse_amount(&u128::MAX.to_string(), 0));
+        assert!(parse_amount("", 2).is_err());
+        assert!(parse_amount("1.2.3", 2).is_err());
+        assert!(parse_amount("-1", 2).is_err());
+        assert!(format_amount(Brc20Amount(1), 39).is_err());
+    }
+}
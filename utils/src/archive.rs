@@ -0,0 +1,143 @@
+//! Committee signed copies of every indexed asset, for serving history after accounts are closed.
+//! Pinning the encoded archive (IPFS, Arweave) is left to the operator's tooling.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::types::{Brc20Asset, Brc20Key};
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::keypair::Keypair;
+use solana_sdk::signer::Signer;
+use crate::call_process::{get_asset, get_indexed_assets};
+use crate::instruction::find_asset_address;
+
+// getMultipleAccounts accepts at most 100 keys.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ArchiveSnapshot {
+    pub program_id: Pubkey,
+    // slot the last batch of accounts was read at.
+    pub slot: u64,
+    // asset accounts still open at `slot`, in uid order.
+    pub assets: Vec<(Pubkey, Brc20Asset)>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct SignedArchive {
+    pub snapshot: ArchiveSnapshot,
+    pub committee: Pubkey,
+    // committee signature over the borsh encoded snapshot.
+    pub signature: Vec<u8>,
+}
+
+impl SignedArchive {
+    /// Whether `signature` is `committee`'s over the snapshot. Callers still have to trust `committee`.
+    pub fn verify(&self) -> bool {
+        let msg = match self.snapshot.try_to_vec() {
+            Ok(msg) => msg,
+            Err(_) => return false,
+        };
+        match Signature::try_from(self.signature.as_slice()) {
+            Ok(signature) => signature.verify(self.committee.as_ref(), &msg),
+            Err(_) => false,
+        }
+    }
+
+    pub fn lookup(&self, key: &Brc20Key) -> Option<&Brc20Asset> {
+        let asset_address = find_asset_address(&self.snapshot.program_id, key).0;
+        self.snapshot
+            .assets
+            .iter()
+            .find(|(address, _)| address == &asset_address)
+            .map(|(_, asset)| asset)
+    }
+}
+
+/// Reads every asset in the on chain index and signs the result with `committee`.
+pub async fn export_archive(client: &RpcClient, program_id: &Pubkey, committee: &Keypair) -> Result<SignedArchive> {
+    let addresses: Vec<Pubkey> = get_indexed_assets(client, program_id)
+        .await?
+        .into_iter()
+        .map(|entry| entry.asset)
+        .collect();
+    let mut assets = Vec::with_capacity(addresses.len());
+    let mut slot = 0;
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let response = client
+            .get_multiple_accounts_with_commitment(chunk, client.commitment())
+            .await?;
+        slot = response.context.slot;
+        for (address, account) in chunk.iter().zip(response.value) {
+            // expired and closed assets are gone, earlier archives still hold them.
+            if let Some(asset) = account.and_then(|account| Brc20Asset::try_from_slice(&account.data).ok()) {
+                assets.push((*address, asset));
+            }
+        }
+    }
+
+    let snapshot = ArchiveSnapshot { program_id: *program_id, slot, assets };
+    let signature = committee.sign_message(&snapshot.try_to_vec()?).as_ref().to_vec();
+    Ok(SignedArchive { snapshot, committee: committee.pubkey(), signature })
+}
+
+/// The live asset when its account still exists, otherwise the copy in the first archive holding it.
+/// Archives are expected to be verified by the caller.
+pub async fn resolve_asset(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    key: &Brc20Key,
+    archives: &[SignedArchive],
+) -> Result<Option<Brc20Asset>> {
+    let asset_address = find_asset_address(program_id, key).0;
+    let response = client.get_account_with_commitment(&asset_address, client.commitment()).await?;
+    if response.value.is_some() {
+        return get_asset(client, program_id, key).await.map(Some);
+    }
+    Ok(archives
+        .iter()
+        .filter(|archive| &archive.snapshot.program_id == program_id)
+        .find_map(|archive| archive.lookup(key).cloned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use brc20_oracle::types::{AttestationSource, Brc20Amount, BRC20_KEY_VERSION};
+
+    #[test]
+    fn test_signed_archive() {
+        let program_id = Pubkey::new_unique();
+        let committee = Keypair::new();
+        let key = Brc20Key { version: BRC20_KEY_VERSION, height: 10, tick: *b"ordi", owner: "a".to_string() };
+        let asset = Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set: true,
+            uid: 0,
+            key: key.clone(),
+            amount: Brc20Amount(100),
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: 5,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        };
+        let snapshot = ArchiveSnapshot {
+            program_id,
+            slot: 10,
+            assets: vec![(find_asset_address(&program_id, &key).0, asset.clone())],
+        };
+        let signature = committee.sign_message(&snapshot.try_to_vec().unwrap()).as_ref().to_vec();
+        let mut archive = SignedArchive { snapshot, committee: committee.pubkey(), signature };
+        assert!(archive.verify());
+        assert_eq!(Some(&asset), archive.lookup(&key));
+        assert_eq!(None, archive.lookup(&Brc20Key { height: 11, ..key }));
+
+        archive.snapshot.assets[0].1.amount = Brc20Amount(101);
+        assert!(!archive.verify());
+    }
+}
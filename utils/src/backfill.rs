@@ -0,0 +1,274 @@
+//! Requests a range of Bitcoin heights for one (tick, owner) and reports how far they got, or
+//! estimates what requesting them would cost before anything is sent.
+
+use std::ops::RangeInclusive;
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::types::{
+    AssetHead, AttestationSource, Brc20Amount, Brc20Asset, Brc20Key, IndexEntry, IndexPage, RequestRecord, TickRegistry,
+    BRC20_KEY_VERSION,
+};
+use solana_client::client_error::{ClientError, ClientErrorKind, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::sysvar;
+use solana_sdk::account::from_account;
+use solana_sdk::signer::keypair::Keypair;
+use solana_sdk::signer::Signer;
+use brc20_oracle::{ASSET_PREFIX, INDEX_PAGE_CAPACITY};
+use crate::call_process::{get_committee, submit_all};
+use crate::instruction::{find_asset_address, find_head_address, find_tick_registry_address, insert_ix, request_ix};
+
+// getMultipleAccounts accepts at most 100 keys.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct BackfillSummary {
+    // heights a request was sent for.
+    pub requested: Vec<u32>,
+    // heights that already had an asset account, from an earlier run or another requester.
+    pub skipped: Vec<u32>,
+    pub failed: Vec<(u32, ClientError)>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FillProgress {
+    pub filled: Vec<u32>,
+    pub pending: Vec<u32>,
+    // no asset account, never requested or already expired.
+    pub missing: Vec<u32>,
+}
+
+// All in lamports at the current rent and fee parameters, priority fees left out. The program
+// pays no bounties, a request costs its requester rent and transaction fees only.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BackfillEstimate {
+    // heights a backfill would request now, the others already have an asset account.
+    pub requests: usize,
+    // asset and request record accounts. Request records are refunded by `CloseRequest`.
+    pub rent: u64,
+    // index page growth, and the tick registry on the first request for the tick.
+    pub index_rent: u64,
+    pub request_fees: u64,
+    // paid by the committee filling the requests, not by the requester.
+    pub insert_fees: u64,
+    // the (tick, owner) head account, created by the first insert.
+    pub insert_rent: u64,
+}
+
+impl BackfillEstimate {
+    pub fn requester_total(&self) -> u64 {
+        self.rent + self.index_rent + self.request_fees
+    }
+
+    pub fn committee_total(&self) -> u64 {
+        self.insert_fees + self.insert_rent
+    }
+}
+
+/// Requests every height in `heights` that has no asset account yet. Safe to re-run after a partial failure.
+/// Requests are sent without a ttl, historical heights don't go stale.
+pub async fn backfill(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    tick: [u8; 4],
+    owner: &str,
+    heights: RangeInclusive<u32>,
+    max_in_flight: usize,
+) -> Result<BackfillSummary> {
+    let keys = backfill_keys(tick, owner, heights);
+    let assets = fetch_assets(client, program_id, &keys).await?;
+
+    // uids are handed out in landing order, a request crossing a page boundary out of order fails
+    // and is picked up by the next run.
+    let mut uid = get_committee(client, program_id).await?.uid;
+    let mut summary = BackfillSummary::default();
+    let mut batches = vec![];
+    let mut heights = vec![];
+    for (key, asset) in keys.into_iter().zip(assets) {
+        match asset {
+            Some(_) => summary.skipped.push(key.height),
+            None => {
+                heights.push(key.height);
+                let tick_registry = find_tick_registry_address(program_id, &key.tick).0;
+                batches.push(request_ix(program_id, payer, key, None, Some(tick_registry), Some(uid / INDEX_PAGE_CAPACITY)));
+                uid += 1;
+            }
+        }
+    }
+    let results = submit_all(client, payer, &[payer], batches, max_in_flight).await;
+    for (height, result) in heights.into_iter().zip(results) {
+        match result {
+            Ok(_) => summary.requested.push(height),
+            Err(e) => summary.failed.push((height, e)),
+        }
+    }
+    Ok(summary)
+}
+
+/// What `backfill` over the same range would cost `payer` now, and what filling it would cost the committee.
+pub async fn estimate_backfill(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    tick: [u8; 4],
+    owner: &str,
+    heights: RangeInclusive<u32>,
+) -> Result<BackfillEstimate> {
+    let keys = backfill_keys(tick, owner, heights);
+    let assets = fetch_assets(client, program_id, &keys).await?;
+    let missing: Vec<Brc20Key> = keys.into_iter().zip(assets).filter(|(_, asset)| asset.is_none()).map(|(key, _)| key).collect();
+    let first = match missing.first() {
+        Some(first) => first,
+        None => return Ok(BackfillEstimate::default()),
+    };
+
+    let uid = get_committee(client, program_id).await?.uid;
+    let addresses = [
+        sysvar::rent::id(),
+        find_tick_registry_address(program_id, &tick).0,
+        find_head_address(program_id, &tick, owner).0,
+    ];
+    let accounts = client.get_multiple_accounts(&addresses).await?;
+    let rent: Rent = accounts[0]
+        .as_ref()
+        .and_then(from_account)
+        .ok_or_else(|| ClientError::from(ClientErrorKind::Custom("rent sysvar not found".to_string())))?;
+
+    // fees only depend on the signatures, the committee key signing here is a throwaway.
+    let blockhash = client.get_latest_blockhash().await?;
+    let tick_registry = find_tick_registry_address(program_id, &first.tick).0;
+    let request = request_ix(program_id, payer, first.clone(), None, Some(tick_registry), Some(uid / INDEX_PAGE_CAPACITY));
+    let request_fee = client.get_fee_for_message(&Message::new_with_blockhash(&request, Some(&payer.pubkey()), &blockhash)).await?;
+    let asset = placeholder_asset(program_id, first, &payer.pubkey());
+    let insert = insert_ix(program_id, &payer.pubkey(), &Keypair::new(), asset.clone(), Brc20Amount::ZERO, None, AttestationSource::default());
+    let insert_fee = client.get_fee_for_message(&Message::new_with_blockhash(&insert, Some(&payer.pubkey()), &blockhash)).await?;
+
+    let count = missing.len() as u64;
+    let asset_rent = rent.minimum_balance(asset.try_to_vec().unwrap().len());
+    let record = RequestRecord { asset: Pubkey::default(), requester: payer.pubkey(), created_slot: 0 };
+    let record_rent = rent.minimum_balance(record.try_to_vec().unwrap().len());
+    let mut index_rent = index_page_rent(&rent, uid, count);
+    if accounts[1].is_none() {
+        let registry = TickRegistry { tick, count: 0, last_uid: 0, head: Pubkey::default() };
+        index_rent += rent.minimum_balance(registry.try_to_vec().unwrap().len());
+    }
+    let insert_rent = match accounts[2] {
+        Some(_) => 0,
+        None => rent.minimum_balance(AssetHead { height: 0, asset: Pubkey::default() }.try_to_vec().unwrap().len()),
+    };
+    Ok(BackfillEstimate {
+        requests: missing.len(),
+        rent: count * (asset_rent + record_rent),
+        index_rent,
+        request_fees: count * request_fee,
+        insert_fees: count * insert_fee,
+        insert_rent,
+    })
+}
+
+/// Where each height of a backfill stands now.
+pub async fn fill_progress(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    tick: [u8; 4],
+    owner: &str,
+    heights: RangeInclusive<u32>,
+) -> Result<FillProgress> {
+    let keys = backfill_keys(tick, owner, heights);
+    let assets = fetch_assets(client, program_id, &keys).await?;
+
+    let mut progress = FillProgress::default();
+    for (key, asset) in keys.iter().zip(assets) {
+        match asset {
+            Some(asset) if asset.set => progress.filled.push(key.height),
+            Some(_) => progress.pending.push(key.height),
+            None => progress.missing.push(key.height),
+        }
+    }
+    Ok(progress)
+}
+
+fn backfill_keys(tick: [u8; 4], owner: &str, heights: RangeInclusive<u32>) -> Vec<Brc20Key> {
+    heights
+        .map(|height| Brc20Key { version: BRC20_KEY_VERSION, height, tick, owner: owner.to_string() })
+        .collect()
+}
+
+// the asset account a request for `key` creates, for its size.
+fn placeholder_asset(program_id: &Pubkey, key: &Brc20Key, requester: &Pubkey) -> Brc20Asset {
+    Brc20Asset {
+        prefix: ASSET_PREFIX,
+        set: false,
+        uid: 0,
+        key: key.clone(),
+        amount: Brc20Amount::ZERO,
+        requester: *requester,
+        expire_slot: None,
+        attested_slot: 0,
+        rejected: None,
+        confidence: None,
+        source: AttestationSource::default(),
+        revoked: None,
+        bump: find_asset_address(program_id, key).1,
+    }
+}
+
+// rent for appending `count` entries to the index from `uid` on. Pages are created on their first
+// entry and topped up on every later one.
+fn index_page_rent(rent: &Rent, uid: u64, count: u64) -> u64 {
+    let page_rent = |entries: u64| {
+        let page = IndexPage {
+            page: 0,
+            next: Pubkey::default(),
+            entries: vec![IndexEntry { uid: 0, asset: Pubkey::default() }; entries as usize],
+        };
+        rent.minimum_balance(page.try_to_vec().unwrap().len())
+    };
+    let mut total = 0;
+    let mut next = uid;
+    while next < uid + count {
+        let before = next % INDEX_PAGE_CAPACITY;
+        let after = INDEX_PAGE_CAPACITY.min(before + uid + count - next);
+        total += match before {
+            0 => page_rent(after),
+            _ => page_rent(after) - page_rent(before),
+        };
+        next += after - before;
+    }
+    total
+}
+
+async fn fetch_assets(client: &RpcClient, program_id: &Pubkey, keys: &[Brc20Key]) -> Result<Vec<Option<Brc20Asset>>> {
+    let addresses: Vec<Pubkey> = keys.iter().map(|key| find_asset_address(program_id, key).0).collect();
+    let mut assets = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts(chunk).await?;
+        assets.extend(
+            accounts
+                .into_iter()
+                .map(|account| account.and_then(|account| Brc20Asset::try_from_slice(&account.data).ok())),
+        );
+    }
+    Ok(assets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_page_rent() {
+        let rent = Rent::default();
+        // page, next and the entries length, then uid and asset per entry.
+        let page_size = |entries: usize| 8 + 32 + 4 + 40 * entries;
+        assert_eq!(0, index_page_rent(&rent, 5, 0));
+        assert_eq!(rent.minimum_balance(page_size(3)), index_page_rent(&rent, 0, 3));
+        assert_eq!(2 * rent.minimum_balance(page_size(64)), index_page_rent(&rent, 0, 128));
+        // the top up of page 0, then a new page 1.
+        let top_up = rent.minimum_balance(page_size(64)) - rent.minimum_balance(page_size(62));
+        assert_eq!(top_up + rent.minimum_balance(page_size(2)), index_page_rent(&rent, 62, 4));
+    }
+}
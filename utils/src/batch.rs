@@ -0,0 +1,149 @@
+//! Sends a long run of transactions in chunks with a checkpoint file. A run that dies halfway
+//! resumes after the last confirmed batch instead of re-sending everything before it.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::instruction::Instruction;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::keypair::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::signers::Signers;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+#[derive(thiserror::Error, Debug)]
+pub enum BatchError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid checkpoint file: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("can't write checkpoint file: {0}")]
+    TomlWrite(#[from] toml::ser::Error),
+    #[error("checkpoint is for {0} batches, got {1}")]
+    CheckpointMismatch(usize, usize),
+    #[error("batch {0}: {1}")]
+    Client(usize, Box<ClientError>),
+    #[error("batch {0} failed: {1}")]
+    Failed(usize, TransactionError),
+}
+
+/// Progress of one run, stored as toml next to the work it describes.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Checkpoint {
+    pub total: usize,
+    // every batch before this one is confirmed.
+    pub next_index: usize,
+}
+
+impl Checkpoint {
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Checkpoint>, BatchError> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Some(toml::from_str(&content)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BatchError> {
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+pub struct BatchExecutor<'a, T: Signers> {
+    client: &'a RpcClient,
+    payer: &'a Keypair,
+    signers: &'a T,
+    checkpoint: PathBuf,
+    // batches sent per blockhash.
+    pub chunk_size: usize,
+    // resends of one batch whose blockhash expired before it landed.
+    pub max_resends: usize,
+}
+
+impl<'a, T: Signers> BatchExecutor<'a, T> {
+    pub fn new(client: &'a RpcClient, payer: &'a Keypair, signers: &'a T, checkpoint: impl Into<PathBuf>) -> Self {
+        Self { client, payer, signers, checkpoint: checkpoint.into(), chunk_size: 50, max_resends: 3 }
+    }
+
+    /// Sends `instruction_batches` in order, one transaction each, starting after the checkpoint.
+    /// The checkpoint is written after every confirmed batch, so a failed run is resumed by calling
+    /// `run` again with the same batches. Returns the signatures sent by this run.
+    pub async fn run(&self, instruction_batches: &[Vec<Instruction>]) -> Result<Vec<Signature>, BatchError> {
+        let mut checkpoint = match Checkpoint::load(&self.checkpoint)? {
+            Some(checkpoint) if checkpoint.total != instruction_batches.len() => {
+                return Err(BatchError::CheckpointMismatch(checkpoint.total, instruction_batches.len()))
+            }
+            Some(checkpoint) => checkpoint,
+            None => Checkpoint { total: instruction_batches.len(), next_index: 0 },
+        };
+
+        let mut signatures = vec![];
+        while checkpoint.next_index < instruction_batches.len() {
+            let mut blockhash = self.blockhash(checkpoint.next_index).await?;
+            let end = (checkpoint.next_index + self.chunk_size.max(1)).min(instruction_batches.len());
+            for (index, instructions) in instruction_batches.iter().enumerate().take(end).skip(checkpoint.next_index) {
+                let (signature, next_blockhash) = self.send(index, instructions, blockhash).await?;
+                blockhash = next_blockhash;
+                signatures.push(signature);
+                checkpoint.next_index = index + 1;
+                checkpoint.save(&self.checkpoint)?;
+            }
+        }
+        Ok(signatures)
+    }
+
+    // sends one batch until it is confirmed, re-signing only once its blockhash can no longer land.
+    async fn send(&self, index: usize, instructions: &[Instruction], mut blockhash: Hash) -> Result<(Signature, Hash), BatchError> {
+        let mut resends = 0;
+        loop {
+            let mut transaction = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+            transaction.sign(self.signers, blockhash);
+            let signature = transaction.signatures[0];
+            let err = match self.client.send_and_confirm_transaction(&transaction).await {
+                Ok(signature) => return Ok((signature, blockhash)),
+                Err(err) => err,
+            };
+            // confirmation can time out after the transaction landed.
+            match self.client.get_signature_status(&signature).await.map_err(|err| BatchError::Client(index, Box::new(err)))? {
+                Some(Ok(())) => return Ok((signature, blockhash)),
+                Some(Err(error)) => return Err(BatchError::Failed(index, error)),
+                None => {}
+            }
+            let expired = err.get_transaction_error() == Some(TransactionError::BlockhashNotFound)
+                || !self
+                    .client
+                    .is_blockhash_valid(&blockhash, CommitmentConfig::processed())
+                    .await
+                    .map_err(|err| BatchError::Client(index, Box::new(err)))?;
+            if !expired || resends >= self.max_resends {
+                return Err(BatchError::Client(index, Box::new(err)));
+            }
+            resends += 1;
+            blockhash = self.blockhash(index).await?;
+        }
+    }
+
+    async fn blockhash(&self, index: usize) -> Result<Hash, BatchError> {
+        self.client.get_latest_blockhash().await.map_err(|err| BatchError::Client(index, Box::new(err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_file() {
+        let path = std::env::temp_dir().join(format!("brc20-batch-{}.toml", std::process::id()));
+        assert_eq!(None, Checkpoint::load(&path).unwrap());
+
+        let checkpoint = Checkpoint { total: 10, next_index: 4 };
+        checkpoint.save(&path).unwrap();
+        assert_eq!(Some(checkpoint), Checkpoint::load(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+//! Builders for the committee instructions integrators send most. Every PDA is derived from the
+//! parts given and `build` fails on a missing part, instead of a positional argument in the wrong
+//! place turning into an account mismatch on chain.
+
+use borsh::BorshSerialize;
+use brc20_oracle::message::{signing_message_for_asset, signing_message_for_committee, signing_message_for_network_committee};
+use brc20_oracle::types::{AttestationSource, BitcoinNetwork, Brc20Amount, Brc20Asset, Brc20OracleInstruction, Committee};
+use brc20_oracle::accounts::{InsertAccounts, SetCommitteeAccounts};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program::{system_program, sysvar};
+use solana_sdk::signer::{keypair::Keypair, Signer};
+use crate::ed25519::ed25519_ix;
+use crate::instruction::{
+    find_asset_address, find_committee_address, find_head_address, find_network_committee_address, find_request_address,
+    find_stats_address,
+};
+
+#[derive(Debug, Eq, thiserror::Error, PartialEq)]
+pub enum BuildError {
+    #[error("missing {0}")]
+    Missing(&'static str),
+    #[error("signature must be 64 bytes, got {0}")]
+    InvalidSignature(usize),
+    #[error("signature doesn't verify against {0}")]
+    WrongSigner(Pubkey),
+}
+
+// who signs the committee message: a keypair at hand, or a signature made elsewhere (e.g. an HSM).
+enum Signing<'a> {
+    Keypair(&'a Keypair),
+    Signature(Pubkey, Vec<u8>),
+}
+
+impl Signing<'_> {
+    fn sign(&self, message: &[u8]) -> Result<(Instruction, Vec<u8>), BuildError> {
+        let (pubkey, signature) = match self {
+            Signing::Keypair(keypair) => (keypair.pubkey(), keypair.sign_message(message).as_ref().to_vec()),
+            Signing::Signature(pubkey, signature) => (*pubkey, signature.clone()),
+        };
+        let bytes: [u8; 64] = signature.as_slice().try_into().map_err(|_| BuildError::InvalidSignature(signature.len()))?;
+        if !solana_sdk::signature::Signature::from(bytes).verify(pubkey.as_ref(), message) {
+            return Err(BuildError::WrongSigner(pubkey));
+        }
+        Ok((ed25519_ix(&pubkey, &bytes, message), signature))
+    }
+}
+
+/// `Insert` of an amount into a pending request.
+/// ```ignore
+/// let ixs = InsertBuilder::new(program_id).payer(payer).request(request).amount(amount).committee(&committee).build()?;
+/// ```
+pub struct InsertBuilder<'a> {
+    program_id: Pubkey,
+    payer: Option<Pubkey>,
+    request: Option<Brc20Asset>,
+    amount: Option<Brc20Amount>,
+    confidence: Option<Brc20Amount>,
+    source: AttestationSource,
+    stats: bool,
+    signing: Option<Signing<'a>>,
+}
+
+impl<'a> InsertBuilder<'a> {
+    pub fn new(program_id: Pubkey) -> Self {
+        Self {
+            program_id,
+            payer: None,
+            request: None,
+            amount: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            stats: false,
+            signing: None,
+        }
+    }
+
+    pub fn payer(mut self, payer: Pubkey) -> Self {
+        self.payer = Some(payer);
+        self
+    }
+
+    /// The pending asset as read from chain, the signed message covers all of it.
+    pub fn request(mut self, request: Brc20Asset) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    pub fn amount(mut self, amount: Brc20Amount) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    pub fn confidence(mut self, confidence: Brc20Amount) -> Self {
+        self.confidence = Some(confidence);
+        self
+    }
+
+    pub fn source(mut self, source: AttestationSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Counts the insert in the global stats account. That account is write locked by every insert
+    /// passing it, so these run one at a time, leave it out on the hot path.
+    pub fn stats(mut self) -> Self {
+        self.stats = true;
+        self
+    }
+
+    /// Signs with the committee key of the request's network.
+    pub fn committee(mut self, committee: &'a Keypair) -> Self {
+        self.signing = Some(Signing::Keypair(committee));
+        self
+    }
+
+    /// Signature of `message` made by `committee` elsewhere.
+    pub fn signature(mut self, committee: Pubkey, signature: Vec<u8>) -> Self {
+        self.signing = Some(Signing::Signature(committee, signature));
+        self
+    }
+
+    /// Message the committee signs, for `signature`.
+    pub fn message(&self) -> Result<Vec<u8>, BuildError> {
+        let request = self.request.as_ref().ok_or(BuildError::Missing("request"))?;
+        let amount = self.amount.ok_or(BuildError::Missing("amount"))?;
+        let asset_address = find_asset_address(&self.program_id, &request.key).0;
+        Ok(signing_message_for_asset(&asset_address, request, amount, self.confidence, self.source).unwrap())
+    }
+
+    pub fn build(self) -> Result<Vec<Instruction>, BuildError> {
+        let message = self.message()?;
+        let payer = self.payer.ok_or(BuildError::Missing("payer"))?;
+        let (verify_instruction, signature) = self.signing.as_ref().ok_or(BuildError::Missing("committee"))?.sign(&message)?;
+        let (program_id, request) = (self.program_id, self.request.unwrap());
+        let key = request.key.clone();
+        let (asset_address, _) = find_asset_address(&program_id, &key);
+        let accounts = InsertAccounts {
+            committee: find_network_committee_address(&program_id, key.network()).0,
+            asset: asset_address,
+            ix_sysvar: sysvar::instructions::id(),
+            payer,
+            head: find_head_address(&program_id, &key.tick, &key.owner).0,
+            system_program: system_program::id(),
+            stats: self.stats.then(|| find_stats_address(&program_id).0),
+            request: Some(find_request_address(&program_id, &asset_address, &request.requester).0),
+        }
+        .to_account_metas();
+        let data = Brc20OracleInstruction::Insert(key, self.amount.unwrap(), self.confidence, self.source, signature)
+            .try_to_vec()
+            .unwrap();
+        Ok(vec![verify_instruction, Instruction { program_id, accounts, data }])
+    }
+}
+
+/// `SetCommittee`, or `SetNetworkCommittee` once a network is given. The first committee is signed
+/// by the payer, later ones by the current committee.
+pub struct CommitteeBuilder<'a> {
+    program_id: Pubkey,
+    payer: Option<Pubkey>,
+    network: Option<BitcoinNetwork>,
+    id: Option<u8>,
+    new_committee: Option<Pubkey>,
+    signing: Option<Signing<'a>>,
+}
+
+impl<'a> CommitteeBuilder<'a> {
+    pub fn new(program_id: Pubkey) -> Self {
+        Self { program_id, payer: None, network: None, id: None, new_committee: None, signing: None }
+    }
+
+    pub fn payer(mut self, payer: Pubkey) -> Self {
+        self.payer = Some(payer);
+        self
+    }
+
+    pub fn network(mut self, network: BitcoinNetwork) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    pub fn id(mut self, id: u8) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn new_committee(mut self, new_committee: Pubkey) -> Self {
+        self.new_committee = Some(new_committee);
+        self
+    }
+
+    /// Current committee, or the payer keypair for the first one.
+    pub fn signer(mut self, signer: &'a Keypair) -> Self {
+        self.signing = Some(Signing::Keypair(signer));
+        self
+    }
+
+    /// Signature of `message` made by `signer` elsewhere.
+    pub fn signature(mut self, signer: Pubkey, signature: Vec<u8>) -> Self {
+        self.signing = Some(Signing::Signature(signer, signature));
+        self
+    }
+
+    /// Message the current committee signs, for `signature`.
+    pub fn message(&self) -> Result<Vec<u8>, BuildError> {
+        let id = self.id.ok_or(BuildError::Missing("id"))?;
+        let new_committee = self.new_committee.ok_or(BuildError::Missing("new committee"))?;
+        Ok(match self.network {
+            Some(network) => signing_message_for_network_committee(network, id, &new_committee),
+            None => signing_message_for_committee(id, &new_committee),
+        }
+        .unwrap())
+    }
+
+    pub fn build(self) -> Result<Vec<Instruction>, BuildError> {
+        let message = self.message()?;
+        let payer = self.payer.ok_or(BuildError::Missing("payer"))?;
+        let (verify_instruction, signature) = self.signing.as_ref().ok_or(BuildError::Missing("signer"))?.sign(&message)?;
+        let committee_address = match self.network {
+            Some(network) => find_network_committee_address(&self.program_id, network).0,
+            None => find_committee_address(&self.program_id).0,
+        };
+        let accounts = SetCommitteeAccounts {
+            payer,
+            committee: committee_address,
+            system_program: system_program::id(),
+            ix_sysvar: sysvar::instructions::id(),
+        }
+        .to_account_metas();
+        let committee = Committee {
+            id: self.id.unwrap(),
+            address: self.new_committee.unwrap(),
+            uid: 0,
+            last_heartbeat_slot: 0,
+            bump: 0,
+        };
+        let data = match self.network {
+            Some(network) => Brc20OracleInstruction::SetNetworkCommittee(network, committee, signature),
+            None => Brc20OracleInstruction::SetCommittee(committee, signature),
+        }
+        .try_to_vec()
+        .unwrap();
+        Ok(vec![verify_instruction, Instruction { program_id: self.program_id, accounts, data }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use brc20_oracle::types::{Brc20Key, BRC20_KEY_VERSION};
+    use super::*;
+
+    fn request() -> Brc20Asset {
+        Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set: false,
+            uid: 0,
+            key: Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() },
+            amount: Brc20Amount::ZERO,
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: 0,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_insert_builder() {
+        let program_id = Pubkey::new_unique();
+        let (payer, committee, request) = (Pubkey::new_unique(), Keypair::new(), request());
+        let builder = || InsertBuilder::new(program_id).payer(payer).request(request.clone()).amount(Brc20Amount(5));
+        assert_eq!(Err(BuildError::Missing("committee")), builder().build().map(|_| ()));
+        assert_eq!(Err(BuildError::Missing("amount")), InsertBuilder::new(program_id).request(request.clone()).build().map(|_| ()));
+
+        let ixs = builder().committee(&committee).build().unwrap();
+        assert_eq!(find_asset_address(&program_id, &request.key).0, ixs[1].accounts[1].pubkey);
+        // the global stats account is only locked when asked for.
+        let stats = find_stats_address(&program_id).0;
+        assert!(ixs[1].accounts.iter().all(|meta| meta.pubkey != stats));
+        assert_eq!(stats, builder().stats().committee(&committee).build().unwrap()[1].accounts[6].pubkey);
+
+        // an external signature lands in the same instruction.
+        let signature = committee.sign_message(&builder().message().unwrap()).as_ref().to_vec();
+        assert_eq!(ixs, builder().signature(committee.pubkey(), signature.clone()).build().unwrap());
+        let other = Pubkey::new_unique();
+        assert_eq!(Err(BuildError::WrongSigner(other)), builder().signature(other, signature).build().map(|_| ()));
+    }
+
+    #[test]
+    fn test_committee_builder() {
+        let program_id = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let new_committee = Pubkey::new_unique();
+        let builder = || CommitteeBuilder::new(program_id).payer(payer.pubkey()).new_committee(new_committee);
+        assert_eq!(Err(BuildError::Missing("id")), builder().signer(&payer).build().map(|_| ()));
+
+        let ixs = builder().id(0).signer(&payer).build().unwrap();
+        assert_eq!(find_committee_address(&program_id).0, ixs[1].accounts[1].pubkey);
+        let ixs = builder().id(0).network(BitcoinNetwork::Testnet).signer(&payer).build().unwrap();
+        assert_eq!(find_network_committee_address(&program_id, BitcoinNetwork::Testnet).0, ixs[1].accounts[1].pubkey);
+    }
+}
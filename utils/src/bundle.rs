@@ -0,0 +1,214 @@
+//! Self-contained evidence of one committee attestation, checkable without RPC access.
+
+use std::str::FromStr;
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::check_ed25519_data;
+use brc20_oracle::message::signing_message_for_asset;
+use brc20_oracle::types::{Brc20Asset, Brc20Key, Brc20OracleInstruction};
+use ed25519_dalek::Verifier;
+use solana_client::client_error::{ClientErrorKind, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::ed25519_program;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::UiTransactionEncoding;
+use crate::instruction::{decode_memos, find_asset_address};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct AttestationBundle {
+    pub asset_address: Pubkey,
+    // owner of the asset account, i.e. the oracle program id.
+    pub program_id: Pubkey,
+    // slot the account data was read at.
+    pub slot: u64,
+    pub account_data: Vec<u8>,
+    // base58 signature of the Insert transaction.
+    pub transaction: String,
+    // the ed25519 verify instruction and the Insert instruction of that transaction.
+    pub ed25519_data: Vec<u8>,
+    pub insert_data: Vec<u8>,
+    // key the ed25519 instruction was signed with.
+    pub committee: Pubkey,
+    // memos attached to the Insert transaction, e.g. an integrator order id.
+    pub memos: Vec<String>,
+}
+
+/// Bundles the asset account of `key` with the Insert transaction that filled it.
+/// Only committee signed inserts can be exported, not snapshot or proof based fills.
+pub async fn export_attestation_bundle(client: &RpcClient, program_id: &Pubkey, key: &Brc20Key) -> Result<AttestationBundle> {
+    let asset_address = find_asset_address(program_id, key).0;
+    let response = client.get_account_with_commitment(&asset_address, client.commitment()).await?;
+    let account = match response.value {
+        Some(account) if &account.owner == program_id => account,
+        _ => return Err(ClientErrorKind::Custom(format!("asset {} not found", asset_address)).into()),
+    };
+    if !Brc20Asset::try_from_slice(&account.data)?.set {
+        return Err(ClientErrorKind::Custom(format!("asset {} is not filled", asset_address)).into());
+    }
+
+    // newest first, the Insert is usually among the last few transactions.
+    for status in client.get_signatures_for_address(&asset_address).await? {
+        if status.err.is_some() {
+            continue;
+        }
+        let signature = Signature::from_str(&status.signature)
+            .map_err(|e| ClientErrorKind::Custom(e.to_string()))?;
+        let confirmed = client.get_transaction(&signature, UiTransactionEncoding::Base64).await?;
+        let transaction = match confirmed.transaction.transaction.decode() {
+            Some(transaction) => transaction,
+            None => continue,
+        };
+        if let Some((ed25519_data, insert_data)) = find_insert(&transaction, program_id) {
+            let committee = match ed25519_data.get(16..48).map(Pubkey::try_from) {
+                Some(Ok(committee)) => committee,
+                _ => continue,
+            };
+            return Ok(AttestationBundle {
+                asset_address,
+                program_id: *program_id,
+                slot: response.context.slot,
+                account_data: account.data,
+                transaction: status.signature,
+                ed25519_data,
+                insert_data,
+                committee,
+                memos: decode_memos(&transaction),
+            });
+        }
+    }
+    Err(ClientErrorKind::Custom(format!("no insert transaction found for {}", asset_address)).into())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum BundleError {
+    #[error("decode error: {0}")]
+    Decode(#[from] std::io::Error),
+    #[error("asset address doesn't match the program id and key")]
+    AddressMismatch,
+    #[error("asset is not filled")]
+    NotFilled,
+    #[error("attestation was revoked by the committee")]
+    Revoked,
+    #[error("instruction is not an Insert of this asset and amount")]
+    InstructionMismatch,
+    #[error("ed25519 instruction doesn't carry the committee, message and signature")]
+    InvalidLayout,
+    #[error("committee signature doesn't verify")]
+    InvalidSignature,
+}
+
+/// Re-runs the program's Insert checks locally: account layout, PDA, ed25519 instruction layout and the
+/// signature itself. Returns the attested asset. Callers still have to compare `bundle.committee` against
+/// a committee key they trust.
+pub fn verify_attestation_bundle(bundle: &AttestationBundle) -> std::result::Result<Brc20Asset, BundleError> {
+    let asset = Brc20Asset::try_from_slice(&bundle.account_data)?;
+    if find_asset_address(&bundle.program_id, &asset.key).0 != bundle.asset_address {
+        return Err(BundleError::AddressMismatch);
+    }
+    if !asset.set {
+        return Err(BundleError::NotFilled);
+    }
+    if asset.revoked.is_some() {
+        return Err(BundleError::Revoked);
+    }
+    let (key, amount, confidence, source, signature) = match Brc20OracleInstruction::try_from_slice(&bundle.insert_data)? {
+        Brc20OracleInstruction::Insert(key, amount, confidence, source, signature) => {
+            (key, amount, confidence, source, signature)
+        }
+        _ => return Err(BundleError::InstructionMismatch),
+    };
+    if key != asset.key || amount != asset.amount || confidence != asset.confidence || source != asset.source {
+        return Err(BundleError::InstructionMismatch);
+    }
+
+    let msg = signing_message_for_asset(&bundle.asset_address, &asset, amount, confidence, source)?;
+    // check_ed25519_data indexes the fixed header directly.
+    if bundle.ed25519_data.len() < 112 {
+        return Err(BundleError::InvalidLayout);
+    }
+    check_ed25519_data(&bundle.ed25519_data, bundle.committee.as_ref(), &msg, &signature)
+        .map_err(|_| BundleError::InvalidLayout)?;
+    // on chain the ed25519 program checks the signature, here we have to.
+    let public = ed25519_dalek::PublicKey::from_bytes(bundle.committee.as_ref())
+        .map_err(|_| BundleError::InvalidSignature)?;
+    let signature = ed25519_dalek::Signature::try_from(signature.as_slice())
+        .map_err(|_| BundleError::InvalidSignature)?;
+    public.verify(&msg, &signature).map_err(|_| BundleError::InvalidSignature)?;
+    Ok(asset)
+}
+
+// the program reads the ed25519 instruction at index 0.
+fn find_insert(transaction: &VersionedTransaction, program_id: &Pubkey) -> Option<(Vec<u8>, Vec<u8>)> {
+    let keys = transaction.message.static_account_keys();
+    let instructions = transaction.message.instructions();
+    let ed25519 = instructions
+        .first()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&ed25519_program::id()))?;
+    instructions
+        .iter()
+        .find(|ix| {
+            keys.get(ix.program_id_index as usize) == Some(program_id)
+                && matches!(Brc20OracleInstruction::try_from_slice(&ix.data), Ok(Brc20OracleInstruction::Insert(..)))
+        })
+        .map(|ix| (ed25519.data.clone(), ix.data.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use brc20_oracle::types::{AttestationSource, Brc20Amount, BRC20_KEY_VERSION};
+    use solana_sdk::ed25519_instruction::new_ed25519_instruction;
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+    use super::*;
+
+    fn signed_bundle(committee: &Keypair, amount: Brc20Amount) -> AttestationBundle {
+        let program_id = Pubkey::new_unique();
+        let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+        let asset = Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set: true,
+            uid: 7,
+            key: key.clone(),
+            amount,
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: 100,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource { indexer_id: 1, source_version: 2 },
+            revoked: None,
+            bump: 0,
+        };
+        let asset_address = find_asset_address(&program_id, &key).0;
+        let msg = signing_message_for_asset(&asset_address, &asset, amount, None, asset.source).unwrap();
+        let signature = committee.sign_message(&msg).as_ref().to_vec();
+        let ed25519 = new_ed25519_instruction(&ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(), &msg);
+        AttestationBundle {
+            asset_address,
+            program_id,
+            slot: 120,
+            account_data: asset.try_to_vec().unwrap(),
+            transaction: String::new(),
+            ed25519_data: ed25519.data,
+            insert_data: Brc20OracleInstruction::Insert(key, amount, None, asset.source, signature).try_to_vec().unwrap(),
+            committee: committee.pubkey(),
+            memos: vec![],
+        }
+    }
+
+    #[test]
+    fn test_verify_bundle() {
+        let committee = Keypair::new();
+        let bundle = signed_bundle(&committee, Brc20Amount(1000));
+        assert_eq!(Brc20Amount(1000), verify_attestation_bundle(&bundle).unwrap().amount);
+
+        // a different committee key doesn't match the ed25519 instruction.
+        let forged = AttestationBundle { committee: Keypair::new().pubkey(), ..bundle.clone() };
+        assert!(matches!(verify_attestation_bundle(&forged), Err(BundleError::InvalidLayout)));
+
+        // the account can't be swapped for another amount.
+        let other = signed_bundle(&committee, Brc20Amount(1));
+        let swapped = AttestationBundle { account_data: other.account_data, ..bundle };
+        assert!(verify_attestation_bundle(&swapped).is_err());
+    }
+}
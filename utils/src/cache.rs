@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use borsh::BorshDeserialize;
+use brc20_oracle::types::{Brc20Asset, Brc20Key, Committee, TickPolicy};
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use crate::instruction::{find_asset_address, find_committee_address, find_tick_policy_address};
+
+/// Account reads with a per-account TTL, for readers polling the same PDAs at high frequency.
+pub struct CachedRpcClient {
+    client: RpcClient,
+    ttl: Duration,
+    entries: Mutex<HashMap<Pubkey, (Account, Instant)>>,
+}
+
+impl CachedRpcClient {
+    pub fn new(client: RpcClient, ttl: Duration) -> Self {
+        Self { client, ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+
+    pub async fn get_account(&self, pubkey: &Pubkey) -> Result<Account> {
+        if let Some((account, fetched_at)) = self.entries.lock().unwrap().get(pubkey) {
+            if fetched_at.elapsed() < self.ttl {
+                return Ok(account.clone());
+            }
+        }
+        let account = self.client.get_account(pubkey).await?;
+        self.entries.lock().unwrap().insert(*pubkey, (account.clone(), Instant::now()));
+        Ok(account)
+    }
+
+    pub async fn get_committee(&self, program_id: &Pubkey) -> Result<Committee> {
+        let account = self.get_account(&find_committee_address(program_id).0).await?;
+        Ok(Committee::try_from_slice(&account.data)?)
+    }
+
+    pub async fn get_tick_policy(&self, program_id: &Pubkey, tick: &[u8; 4]) -> Result<TickPolicy> {
+        let account = self.get_account(&find_tick_policy_address(program_id, tick).0).await?;
+        Ok(TickPolicy::try_from_slice(&account.data)?)
+    }
+
+    pub async fn get_asset(&self, program_id: &Pubkey, key: &Brc20Key) -> Result<Brc20Asset> {
+        let account = self.get_account(&find_asset_address(program_id, key).0).await?;
+        Ok(Brc20Asset::try_from_slice(&account.data)?)
+    }
+
+    /// Feed an account pushed by a subscription, so it's served fresh without a refetch.
+    pub fn update(&self, pubkey: Pubkey, account: Account) {
+        self.entries.lock().unwrap().insert(pubkey, (account, Instant::now()));
+    }
+
+    pub fn invalidate(&self, pubkey: &Pubkey) {
+        self.entries.lock().unwrap().remove(pubkey);
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use solana_account_decoder::{UiAccount, UiAccountEncoding};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_program_test::tokio;
+    use super::*;
+
+    // a client answering the first getAccountInfo with `account` and failing every other request,
+    // so a second fetch shows up as an error. The node version is asked for once, before the first fetch.
+    fn client_serving(pubkey: &Pubkey, account: &Account) -> RpcClient {
+        let ui_account = UiAccount::encode(pubkey, account, UiAccountEncoding::Base64, None, None);
+        let response = serde_json::json!({ "context": { "slot": 1 }, "value": ui_account });
+        let version = serde_json::json!({ "solana-core": "1.18.26", "feature-set": null });
+        RpcClient::new_mock_with_mocks(
+            "fails".to_string(),
+            HashMap::from([(RpcRequest::GetAccountInfo, response), (RpcRequest::GetVersion, version)]),
+        )
+    }
+
+    fn account(lamports: u64) -> Account {
+        Account { lamports, data: vec![1, 2, 3], owner: Pubkey::new_unique(), executable: false, rent_epoch: 0 }
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let pubkey = Pubkey::new_unique();
+        let cache = CachedRpcClient::new(client_serving(&pubkey, &account(1)), Duration::from_secs(3600));
+        assert_eq!(account(1).lamports, cache.get_account(&pubkey).await.unwrap().lamports);
+        // served from the cache, the client has nothing left to answer with.
+        assert_eq!(account(1).lamports, cache.get_account(&pubkey).await.unwrap().lamports);
+
+        cache.update(pubkey, account(2));
+        assert_eq!(account(2).lamports, cache.get_account(&pubkey).await.unwrap().lamports);
+        cache.invalidate(&pubkey);
+        assert!(cache.get_account(&pubkey).await.is_err());
+
+        // an expired entry is fetched again.
+        let cache = CachedRpcClient::new(client_serving(&pubkey, &account(1)), Duration::ZERO);
+        assert_eq!(account(1).lamports, cache.get_account(&pubkey).await.unwrap().lamports);
+        assert!(cache.get_account(&pubkey).await.is_err());
+    }
+}
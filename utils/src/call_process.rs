@@ -1,14 +1,32 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+use futures::lock::Mutex;
+use futures::stream::{self, StreamExt};
 use solana_program::pubkey::Pubkey;
 use solana_program::instruction::Instruction;
 use solana_sdk::signer::keypair::Keypair;
 use solana_client::client_error::Result;
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
 use solana_sdk::signature::Signature;
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_sdk::account::Account;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
 use solana_sdk::signers::Signers;
-use brc20_oracle::types::Brc20Key;
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::{amount_view, blind_key, is_committee_alive, ASSET_PREFIX, CONSUMER_PREFIX, INDEX_PAGE_CAPACITY};
+use brc20_oracle::types::{
+    AddressAssetMap, AmountDelta, AmountView, AssetHead, AttestationSource, BitcoinNetwork, BlindedAsset, Brc20Amount,
+    Brc20Asset, Brc20Key, Committee, ConsumerTarget, Groth16Proof, Heartbeat, HeightRangeAttestation, HeightRangeKey,
+    IndexEntry, IndexPage, OracleStats, ProgramVersion, RejectReason, RequestRecord, RevokeReason, SatRangeAttestation,
+    SatRangeKey, Snapshot, TickPolicy, TickRegistry, VerifyingKey,
+};
 use crate::instruction::*;
 
 pub async fn call_init_committee(
@@ -31,9 +49,12 @@ pub async fn call_request(
     program_id: &Pubkey,
     payer: &Keypair,
     key: &Brc20Key,
+    ttl: Option<u64>,
 ) -> Result<Signature> {
     let client = RpcClient::new_with_commitment(url.to_string(), commitment);
-    let ixs = request_ix(program_id, payer, key.clone());
+    let committee = get_committee(&client, program_id).await?;
+    let tick_registry = find_tick_registry_address(program_id, &key.tick).0;
+    let ixs = request_ix(program_id, payer, key.clone(), ttl, Some(tick_registry), Some(committee.uid / INDEX_PAGE_CAPACITY));
     process_instruction(&client, payer, &[payer], &ixs).await
 }
 
@@ -43,16 +64,572 @@ pub async fn call_insert(
     payer: &Keypair,
     program_id: &Pubkey,
     committee: &Keypair,
-    uid: u64,
     key: Brc20Key,
-    amount: u128,
+    amount: Brc20Amount,
 ) -> Result<Signature> {
     let client = RpcClient::new_with_commitment(url.to_string(), commitment);
-    let committee_info = find_committee_address(program_id).0;
-    let ixs = insert_ix(program_id, committee, committee_info, uid, key, amount);
+    let request = get_asset(&client, program_id, &key).await?;
+    let ixs = insert_ix(program_id, &payer.pubkey(), committee, request, amount, None, AttestationSource::default());
     process_instruction(&client, payer, &[payer], &ixs).await
 }
 
+pub async fn call_expire(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    key: Brc20Key,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let request = get_asset(&client, program_id, &key).await?;
+    let requesters: Vec<Pubkey> = get_request_records(&client, program_id, &key)
+        .await?
+        .into_iter()
+        .map(|record| record.requester)
+        .collect();
+    let ixs = expire_ix(program_id, &request, &requesters);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn call_insert_delta(
+    url: &str,
+    commitment: CommitmentConfig,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    committee: &Keypair,
+    key: Brc20Key,
+    delta: AmountDelta,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let request = get_asset(&client, program_id, &key).await?;
+    let ixs = insert_delta_ix(program_id, &payer.pubkey(), committee, request, delta);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn call_reject(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+    reason: RejectReason,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let request = get_asset(&client, program_id, &key).await?;
+    let ixs = reject_ix(program_id, committee, request, reason);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+/// Revokes the current attestation of `key`.
+pub async fn call_revoke(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+    reason: RevokeReason,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let asset = get_asset(&client, program_id, &key).await?;
+    let ixs = revoke_ix(program_id, committee, asset, reason);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+/// Re-attests `key` unchanged, moving its `attested_slot` to the current slot.
+pub async fn call_refresh(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let asset = get_asset(&client, program_id, &key).await?;
+    let ixs = refresh_ix(program_id, committee, asset);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn call_register_consumer(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    consumer: &Keypair,
+    target: ConsumerTarget,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = register_consumer_ix(program_id, &payer.pubkey(), &consumer.pubkey(), target);
+    process_instruction(&client, payer, &[payer, consumer], &ixs).await
+}
+
+/// Number of consumers registered for exactly `target`, key registrations don't count towards their tick.
+pub async fn get_consumer_count(client: &RpcClient, program_id: &Pubkey, target: &ConsumerTarget) -> Result<usize> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &CONSUMER_PREFIX)),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(CONSUMER_PREFIX.len(), &target.try_to_vec()?)),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(client.commitment()),
+            // only the count is needed.
+            data_slice: Some(UiDataSliceConfig { offset: 0, length: 0 }),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    Ok(client.get_program_accounts_with_config(program_id, config).await?.len())
+}
+
+pub async fn call_close_request(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    requester: &Keypair,
+    key: Brc20Key,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = close_request_ix(program_id, requester, key);
+    process_instruction(&client, requester, &[requester], &ixs).await
+}
+
+/// Signs a heartbeat for the current slot with the active committee key.
+pub async fn call_heartbeat(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let current = get_committee(&client, program_id).await?;
+    let slot = client.get_slot().await?;
+    let ixs = heartbeat_ix(program_id, committee, Heartbeat { id: current.id, slot }, true);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+/// Returns false when the committee missed its heartbeat window, i.e. the oracle should be treated as down.
+pub async fn check_committee_alive(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+) -> Result<bool> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let committee = get_committee(&client, program_id).await?;
+    let slot = client.get_slot().await?;
+    Ok(is_committee_alive(&committee, slot))
+}
+
+pub async fn call_set_tick_policy(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    admin: &Keypair,
+    committee: &Keypair,
+    policy: TickPolicy,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = set_tick_policy_ix(program_id, payer, &admin.pubkey(), committee, policy);
+    process_instruction(&client, payer, &[payer, admin], &ixs).await
+}
+
+/// Client side `GetAmount`: reads the asset and its tick policy and reports `Stale` when the attestation is too old.
+pub async fn read_amount(client: &RpcClient, program_id: &Pubkey, key: &Brc20Key) -> Result<AmountView> {
+    let asset_address = find_asset_address(program_id, key).0;
+    let policy_address = find_tick_policy_address(program_id, &key.tick).0;
+    let accounts = client.get_multiple_accounts(&[asset_address, policy_address]).await?;
+    let asset = accounts[0].as_ref().and_then(|account| Brc20Asset::try_from_slice(&account.data).ok());
+    let policy = accounts[1].as_ref().and_then(|account| TickPolicy::try_from_slice(&account.data).ok());
+    let slot = client.get_slot().await?;
+    Ok(amount_view(asset.as_ref(), policy.as_ref(), slot))
+}
+
+pub async fn call_attest_sat_range(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    attestation: SatRangeAttestation,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = attest_sat_range_ix(program_id, payer, committee, attestation);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn call_insert_address_entry(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+    amount: Brc20Amount,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = insert_address_entry_ix(program_id, payer, committee, key, amount);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn get_address_map(client: &RpcClient, program_id: &Pubkey, owner: &str) -> Result<AddressAssetMap> {
+    let address_map_address = find_address_map_address(program_id, owner).0;
+    let data = client.get_account_data(&address_map_address).await?;
+    Ok(AddressAssetMap::try_from_slice(&data)?)
+}
+
+/// The newest inserted asset of `owner` for `tick`, whatever height it was requested at.
+pub async fn get_latest_asset(client: &RpcClient, program_id: &Pubkey, tick: &[u8; 4], owner: &str) -> Result<Brc20Asset> {
+    let head_address = find_head_address(program_id, tick, owner).0;
+    let data = client.get_account_data(&head_address).await?;
+    let head = AssetHead::try_from_slice(&data)?;
+    let data = client.get_account_data(&head.asset).await?;
+    Ok(Brc20Asset::try_from_slice(&data)?)
+}
+
+pub async fn call_attest_height_range(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    attestation: HeightRangeAttestation,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = attest_height_range_ix(program_id, payer, committee, attestation);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn get_height_range(client: &RpcClient, program_id: &Pubkey, key: &HeightRangeKey) -> Result<HeightRangeAttestation> {
+    let height_range_address = find_height_range_address(program_id, key).0;
+    let data = client.get_account_data(&height_range_address).await?;
+    Ok(HeightRangeAttestation::try_from_slice(&data)?)
+}
+
+/// Records the running program version in the version account, needed once per deployment and upgrade.
+pub async fn call_get_version(
+    url: &str,
+    commitment: CommitmentConfig,
+    payer: &Keypair,
+    program_id: &Pubkey,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = get_version_ix(program_id, &payer.pubkey());
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn get_version(client: &RpcClient, program_id: &Pubkey) -> Result<ProgramVersion> {
+    let version_address = find_version_address(program_id).0;
+    let data = client.get_account_data(&version_address).await?;
+    Ok(ProgramVersion::try_from_slice(&data)?)
+}
+
+pub async fn get_sat_range(client: &RpcClient, program_id: &Pubkey, key: &SatRangeKey) -> Result<SatRangeAttestation> {
+    let sat_range_address = find_sat_range_address(program_id, key).0;
+    let data = client.get_account_data(&sat_range_address).await?;
+    Ok(SatRangeAttestation::try_from_slice(&data)?)
+}
+
+pub async fn get_committee(client: &RpcClient, program_id: &Pubkey) -> Result<Committee> {
+    let committee_address = find_committee_address(program_id).0;
+    let data = client.get_account_data(&committee_address).await?;
+    Ok(Committee::decode_any_version(&data)?)
+}
+
+/// Insert and heartbeat counters, see `brc20_oracle::latency_bucket` for the latency buckets.
+pub async fn get_oracle_stats(client: &RpcClient, program_id: &Pubkey) -> Result<OracleStats> {
+    let data = client.get_account_data(&find_stats_address(program_id).0).await?;
+    Ok(OracleStats::try_from_slice(&data)?)
+}
+
+pub async fn get_network_committee(client: &RpcClient, program_id: &Pubkey, network: BitcoinNetwork) -> Result<Committee> {
+    let committee_address = find_network_committee_address(program_id, network).0;
+    let data = client.get_account_data(&committee_address).await?;
+    Ok(Committee::decode_any_version(&data)?)
+}
+
+/// Checks `signature` over `message` against the current committee of `network` by simulation,
+/// so a new signer can be tried on a live cluster before a real rotation. Nothing is sent.
+pub async fn call_verify_committee_signature(
+    url: &str,
+    commitment: CommitmentConfig,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    network: BitcoinNetwork,
+    message: Vec<u8>,
+    signature: [u8; 64],
+) -> Result<Simulation> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let committee = get_network_committee(&client, program_id, network).await?;
+    let ixs = verify_committee_signature_ix(program_id, network, &committee.address, message, signature);
+    simulate_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn get_asset(client: &RpcClient, program_id: &Pubkey, key: &Brc20Key) -> Result<Brc20Asset> {
+    let asset_address = find_asset_address(program_id, key).0;
+    let data = client.get_account_data(&asset_address).await?;
+    Ok(Brc20Asset::try_from_slice(&data)?)
+}
+
+/// `requester`'s record of the request for `key`, with the slot it was made at.
+pub async fn get_request_record(client: &RpcClient, program_id: &Pubkey, key: &Brc20Key, requester: &Pubkey) -> Result<RequestRecord> {
+    let asset_address = find_asset_address(program_id, key).0;
+    let data = client.get_account_data(&find_request_address(program_id, &asset_address, requester).0).await?;
+    Ok(RequestRecord::try_from_slice(&data)?)
+}
+
+// asset, requester and created_slot.
+pub(crate) const REQUEST_RECORD_LEN: u64 = 32 + 32 + 8;
+
+/// Records of every requester still waiting on `key`.
+pub async fn get_request_records(client: &RpcClient, program_id: &Pubkey, key: &Brc20Key) -> Result<Vec<RequestRecord>> {
+    let asset_address = find_asset_address(program_id, key).0;
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(REQUEST_RECORD_LEN),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, asset_address.as_ref())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(client.commitment()),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    Ok(client
+        .get_program_accounts_with_config(program_id, config)
+        .await?
+        .into_iter()
+        .filter_map(|(_, account)| RequestRecord::try_from_slice(&account.data).ok())
+        .collect())
+}
+
+/// Slots from `requester`'s request to the insert, `None` while the asset is pending.
+pub async fn get_fulfillment_latency(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    key: &Brc20Key,
+    requester: &Pubkey,
+) -> Result<Option<u64>> {
+    let asset = get_asset(client, program_id, key).await?;
+    let record = get_request_record(client, program_id, key, requester).await?;
+    Ok(Some(asset.attested_slot.saturating_sub(record.created_slot)).filter(|_| asset.set))
+}
+
+/// The blinded request of `key`, recomputed from the salt it was requested with.
+pub async fn get_blinded_asset(client: &RpcClient, program_id: &Pubkey, key: &Brc20Key, salt: &[u8; 32]) -> Result<BlindedAsset> {
+    let blinded = blind_key(&key.canonicalize(), salt)?;
+    let data = client.get_account_data(&find_blinded_asset_address(program_id, &blinded).0).await?;
+    Ok(BlindedAsset::try_from_slice(&data)?)
+}
+
+pub async fn get_index_page(client: &RpcClient, program_id: &Pubkey, page: u64) -> Result<Option<IndexPage>> {
+    let index_page_address = find_index_page_address(program_id, page).0;
+    let response = client.get_account_with_commitment(&index_page_address, client.commitment()).await?;
+    match response.value {
+        Some(account) => Ok(Some(IndexPage::try_from_slice(&account.data)?)),
+        None => Ok(None),
+    }
+}
+
+/// Every indexed asset in uid order, following the pages until one doesn't exist yet.
+pub async fn get_indexed_assets(client: &RpcClient, program_id: &Pubkey) -> Result<Vec<IndexEntry>> {
+    let mut entries = vec![];
+    let mut page = 0;
+    while let Some(index_page) = get_index_page(client, program_id, page).await? {
+        entries.extend(index_page.entries);
+        page += 1;
+    }
+    Ok(entries)
+}
+
+pub async fn get_tick_registry(client: &RpcClient, program_id: &Pubkey, tick: &[u8; 4]) -> Result<TickRegistry> {
+    let tick_registry_address = find_tick_registry_address(program_id, tick).0;
+    let data = client.get_account_data(&tick_registry_address).await?;
+    Ok(TickRegistry::try_from_slice(&data)?)
+}
+
+/// Shard out of `shards` that handles `tick`, stable across workers.
+pub fn tick_shard(tick: &[u8; 4], shards: usize) -> usize {
+    u32::from_le_bytes(*tick) as usize % shards.max(1)
+}
+
+// prefix (5) + set (1) + uid (8) + key.version (1) + key.height (4).
+const ASSET_TICK_OFFSET: usize = 19;
+
+/// Every asset account of `tick`, requested or filled. Meant for monitoring, it scans the whole program.
+pub async fn get_assets_by_tick(client: &RpcClient, program_id: &Pubkey, tick: &[u8; 4]) -> Result<Vec<(Pubkey, Brc20Asset)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &ASSET_PREFIX)),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(ASSET_TICK_OFFSET, tick)),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(client.commitment()),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = client.get_program_accounts_with_config(program_id, config).await?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(pubkey, account)| Brc20Asset::try_from_slice(&account.data).ok().map(|asset| (pubkey, asset)))
+        .collect())
+}
+
+pub async fn call_publish_snapshot(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    snapshot: Snapshot,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = publish_snapshot_ix(program_id, payer, committee, snapshot);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn call_materialize_entry(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    key: Brc20Key,
+    amount: Brc20Amount,
+    proof: Vec<[u8; 32]>,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = materialize_entry_ix(program_id, payer, key, amount, proof);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn call_set_verifying_key(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    vk: VerifyingKey,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = set_verifying_key_ix(program_id, payer, committee, vk);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+pub async fn call_insert_with_proof(
+    url: &str,
+    commitment: CommitmentConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    key: Brc20Key,
+    amount: Brc20Amount,
+    proof: Groth16Proof,
+) -> Result<Signature> {
+    let client = RpcClient::new_with_commitment(url.to_string(), commitment);
+    let ixs = insert_with_proof_ix(program_id, &payer.pubkey(), key, amount, proof, false);
+    process_instruction(&client, payer, &[payer], &ixs).await
+}
+
+// re-fetch the blockhash well before it expires (~60s).
+const BLOCKHASH_REFRESH: Duration = Duration::from_secs(20);
+
+/// Sends each batch as its own transaction, keeping at most `max_in_flight` unconfirmed at once.
+/// Statuses are returned in the order of `instruction_batches`.
+pub async fn submit_all<T: Signers>(
+    client: &RpcClient,
+    payer: &Keypair,
+    signers: &T,
+    instruction_batches: Vec<Vec<Instruction>>,
+    max_in_flight: usize,
+) -> Vec<Result<Signature>> {
+    let blockhash: Mutex<Option<(Hash, Instant)>> = Mutex::new(None);
+    let blockhash = &blockhash;
+    buffered_in_order(instruction_batches, max_in_flight, |instructions| async move {
+        let recent_blockhash = cached_blockhash(client, blockhash).await?;
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(signers, recent_blockhash);
+        client.send_and_confirm_transaction(&transaction).await
+    })
+    .await
+}
+
+// `send` over every item with at most `max_in_flight` pending, results in the order of `items`
+// whichever finishes first. A failed item doesn't stop the others.
+pub(crate) async fn buffered_in_order<T, F, Fut>(items: Vec<T>, max_in_flight: usize, send: F) -> Vec<Result<Signature>>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = Result<Signature>>,
+{
+    stream::iter(items).map(send).buffered(max_in_flight.max(1)).collect().await
+}
+
+pub(crate) async fn cached_blockhash(client: &RpcClient, cache: &Mutex<Option<(Hash, Instant)>>) -> Result<Hash> {
+    let mut cache = cache.lock().await;
+    match *cache {
+        Some((hash, fetched_at)) if fetched_at.elapsed() < BLOCKHASH_REFRESH => Ok(hash),
+        _ => {
+            let hash = client.get_latest_blockhash().await?;
+            *cache = Some((hash, Instant::now()));
+            Ok(hash)
+        }
+    }
+}
+
+/// Result of running a transaction through `simulateTransaction` instead of sending it.
+#[derive(Clone, Debug)]
+pub struct Simulation {
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    // data of every writable account after the simulated transaction, `None` if it would be closed.
+    pub accounts: Vec<(Pubkey, Option<Vec<u8>>)>,
+}
+
+impl Simulation {
+    pub fn is_ok(&self) -> bool {
+        self.err.is_none()
+    }
+}
+
+/// Dry run of `process_instruction`, nothing is sent.
+pub async fn simulate_instruction<T: Signers>(
+    client: &RpcClient,
+    payer: &Keypair,
+    signers: &T,
+    instructions: &[Instruction],
+) -> Result<Simulation> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = client.get_latest_blockhash().await?;
+    transaction.sign(signers, recent_blockhash);
+
+    let writable = writable_accounts(instructions);
+    let config = RpcSimulateTransactionConfig {
+        commitment: Some(client.commitment()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: writable.iter().map(|pubkey| pubkey.to_string()).collect(),
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = client.simulate_transaction_with_config(&transaction, config).await?.value;
+    let accounts = match result.accounts {
+        Some(accounts) => writable
+            .into_iter()
+            .zip(accounts)
+            .map(|(pubkey, account)| (pubkey, account.and_then(|account| account.decode::<Account>()).map(|account| account.data)))
+            .collect(),
+        None => vec![],
+    };
+    Ok(Simulation {
+        err: result.err,
+        logs: result.logs.unwrap_or_default(),
+        units_consumed: result.units_consumed,
+        accounts,
+    })
+}
+
 pub async fn process_instruction<T: Signers>(
     client: &RpcClient,
     payer: &Keypair,
@@ -70,18 +647,48 @@ pub mod call_tests {
     use solana_program_test::tokio;
     use borsh::BorshDeserialize;
     use crate::call_process::*;
+    use crate::keys::KeySource;
+    use brc20_oracle::types::BRC20_KEY_VERSION;
+    use solana_client::client_error::{ClientError, ClientErrorKind};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    pub async fn test_submit_order() {
+        let (in_flight, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let (in_flight, peak) = (&in_flight, &peak);
+        let results = buffered_in_order((0..6u64).collect(), 2, |i| async move {
+            peak.fetch_max(in_flight.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+            // later batches confirm first.
+            tokio::time::sleep(Duration::from_millis(30 - 5 * i)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            match i % 2 {
+                0 => Ok(Signature::from([i as u8; 64])),
+                _ => Err(ClientError::from(ClientErrorKind::Custom(format!("batch {}", i)))),
+            }
+        })
+        .await;
+
+        assert_eq!(2, peak.load(Ordering::SeqCst));
+        assert_eq!(6, results.len());
+        for (i, result) in results.iter().enumerate() {
+            match result {
+                Ok(signature) => assert_eq!(Signature::from([i as u8; 64]), *signature),
+                Err(err) => assert!(err.to_string().ends_with(&format!("batch {}", i))),
+            }
+            assert_eq!(i % 2 == 0, result.is_ok());
+        }
+    }
 
     #[tokio::test]
     #[ignore]
     pub async fn test_init_committee() {
         let url = "https://api.devnet.solana.com";
         let program_id = Pubkey::try_from("CMmMYo674EKUz52kPWmPuAfx1ZH9i4bxBQEKM6NEiZda").unwrap();
-        let payer_sk = [];
-        let payer = Keypair::from_bytes(&payer_sk).unwrap();
+        let payer = KeySource::Env("BRC20_ORACLE_PAYER".to_string()).load().unwrap();
         let committee_pk = hex::decode("02f48c4bda350e728d9952dc209323a7ac2f0a1ffe56f342e40c88eeb90892f7").unwrap();
         let committee = Pubkey::try_from_slice(&committee_pk).unwrap();
 
-        let signature = call_init_committee(url, CommitmentConfig::confirmed(), &program_id, &payer, None, &committee, 0).await.unwrap();
+        call_init_committee(url, CommitmentConfig::confirmed(), &program_id, &payer, None, &committee, 0).await.unwrap();
     }
 
     #[tokio::test]
@@ -89,18 +696,18 @@ pub mod call_tests {
     pub async fn test_request() {
         let url = "https://api.devnet.solana.com";
         let program_id = Pubkey::try_from("CMmMYo674EKUz52kPWmPuAfx1ZH9i4bxBQEKM6NEiZda").unwrap();
-        let payer_sk = [];
-        let payer = Keypair::from_bytes(&payer_sk).unwrap();
+        let payer = KeySource::Env("BRC20_ORACLE_PAYER".to_string()).load().unwrap();
 
         let mut tick = [0u8;4];
         tick.copy_from_slice("ordi".as_bytes());
         let key = Brc20Key {
+            version: BRC20_KEY_VERSION,
             height: 786086,
             tick,
             owner: "bc1qznsaq2279xkyqxteh5q8s90u9fmnkl4n8laqpas0faf8lng0j4gqgm8pm0".to_string(),
         };
 
-        let signature = call_request(url, CommitmentConfig::confirmed(), &program_id, &payer, &key).await.unwrap();
+        call_request(url, CommitmentConfig::confirmed(), &program_id, &payer, &key, None).await.unwrap();
     }
 }
 
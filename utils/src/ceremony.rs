@@ -0,0 +1,117 @@
+//! Committee key ceremony: generate or import the committee keypair, show what operators check
+//! before funding anything, build the genesis `SetCommittee` and write an encrypted backup.
+//! There is no CLI in this repository, a binary only has to wrap these.
+
+use std::fmt;
+use std::path::PathBuf;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signer::keypair::{keypair_from_seed, Keypair};
+use solana_sdk::signer::Signer;
+use crate::instruction::{find_committee_address, init_committee_ix};
+use crate::keys::{decrypt_keypair, write_encrypted_keypair, KeyError};
+
+const SECRET_LEN: usize = 32;
+const KEYPAIR_LEN: usize = 64;
+
+/// What operators compare out of band before the genesis transaction is sent.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationMaterial {
+    pub committee: Pubkey,
+    // committee account the genesis `SetCommittee` creates.
+    pub committee_address: Pubkey,
+    pub program_id: Pubkey,
+}
+
+impl fmt::Display for VerificationMaterial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "program: {}", self.program_id)?;
+        writeln!(f, "committee key: {}", self.committee)?;
+        writeln!(f, "committee account: {}", self.committee_address)
+    }
+}
+
+pub fn generate_committee_keypair() -> Keypair {
+    Keypair::new()
+}
+
+/// Imports a DKG output: a 32 byte ed25519 secret, or a 64 byte secret || public key checked
+/// against the secret.
+pub fn import_committee_keypair(bytes: &[u8]) -> Result<Keypair, KeyError> {
+    let keypair = match bytes.len() {
+        SECRET_LEN | KEYPAIR_LEN => {
+            keypair_from_seed(&bytes[..SECRET_LEN]).map_err(|e| KeyError::InvalidKeypair(e.to_string()))?
+        }
+        len => {
+            return Err(KeyError::InvalidKeypair(format!("expected {} or {} bytes, got {}", SECRET_LEN, KEYPAIR_LEN, len)))
+        }
+    };
+    if bytes.len() == KEYPAIR_LEN && keypair.pubkey().as_ref() != &bytes[SECRET_LEN..] {
+        return Err(KeyError::InvalidKeypair("public key doesn't match the secret".to_string()));
+    }
+    Ok(keypair)
+}
+
+pub fn verification_material(program_id: &Pubkey, committee: &Pubkey) -> VerificationMaterial {
+    VerificationMaterial {
+        committee: *committee,
+        committee_address: find_committee_address(program_id).0,
+        program_id: *program_id,
+    }
+}
+
+/// The genesis `SetCommittee` creating the committee account with id 0. The program doesn't check a
+/// signature on creation, so only `payer` signs.
+pub fn genesis_committee_ix(program_id: &Pubkey, payer: &Keypair, committee: &Pubkey) -> Vec<Instruction> {
+    init_committee_ix(program_id, payer, None, committee, 0)
+}
+
+/// Writes `committee` encrypted under `passphrase` and reads it back, so a backup that can't be
+/// restored fails the ceremony instead of surfacing at the next rotation.
+pub fn backup_committee_keypair(path: &PathBuf, committee: &Keypair, passphrase: &str) -> Result<(), KeyError> {
+    write_encrypted_keypair(path, committee, passphrase)?;
+    let restored = decrypt_keypair(&std::fs::read(path)?, passphrase)?;
+    if restored.pubkey() != committee.pubkey() {
+        return Err(KeyError::Decrypt);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshDeserialize;
+    use brc20_oracle::types::Brc20OracleInstruction;
+    use super::*;
+
+    #[test]
+    fn test_ceremony() {
+        let program_id = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let committee = generate_committee_keypair();
+
+        let imported = import_committee_keypair(&committee.to_bytes()).unwrap();
+        assert_eq!(committee.pubkey(), imported.pubkey());
+        assert_eq!(committee.pubkey(), import_committee_keypair(&committee.to_bytes()[..32]).unwrap().pubkey());
+        let mut mismatched = committee.to_bytes();
+        mismatched[63] ^= 1;
+        assert!(import_committee_keypair(&mismatched).is_err());
+
+        let material = verification_material(&program_id, &committee.pubkey());
+        assert_eq!(find_committee_address(&program_id).0, material.committee_address);
+        let ixs = genesis_committee_ix(&program_id, &payer, &committee.pubkey());
+        assert_eq!(material.committee_address, ixs[1].accounts[1].pubkey);
+        match Brc20OracleInstruction::try_from_slice(&ixs[1].data).unwrap() {
+            Brc20OracleInstruction::SetCommittee(genesis, _) => {
+                assert_eq!(0, genesis.id);
+                assert_eq!(committee.pubkey(), genesis.address);
+            }
+            _ => panic!("genesis isn't a SetCommittee"),
+        }
+
+        let path = std::env::temp_dir().join(format!("committee-{}.enc", committee.pubkey()));
+        backup_committee_keypair(&path, &committee, "correct horse").unwrap();
+        let restored = decrypt_keypair(&std::fs::read(&path).unwrap(), "correct horse").unwrap();
+        assert_eq!(committee.pubkey(), restored.pubkey());
+        std::fs::remove_file(path).unwrap();
+    }
+}
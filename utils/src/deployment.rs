@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use serde::Deserialize;
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use crate::call_process::get_committee;
+
+#[derive(thiserror::Error, Debug)]
+pub enum DeploymentError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid deployments file: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("deployment {0}: invalid {1}")]
+    InvalidField(String, &'static str),
+}
+
+/// One oracle deployment: a cluster endpoint, its program id and the committee expected there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deployment {
+    pub name: String,
+    pub url: String,
+    pub program_id: Pubkey,
+    pub committee: Pubkey,
+    pub commitment: CommitmentConfig,
+    // micro-lamports per compute unit, no priority fee when unset.
+    pub compute_unit_price: Option<u64>,
+}
+
+impl Deployment {
+    pub fn client(&self) -> RpcClient {
+        RpcClient::new_with_commitment(self.url.clone(), self.commitment)
+    }
+
+    /// Whether the on-chain committee is the one this deployment is configured with.
+    pub async fn verify_committee(&self) -> Result<bool> {
+        let committee = get_committee(&self.client(), &self.program_id).await?;
+        Ok(committee.address == self.committee)
+    }
+}
+
+/// Named deployments, so one process can serve devnet, mainnet and fractal variants side by side.
+#[derive(Clone, Debug, Default)]
+pub struct Deployments {
+    deployments: HashMap<String, Deployment>,
+}
+
+impl Deployments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, deployment: Deployment) -> Option<Deployment> {
+        self.deployments.insert(deployment.name.clone(), deployment)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Deployment> {
+        self.deployments.get(name)
+    }
+
+    pub fn by_program_id(&self, program_id: &Pubkey) -> Option<&Deployment> {
+        self.deployments.values().find(|deployment| &deployment.program_id == program_id)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.deployments.keys()
+    }
+
+    /// Reads a deployments file, one table per environment:
+    ///
+    /// ```toml
+    /// [devnet]
+    /// url = "https://api.devnet.solana.com"
+    /// program_id = "..."
+    /// committee = "..."
+    /// commitment = "confirmed"  # optional, defaults to finalized
+    /// compute_unit_price = 1000 # optional
+    /// ```
+    pub fn load(path: impl AsRef<Path>) -> std::result::Result<Self, DeploymentError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(content: &str) -> std::result::Result<Self, DeploymentError> {
+        let entries: HashMap<String, DeploymentEntry> = toml::from_str(content)?;
+        let mut deployments = Self::new();
+        for (name, entry) in entries {
+            let program_id = Pubkey::from_str(&entry.program_id)
+                .map_err(|_| DeploymentError::InvalidField(name.clone(), "program_id"))?;
+            let committee = Pubkey::from_str(&entry.committee)
+                .map_err(|_| DeploymentError::InvalidField(name.clone(), "committee"))?;
+            let commitment = match entry.commitment {
+                Some(commitment) => CommitmentConfig::from_str(&commitment)
+                    .map_err(|_| DeploymentError::InvalidField(name.clone(), "commitment"))?,
+                None => CommitmentConfig::finalized(),
+            };
+            deployments.add(Deployment {
+                name,
+                url: entry.url,
+                program_id,
+                committee,
+                commitment,
+                compute_unit_price: entry.compute_unit_price,
+            });
+        }
+        Ok(deployments)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeploymentEntry {
+    url: String,
+    program_id: String,
+    committee: String,
+    commitment: Option<String>,
+    compute_unit_price: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_by_name_and_program_id() {
+        let devnet = Deployment {
+            name: "devnet".to_string(),
+            url: "https://api.devnet.solana.com".to_string(),
+            program_id: Pubkey::new_unique(),
+            committee: Pubkey::new_unique(),
+            commitment: CommitmentConfig::confirmed(),
+            compute_unit_price: None,
+        };
+        let mainnet = Deployment {
+            name: "mainnet".to_string(),
+            url: "https://api.mainnet-beta.solana.com".to_string(),
+            program_id: Pubkey::new_unique(),
+            committee: Pubkey::new_unique(),
+            commitment: CommitmentConfig::finalized(),
+            compute_unit_price: Some(1000),
+        };
+        let mut deployments = Deployments::new();
+        deployments.add(devnet.clone());
+        deployments.add(mainnet.clone());
+
+        assert_eq!(Some(&devnet), deployments.get("devnet"));
+        assert_eq!(Some(&mainnet), deployments.by_program_id(&mainnet.program_id));
+        assert_eq!(None, deployments.get("fractal"));
+    }
+
+    #[test]
+    fn test_load_from_toml() {
+        let program_id = Pubkey::new_unique();
+        let committee = Pubkey::new_unique();
+        let content = format!(
+            "[devnet]\nurl = \"https://api.devnet.solana.com\"\nprogram_id = \"{}\"\ncommittee = \"{}\"\ncommitment = \"confirmed\"\ncompute_unit_price = 1000\n",
+            program_id, committee,
+        );
+        let deployments = Deployments::from_toml(&content).unwrap();
+        let devnet = deployments.get("devnet").unwrap();
+        assert_eq!(program_id, devnet.program_id);
+        assert_eq!(committee, devnet.committee);
+        assert_eq!(CommitmentConfig::confirmed(), devnet.commitment);
+        assert_eq!(Some(1000), devnet.compute_unit_price);
+
+        let bad = content.replace(&program_id.to_string(), "not-a-pubkey");
+        assert!(matches!(Deployments::from_toml(&bad), Err(DeploymentError::InvalidField(_, "program_id"))));
+    }
+}
@@ -0,0 +1,128 @@
+//! Compares the assets of two deployments, e.g. before and after a migration or a devnet mirror
+//! against mainnet. Read only, the two program ids may live on different clusters.
+
+use std::collections::HashMap;
+use std::fmt;
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::types::{Brc20Amount, Brc20Asset, Brc20Key};
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use crate::call_process::get_indexed_assets;
+
+// accounts per getMultipleAccounts call.
+const ACCOUNTS_PER_CALL: usize = 100;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AssetDiff {
+    pub only_left: Vec<Brc20Key>,
+    pub only_right: Vec<Brc20Key>,
+    // filled on both sides with different amounts, (key, left, right).
+    pub amount_mismatch: Vec<(Brc20Key, Brc20Amount, Brc20Amount)>,
+    // filled on one side and still pending on the other.
+    pub fill_mismatch: Vec<Brc20Key>,
+}
+
+impl AssetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_left.is_empty() && self.only_right.is_empty() && self.amount_mismatch.is_empty() && self.fill_mismatch.is_empty()
+    }
+}
+
+impl fmt::Display for AssetDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for key in &self.only_left {
+            writeln!(f, "- {:?}", key)?;
+        }
+        for key in &self.only_right {
+            writeln!(f, "+ {:?}", key)?;
+        }
+        for (key, left, right) in &self.amount_mismatch {
+            writeln!(f, "~ {:?}: {} -> {}", key, left.0, right.0)?;
+        }
+        for key in &self.fill_mismatch {
+            writeln!(f, "? {:?}: filled on one side only", key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Diffs two sets of assets by key. Results keep the order of `left`, then of `right`.
+pub fn diff_assets(left: &[Brc20Asset], right: &[Brc20Asset]) -> AssetDiff {
+    let encode = |key: &Brc20Key| key.try_to_vec().unwrap();
+    let by_key: HashMap<Vec<u8>, &Brc20Asset> = right.iter().map(|asset| (encode(&asset.key), asset)).collect();
+    let left_keys: HashMap<Vec<u8>, ()> = left.iter().map(|asset| (encode(&asset.key), ())).collect();
+
+    let mut diff = AssetDiff::default();
+    for asset in left {
+        match by_key.get(&encode(&asset.key)) {
+            None => diff.only_left.push(asset.key.clone()),
+            Some(other) if asset.set != other.set => diff.fill_mismatch.push(asset.key.clone()),
+            Some(other) if asset.set && asset.amount != other.amount => {
+                diff.amount_mismatch.push((asset.key.clone(), asset.amount, other.amount))
+            }
+            Some(_) => {}
+        }
+    }
+    diff.only_right = right.iter().filter(|asset| !left_keys.contains_key(&encode(&asset.key))).map(|asset| asset.key.clone()).collect();
+    diff
+}
+
+/// `diff_assets` over every indexed asset of both deployments.
+pub async fn diff_deployments(
+    left_client: &RpcClient,
+    left_program_id: &Pubkey,
+    right_client: &RpcClient,
+    right_program_id: &Pubkey,
+) -> Result<AssetDiff> {
+    let left = get_all_assets(left_client, left_program_id).await?;
+    let right = get_all_assets(right_client, right_program_id).await?;
+    Ok(diff_assets(&left, &right))
+}
+
+// indexed assets that still exist, closed ones are skipped.
+async fn get_all_assets(client: &RpcClient, program_id: &Pubkey) -> Result<Vec<Brc20Asset>> {
+    let addresses: Vec<Pubkey> = get_indexed_assets(client, program_id).await?.into_iter().map(|entry| entry.asset).collect();
+    let mut assets = vec![];
+    for chunk in addresses.chunks(ACCOUNTS_PER_CALL) {
+        let accounts = client.get_multiple_accounts_with_commitment(chunk, client.commitment()).await?.value;
+        assets.extend(accounts.into_iter().flatten().filter_map(|account| Brc20Asset::try_from_slice(&account.data).ok()));
+    }
+    Ok(assets)
+}
+
+#[cfg(test)]
+mod tests {
+    use brc20_oracle::types::{AttestationSource, BRC20_KEY_VERSION};
+    use super::*;
+
+    fn asset(owner: &str, set: bool, amount: u128) -> Brc20Asset {
+        Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set,
+            uid: 0,
+            key: Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: owner.to_string() },
+            amount: Brc20Amount(amount),
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: 0,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_diff_assets() {
+        let left = vec![asset("a", true, 100), asset("b", true, 100), asset("c", false, 0), asset("d", true, 5)];
+        let right = vec![asset("a", true, 100), asset("b", true, 90), asset("c", true, 7), asset("e", false, 0)];
+        let diff = diff_assets(&left, &right);
+        assert_eq!(vec![asset("d", true, 5).key], diff.only_left);
+        assert_eq!(vec![asset("e", false, 0).key], diff.only_right);
+        assert_eq!(vec![(asset("b", true, 0).key, Brc20Amount(100), Brc20Amount(90))], diff.amount_mismatch);
+        assert_eq!(vec![asset("c", false, 0).key], diff.fill_mismatch);
+        assert!(diff_assets(&left, &left).is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+//! ed25519 program instructions for the committee signatures the oracle checks. The oracle reads
+//! a single signature from instruction 0, so every verify instruction here carries exactly one.
+
+use solana_program::ed25519_program;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signer::{keypair::Keypair, Signer};
+
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+// typed message (header then value) right after the borsh variant tag of the oracle instruction.
+const ORACLE_MESSAGE_OFFSET: usize = 1;
+
+/// Data of an ed25519 instruction verifying one signature, byte for byte what the SDK's
+/// `new_ed25519_instruction` builds. Only plain bytes in and out, so signers without the SDK
+/// (wasm, HSM firmware) can build the verify instruction too.
+pub fn ed25519_instruction_data(pubkey: &[u8; 32], signature: &[u8; 64], message: &[u8]) -> Vec<u8> {
+    let public_key_offset = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN;
+    let signature_offset = public_key_offset + PUBKEY_LEN;
+    let message_data_offset = signature_offset + SIGNATURE_LEN;
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    data.push(1);
+    data.push(0);
+    for field in [
+        signature_offset as u16,
+        u16::MAX,
+        public_key_offset as u16,
+        u16::MAX,
+        message_data_offset as u16,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey);
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    data
+}
+
+pub fn ed25519_ix(pubkey: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Instruction {
+    Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data: ed25519_instruction_data(&pubkey.to_bytes(), signature, message),
+    }
+}
+
+/// Data of an ed25519 instruction verifying one signature over `message_len` bytes at
+/// `message_offset` of instruction `message_instruction_index`, the layout the oracle accepts for
+/// snapshots and range attestations.
+pub fn referenced_ed25519_instruction_data(
+    pubkey: &[u8; 32],
+    signature: &[u8; 64],
+    message_instruction_index: u16,
+    message_offset: u16,
+    message_len: u16,
+) -> Vec<u8> {
+    let public_key_offset = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN;
+    let signature_offset = public_key_offset + PUBKEY_LEN;
+    let mut data = Vec::with_capacity(signature_offset + SIGNATURE_LEN);
+    data.push(1);
+    data.push(0);
+    for field in [
+        signature_offset as u16,
+        u16::MAX,
+        public_key_offset as u16,
+        u16::MAX,
+        message_offset,
+        message_len,
+        message_instruction_index,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey);
+    data.extend_from_slice(signature);
+    data
+}
+
+/// Rewrites the verify instruction at the head of `ixs`, as `publish_snapshot_ix` and the range
+/// attestation builders return them, to read the message from the oracle instruction after it
+/// instead of carrying a copy. Panics if that instruction doesn't start with the signed message.
+pub fn with_referenced_message(mut ixs: Vec<Instruction>) -> Vec<Instruction> {
+    let data = &ixs[0].data;
+    let message_start = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN + PUBKEY_LEN + SIGNATURE_LEN;
+    let message = &data[message_start..];
+    assert_eq!(
+        Some(message),
+        ixs[1].data.get(ORACLE_MESSAGE_OFFSET..ORACLE_MESSAGE_OFFSET + message.len()),
+        "oracle instruction doesn't carry the signed message"
+    );
+    let pubkey: [u8; 32] = data[16..48].try_into().unwrap();
+    let signature: [u8; 64] = data[48..112].try_into().unwrap();
+    ixs[0].data = referenced_ed25519_instruction_data(&pubkey, &signature, 1, ORACLE_MESSAGE_OFFSET as u16, message.len() as u16);
+    ixs
+}
+
+/// Signs `message` with `signer` and returns the verify instruction along with the signature the
+/// oracle instruction carries.
+pub fn signed_ed25519_ix(signer: &Keypair, message: &[u8]) -> (Instruction, Vec<u8>) {
+    let signature: [u8; 64] = signer.sign_message(message).as_ref().try_into().unwrap();
+    (ed25519_ix(&signer.pubkey(), &signature, message), signature.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::ed25519_instruction::new_ed25519_instruction;
+    use super::*;
+
+    #[test]
+    fn test_instruction_data_matches_sdk() {
+        let signer = Keypair::new();
+        let msg = b"brc20 oracle".to_vec();
+        let expected = new_ed25519_instruction(&ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap(), &msg);
+        let (instruction, signature) = signed_ed25519_ix(&signer, &msg);
+        assert_eq!(expected, instruction);
+        assert_eq!(signer.sign_message(&msg).as_ref(), signature.as_slice());
+    }
+
+    #[test]
+    fn test_referenced_message() {
+        let signer = Keypair::new();
+        let msg = vec![7u8; 300];
+        let (verify_instruction, signature) = signed_ed25519_ix(&signer, &msg);
+        let oracle_instruction = Instruction { program_id: Pubkey::new_unique(), accounts: vec![], data: [&[2u8][..], &msg, &signature].concat() };
+        let ixs = with_referenced_message(vec![verify_instruction, oracle_instruction]);
+        assert_eq!(112, ixs[0].data.len());
+        // message offset, size and instruction index.
+        assert_eq!([1, 0, 44, 1, 1, 0], ixs[0].data[10..16]);
+        assert_eq!(signature.as_slice(), &ixs[0].data[48..112]);
+    }
+}
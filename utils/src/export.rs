@@ -0,0 +1,252 @@
+//! Plain text exports of oracle data for downstream systems that don't read borsh.
+
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use borsh::BorshDeserialize;
+use brc20_oracle::types::{Brc20Asset, Brc20OracleInstruction};
+use brc20_oracle::INDEX_PAGE_CAPACITY;
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use crate::bundle::{AttestationBundle, BundleError};
+use crate::call_process::get_index_page;
+
+// bumped on any change that isn't a new optional field.
+pub const ATTESTATION_SCHEMA: &str = "brc20-oracle/attestation/v1";
+
+/// Canonical JSON form of one committee attestation. Pubkeys and signatures are base58, amounts
+/// are decimal strings since they don't fit a JSON number.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AttestationJson {
+    pub schema: String,
+    pub program_id: String,
+    pub asset: String,
+    pub key: KeyJson,
+    pub amount: String,
+    pub confidence: Option<String>,
+    pub indexer_id: u8,
+    pub source_version: u8,
+    pub committee: String,
+    pub signature: String,
+    // slot the Insert landed in.
+    pub attested_slot: u64,
+    // slot the account was read at.
+    pub slot: u64,
+    pub transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyJson {
+    pub version: u8,
+    pub height: u32,
+    pub tick: String,
+    pub owner: String,
+}
+
+impl AttestationJson {
+    /// Decodes the bundle's account and Insert data. Doesn't check the signature, see
+    /// `verify_attestation_bundle`.
+    pub fn from_bundle(bundle: &AttestationBundle) -> Result<Self, BundleError> {
+        let asset = Brc20Asset::try_from_slice(&bundle.account_data)?;
+        let signature = match Brc20OracleInstruction::try_from_slice(&bundle.insert_data)? {
+            Brc20OracleInstruction::Insert(_, _, _, _, signature) => signature,
+            _ => return Err(BundleError::InstructionMismatch),
+        };
+        let signature = Signature::try_from(signature.as_slice()).map_err(|_| BundleError::InvalidSignature)?;
+        Ok(AttestationJson {
+            schema: ATTESTATION_SCHEMA.to_string(),
+            program_id: bundle.program_id.to_string(),
+            asset: bundle.asset_address.to_string(),
+            key: KeyJson {
+                version: asset.key.version,
+                height: asset.key.height,
+                tick: String::from_utf8_lossy(&asset.key.tick).to_string(),
+                owner: asset.key.owner.clone(),
+            },
+            amount: asset.amount.0.to_string(),
+            confidence: asset.confidence.map(|confidence| confidence.0.to_string()),
+            indexer_id: asset.source.indexer_id,
+            source_version: asset.source.source_version,
+            committee: bundle.committee.to_string(),
+            signature: signature.to_string(),
+            attested_slot: asset.attested_slot,
+            slot: bundle.slot,
+            transaction: bundle.transaction.clone(),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+pub const ASSET_CSV_HEADER: &str = "asset,uid,height,tick,owner,amount,set,attested_slot\n";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid cursor file: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("can't write cursor file: {0}")]
+    TomlWrite(#[from] toml::ser::Error),
+    #[error("client error: {0}")]
+    Client(Box<ClientError>),
+}
+
+impl From<ClientError> for ExportError {
+    fn from(err: ClientError) -> Self {
+        ExportError::Client(Box::new(err))
+    }
+}
+
+/// Resume point of a CSV export, stored as toml next to the CSV.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ExportCursor {
+    // next index page to export.
+    pub page: u64,
+    // CSV length once every page before `page` was written, anything after it is rewritten.
+    pub bytes: u64,
+}
+
+impl ExportCursor {
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<ExportCursor>, ExportError> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Some(toml::from_str(&content)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ExportError> {
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+pub fn asset_csv_row(address: &Pubkey, asset: &Brc20Asset) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{}\n",
+        address,
+        asset.uid,
+        asset.key.height,
+        csv_field(&String::from_utf8_lossy(&asset.key.tick)),
+        csv_field(&asset.key.owner),
+        asset.amount.0,
+        asset.set,
+        asset.attested_slot,
+    )
+}
+
+// quotes a field holding a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Appends every indexed asset to `csv_path`, one index page at a time, so memory stays at one page
+/// whatever the number of assets. Progress is saved to `cursor_path` after each full page, calling it
+/// again with the same paths resumes there, and once done picks up assets requested since. Closed
+/// assets are skipped. Returns the number of rows written by this call.
+pub async fn export_assets_csv(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    csv_path: impl AsRef<Path>,
+    cursor_path: impl AsRef<Path>,
+) -> Result<u64, ExportError> {
+    let mut file = OpenOptions::new().create(true).write(true).truncate(false).open(&csv_path)?;
+    let mut cursor = match ExportCursor::load(&cursor_path)? {
+        // the CSV must still hold everything the cursor counted.
+        Some(cursor) if file.metadata()?.len() >= cursor.bytes => cursor,
+        _ => {
+            file.set_len(0)?;
+            file.write_all(ASSET_CSV_HEADER.as_bytes())?;
+            ExportCursor { page: 0, bytes: ASSET_CSV_HEADER.len() as u64 }
+        }
+    };
+    // drops rows of a page that was being written when the last run stopped.
+    file.set_len(cursor.bytes)?;
+    file.seek(SeekFrom::Start(cursor.bytes))?;
+
+    let mut rows = 0;
+    while let Some(index_page) = get_index_page(client, program_id, cursor.page).await? {
+        let addresses: Vec<Pubkey> = index_page.entries.iter().map(|entry| entry.asset).collect();
+        let accounts = client.get_multiple_accounts_with_commitment(&addresses, client.commitment()).await?.value;
+        let mut chunk = String::new();
+        for (address, account) in addresses.iter().zip(accounts) {
+            if let Some(asset) = account.and_then(|account| Brc20Asset::try_from_slice(&account.data).ok()) {
+                chunk.push_str(&asset_csv_row(address, &asset));
+                rows += 1;
+            }
+        }
+        file.write_all(chunk.as_bytes())?;
+        file.sync_data()?;
+        // the last page still fills up, the next run rewrites it instead of moving past.
+        if (index_page.entries.len() as u64) < INDEX_PAGE_CAPACITY {
+            break;
+        }
+        cursor.page += 1;
+        cursor.bytes += chunk.len() as u64;
+        cursor.save(&cursor_path)?;
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+    use brc20_oracle::types::{AttestationSource, Brc20Amount, Brc20Key, BRC20_KEY_VERSION};
+    use solana_program::pubkey::Pubkey;
+    use super::*;
+
+    #[test]
+    fn test_attestation_json() {
+        let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+        let asset = Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set: true,
+            uid: 7,
+            key: key.clone(),
+            amount: Brc20Amount(u128::MAX - 1),
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: 100,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource { indexer_id: 1, source_version: 2 },
+            revoked: None,
+            bump: 0,
+        };
+        let bundle = AttestationBundle {
+            asset_address: Pubkey::new_unique(),
+            program_id: Pubkey::new_unique(),
+            slot: 120,
+            account_data: asset.try_to_vec().unwrap(),
+            transaction: "tx".to_string(),
+            ed25519_data: vec![],
+            insert_data: Brc20OracleInstruction::Insert(key, asset.amount, None, asset.source, vec![3; 64]).try_to_vec().unwrap(),
+            committee: Pubkey::new_unique(),
+            memos: vec![],
+        };
+        let json = AttestationJson::from_bundle(&bundle).unwrap();
+        assert_eq!("ordi", json.key.tick);
+        assert_eq!((u128::MAX - 1).to_string(), json.amount);
+        let text = json.to_json();
+        assert!(text.starts_with(r#"{"schema":"brc20-oracle/attestation/v1","#));
+        assert!(text.contains(r#""confidence":null"#));
+        assert_eq!(json, serde_json::from_str(&text).unwrap());
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!("ordi", csv_field("ordi"));
+        assert_eq!("\"a,b\"", csv_field("a,b"));
+        assert_eq!("\"a\"\"b\"", csv_field("a\"b"));
+    }
+}
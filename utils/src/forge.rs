@@ -0,0 +1,88 @@
+//! Malformed ed25519 verify instructions for security tests of programs built on this oracle.
+
+use solana_program::instruction::Instruction;
+use solana_sdk::ed25519_program;
+use solana_sdk::signer::{keypair::Keypair, Signer};
+
+const HEADER_LEN: u16 = 16;
+const PUBKEY_LEN: u16 = 32;
+const SIGNATURE_LEN: u16 = 64;
+
+/// Header of an ed25519 program instruction with a single signature. Every field can be
+/// tampered with, `canonical` gives the layout the program accepts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Ed25519Layout {
+    pub num_signatures: u8,
+    pub padding: u8,
+    pub signature_offset: u16,
+    pub signature_instruction_index: u16,
+    pub public_key_offset: u16,
+    pub public_key_instruction_index: u16,
+    pub message_data_offset: u16,
+    pub message_data_size: u16,
+    pub message_instruction_index: u16,
+}
+
+impl Ed25519Layout {
+    pub fn canonical(msg_len: usize) -> Self {
+        Self {
+            num_signatures: 1,
+            padding: 0,
+            signature_offset: HEADER_LEN + PUBKEY_LEN,
+            signature_instruction_index: u16::MAX,
+            public_key_offset: HEADER_LEN,
+            public_key_instruction_index: u16::MAX,
+            message_data_offset: HEADER_LEN + PUBKEY_LEN + SIGNATURE_LEN,
+            message_data_size: msg_len as u16,
+            message_instruction_index: u16::MAX,
+        }
+    }
+}
+
+/// Raw ed25519 instruction: `layout` header followed by pubkey, signature and message.
+/// Nothing is checked, the signature doesn't have to match.
+pub fn forged_ed25519_ix(layout: &Ed25519Layout, pubkey: &[u8; 32], signature: &[u8; 64], msg: &[u8]) -> Instruction {
+    let mut data = Vec::with_capacity(HEADER_LEN as usize + 32 + 64 + msg.len());
+    data.push(layout.num_signatures);
+    data.push(layout.padding);
+    data.extend_from_slice(&layout.signature_offset.to_le_bytes());
+    data.extend_from_slice(&layout.signature_instruction_index.to_le_bytes());
+    data.extend_from_slice(&layout.public_key_offset.to_le_bytes());
+    data.extend_from_slice(&layout.public_key_instruction_index.to_le_bytes());
+    data.extend_from_slice(&layout.message_data_offset.to_le_bytes());
+    data.extend_from_slice(&layout.message_data_size.to_le_bytes());
+    data.extend_from_slice(&layout.message_instruction_index.to_le_bytes());
+    data.extend_from_slice(pubkey);
+    data.extend_from_slice(signature);
+    data.extend_from_slice(msg);
+    Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+/// A real signature of `signed_msg` by `signer`, carried with `layout` and `carried_msg`.
+/// Use it to check that a program compares the carried message and offsets, not only the signature.
+pub fn signed_ed25519_ix(signer: &Keypair, layout: &Ed25519Layout, signed_msg: &[u8], carried_msg: &[u8]) -> Instruction {
+    let signature: [u8; 64] = signer.sign_message(signed_msg).as_ref().try_into().unwrap();
+    forged_ed25519_ix(layout, &signer.pubkey().to_bytes(), &signature, carried_msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::ed25519_instruction::new_ed25519_instruction;
+    use super::*;
+
+    #[test]
+    fn test_canonical_layout_matches_sdk() {
+        let signer = Keypair::new();
+        let msg = b"brc20 oracle".to_vec();
+        let expected = new_ed25519_instruction(
+            &ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap(),
+            &msg,
+        );
+        let forged = signed_ed25519_ix(&signer, &Ed25519Layout::canonical(msg.len()), &msg, &msg);
+        assert_eq!(expected, forged);
+    }
+}
@@ -0,0 +1,93 @@
+//! Deterministic memo tags on submissions. A worker restarted after a crash looks for its tag
+//! among the asset's confirmed transactions instead of sending the same insert twice.
+
+use std::str::FromStr;
+use borsh::BorshSerialize;
+use brc20_oracle::types::{AttestationSource, Brc20Amount, Brc20Asset};
+use solana_client::client_error::{ClientErrorKind, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_program::keccak::hash;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::{keypair::Keypair, Signer};
+use crate::call_process::process_instruction;
+use crate::instruction::{find_asset_address, insert_ix, memo_ix};
+
+pub const IDEMPOTENCY_TAG_PREFIX: &str = "brc20-oracle:";
+
+/// Tag of the submission moving `asset` on from `revision`, the `attested_slot` it builds on (0 for
+/// an insert). The uid tells a re-requested asset apart from an expired one with the same key.
+pub fn idempotency_tag(asset: &Brc20Asset, revision: u64) -> String {
+    let seed = (asset.key.clone(), asset.uid, revision).try_to_vec().unwrap();
+    let digest: String = hash(&seed).to_bytes()[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", IDEMPOTENCY_TAG_PREFIX, digest)
+}
+
+/// Newest successful transaction touching `address` whose memo carries `tag`.
+pub async fn find_submitted(client: &RpcClient, address: &Pubkey, tag: &str) -> Result<Option<Signature>> {
+    let config = GetConfirmedSignaturesForAddress2Config {
+        commitment: Some(client.commitment()),
+        ..GetConfirmedSignaturesForAddress2Config::default()
+    };
+    for status in client.get_signatures_for_address_with_config(address, config).await? {
+        // the RPC reports memos as "[len] memo", joined with "; ".
+        if status.err.is_none() && matches!(status.memo.as_deref(), Some(memo) if memo.contains(tag)) {
+            let signature = Signature::from_str(&status.signature).map_err(|e| ClientErrorKind::Custom(e.to_string()))?;
+            return Ok(Some(signature));
+        }
+    }
+    Ok(None)
+}
+
+/// `Insert` tagged with `idempotency_tag`, skipped when a transaction with the same tag already
+/// landed. Safe to call again after a crash, it returns the earlier signature.
+pub async fn insert_once(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    committee: &Keypair,
+    request: Brc20Asset,
+    amount: Brc20Amount,
+) -> Result<Signature> {
+    let asset_address = find_asset_address(program_id, &request.key).0;
+    let tag = idempotency_tag(&request, 0);
+    if let Some(signature) = find_submitted(client, &asset_address, &tag).await? {
+        return Ok(signature);
+    }
+    let mut ixs = insert_ix(program_id, &payer.pubkey(), committee, request, amount, None, AttestationSource::default());
+    ixs.push(memo_ix(&tag));
+    process_instruction(client, payer, &[payer], &ixs).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use brc20_oracle::types::{Brc20Key, BRC20_KEY_VERSION};
+
+    #[test]
+    fn test_idempotency_tag() {
+        let asset = Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set: false,
+            uid: 3,
+            key: Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() },
+            amount: Brc20Amount(0),
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: 0,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        };
+        let tag = idempotency_tag(&asset, 0);
+        assert!(tag.starts_with(IDEMPOTENCY_TAG_PREFIX));
+        assert_eq!(IDEMPOTENCY_TAG_PREFIX.len() + 32, tag.len());
+        // filling the request doesn't change the tag of the insert that filled it.
+        assert_eq!(tag, idempotency_tag(&Brc20Asset { set: true, amount: Brc20Amount(1000), ..asset.clone() }, 0));
+        assert_ne!(tag, idempotency_tag(&Brc20Asset { uid: 4, ..asset.clone() }, 0));
+        assert_ne!(tag, idempotency_tag(&asset, 1));
+    }
+}
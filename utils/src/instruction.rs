@@ -1,12 +1,34 @@
 use borsh::BorshSerialize;
-use brc20_oracle::types::{Brc20Asset, Brc20Key, Brc20OracleInstruction, Committee};
-use brc20_oracle::{ASSET_PREFIX, COMMITTEE_PREFIX};
+use brc20_oracle::accounts::{RefreshAccounts, RejectAccounts, RevokeAccounts};
+use brc20_oracle::{blind_key, INDEX_PAGE_CAPACITY};
+use brc20_oracle::types::{
+    AmountDelta, AttestationSource, BitcoinNetwork, BlindedAsset, Brc20Amount, Brc20Asset, Brc20Key,
+    Brc20OracleInstruction, ConsumerTarget, Groth16Proof, Heartbeat, HeightRangeAttestation, RejectReason,
+    RevokeReason, SatRangeAttestation, Snapshot, TickPolicy, VerifyingKey,
+};
+use brc20_oracle::message::{
+    signing_message_for_address_entry, signing_message_for_blinded_asset, signing_message_for_delta,
+    signing_message_for_heartbeat, signing_message_for_height_range, signing_message_for_refresh,
+    signing_message_for_rejection, signing_message_for_revocation, signing_message_for_sat_range,
+    signing_message_for_snapshot, signing_message_for_tick_policy, signing_message_for_verifying_key, typed_header,
+    MessageKind,
+};
+pub use brc20_oracle::pda::{
+    find_address_map_address, find_asset_address, find_blinded_asset_address, find_committee_address,
+    find_consumer_address, find_head_address, find_height_range_address, find_index_page_address,
+    find_network_committee_address, find_request_address, find_sat_range_address, find_snapshot_address,
+    find_stats_address, find_tick_policy_address, find_tick_registry_address, find_verifying_key_address,
+    find_version_address,
+};
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::pubkey::Pubkey;
-use solana_program::{system_program, sysvar};
-use solana_sdk::ed25519_instruction::new_ed25519_instruction;
+use solana_program::{bpf_loader_upgradeable, system_program, sysvar};
 use solana_sdk::signer::{keypair::Keypair, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use crate::builder::{CommitteeBuilder, InsertBuilder};
+use crate::ed25519::{ed25519_ix, signed_ed25519_ix};
 
+/// Positional shorthand for [CommitteeBuilder], kept for existing callers.
 pub fn init_committee_ix(
     program_id: &Pubkey,
     payer: &Keypair,
@@ -14,112 +36,845 @@ pub fn init_committee_ix(
     new_committee: &Pubkey,
     id: u8,
 ) -> Vec<Instruction> {
+    CommitteeBuilder::new(*program_id)
+        .payer(payer.pubkey())
+        .id(id)
+        .new_committee(*new_committee)
+        .signer(old_committee.unwrap_or(payer))
+        .build()
+        .unwrap()
+}
+
+/// Sets or rotates the committee of `network`, signed by `old_committee` (or the payer for the first one).
+pub fn init_network_committee_ix(
+    program_id: &Pubkey,
+    payer: &Keypair,
+    network: BitcoinNetwork,
+    old_committee: Option<&Keypair>,
+    new_committee: &Pubkey,
+    id: u8,
+) -> Vec<Instruction> {
+    CommitteeBuilder::new(*program_id)
+        .payer(payer.pubkey())
+        .network(network)
+        .id(id)
+        .new_committee(*new_committee)
+        .signer(old_committee.unwrap_or(payer))
+        .build()
+        .unwrap()
+}
+
+/// `tick_registry` is the [TickRegistry] of the key's tick, `find_tick_registry_address`, kept only
+/// when passed. It is write locked, so requests passing it are serialized per tick.
+/// `index_page` is the [IndexPage] the new asset lands in, `committee.uid / INDEX_PAGE_CAPACITY`.
+/// Leave it out when joining an existing asset or when the index isn't kept. The index page can
+/// only follow the tick registry.
+pub fn request_ix(
+    program_id: &Pubkey,
+    payer: &Keypair,
+    key: Brc20Key,
+    ttl: Option<u64>,
+    tick_registry: Option<Pubkey>,
+    index_page: Option<u64>,
+) -> Vec<Instruction> {
+    let key = key.canonicalize();
     let (committee_info_address, _) = find_committee_address(program_id);
-    let accounts = vec![
+    let (asset_address, _) = find_asset_address(program_id, &key);
+    let (request_address, _) = find_request_address(program_id, &asset_address, &payer.pubkey());
+    let mut accounts = vec![
         AccountMeta::new(payer.pubkey(), true),
-        AccountMeta::new(committee_info_address.clone(), false),
+        AccountMeta::new(committee_info_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new(request_address, false),
         AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(tick_registry) = tick_registry {
+        accounts.push(AccountMeta::new(tick_registry, false));
+        accounts.extend(index_page.map(|page| AccountMeta::new(find_index_page_address(program_id, page).0, false)));
+    }
+
+    let data = Brc20OracleInstruction::Request(key, ttl).try_to_vec().unwrap();
+    vec![
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    ]
+}
+
+/// Accounts write locked by `instructions`, in first use order. Transactions only run in parallel
+/// when these don't overlap, so the committee account must never show up here for inserts.
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut writable: Vec<Pubkey> = vec![];
+    for meta in instructions.iter().flat_map(|ix| ix.accounts.iter()) {
+        if meta.is_writable && !writable.contains(&meta.pubkey) {
+            writable.push(meta.pubkey);
+        }
+    }
+    writable
+}
+
+/// Positional shorthand for [InsertBuilder], kept for existing callers.
+pub fn insert_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    committee: &Keypair,
+    request: Brc20Asset,
+    amount: Brc20Amount,
+    confidence: Option<Brc20Amount>,
+    source: AttestationSource,
+) -> Vec<Instruction> {
+    let mut builder = InsertBuilder::new(*program_id).payer(*payer).request(request).amount(amount).source(source);
+    if let Some(confidence) = confidence {
+        builder = builder.confidence(confidence);
+    }
+    builder.committee(committee).build().unwrap()
+}
+
+/// Blinded request for `key`, pass the same `salt` to the committee off chain. Returns the blinded
+/// key to find the request again with `find_blinded_asset_address`.
+pub fn request_blinded_ix(program_id: &Pubkey, payer: &Pubkey, key: &Brc20Key, salt: &[u8; 32]) -> ([u8; 32], Instruction) {
+    let blinded = blind_key(&key.canonicalize(), salt).unwrap();
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_blinded_asset_address(program_id, &blinded).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    let data = Brc20OracleInstruction::RequestBlinded(blinded).try_to_vec().unwrap();
+    (blinded, Instruction { program_id: *program_id, accounts, data })
+}
+
+pub fn insert_blinded_ix(
+    program_id: &Pubkey,
+    committee: &Keypair,
+    request: &BlindedAsset,
+    amount: Brc20Amount,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_committee_address(program_id);
+    let (blinded_asset_address, _) = find_blinded_asset_address(program_id, &request.blinded);
+    let accounts = vec![
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(blinded_asset_address, false),
         AccountMeta::new_readonly(sysvar::instructions::id(), false),
     ];
 
-    let new_committee = Committee {
-        id,
-        address: *new_committee,
-        uid: 0,
-    };
-    let sign_msg = new_committee.try_to_vec().unwrap();
+    let sign_msg = signing_message_for_blinded_asset(&blinded_asset_address, request, amount).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::InsertBlinded(request.blinded, amount, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+pub fn insert_delta_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    committee: &Keypair,
+    request: Brc20Asset,
+    delta: AmountDelta,
+) -> Vec<Instruction> {
+    let key = request.key.clone();
+    let (committee_info_address, _) = find_network_committee_address(program_id, key.network());
+    let (asset_address, _) = find_asset_address(program_id, &key);
+    let base_key = Brc20Key { height: delta.base_height, ..key.clone() };
+    let (base_address, _) = find_asset_address(program_id, &base_key);
+    let (head_address, _) = find_head_address(program_id, &key.tick, &key.owner);
+    let accounts = vec![
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new_readonly(base_address, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(head_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_delta(&asset_address, &request, &delta).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::InsertDelta(key, delta, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+pub fn reject_ix(
+    program_id: &Pubkey,
+    committee: &Keypair,
+    request: Brc20Asset,
+    reason: RejectReason,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_network_committee_address(program_id, request.key.network());
+    let (asset_address, _) = find_asset_address(program_id, &request.key);
+    let accounts =
+        RejectAccounts { committee: committee_info_address, asset: asset_address, ix_sysvar: sysvar::instructions::id() }
+            .to_account_metas();
+
+    let sign_msg = signing_message_for_rejection(&asset_address, &request, reason).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::Reject(request.key, reason, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+pub fn revoke_ix(
+    program_id: &Pubkey,
+    committee: &Keypair,
+    asset: Brc20Asset,
+    reason: RevokeReason,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_network_committee_address(program_id, asset.key.network());
+    let (asset_address, _) = find_asset_address(program_id, &asset.key);
+    let accounts =
+        RevokeAccounts { committee: committee_info_address, asset: asset_address, ix_sysvar: sysvar::instructions::id() }
+            .to_account_metas();
+
+    let sign_msg = signing_message_for_revocation(&asset_address, &asset, reason).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::Revoke(asset.key, asset.attested_slot, reason, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+/// Moves `attested_slot` of an unchanged `asset` to the current slot.
+pub fn refresh_ix(program_id: &Pubkey, committee: &Keypair, asset: Brc20Asset) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_network_committee_address(program_id, asset.key.network());
+    let (asset_address, _) = find_asset_address(program_id, &asset.key);
+    let accounts =
+        RefreshAccounts { committee: committee_info_address, asset: asset_address, ix_sysvar: sysvar::instructions::id() }
+            .to_account_metas();
 
-    let signer = old_committee.unwrap_or(payer);
-    let verify_instruction = new_ed25519_instruction(
-        &ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap(),
-        &sign_msg,
-    );
-    let signature = signer.sign_message(&sign_msg).as_ref().to_vec();
-    let data = Brc20OracleInstruction::SetCommittee(new_committee, signature)
+    let sign_msg = signing_message_for_refresh(&asset_address, &asset).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::Refresh(asset.key, asset.attested_slot, signature)
         .try_to_vec()
         .unwrap();
     vec![
         verify_instruction,
         Instruction {
-            program_id: program_id.clone(),
+            program_id: *program_id,
             accounts,
             data,
         },
     ]
 }
 
-pub fn request_ix(program_id: &Pubkey, payer: &Keypair, key: Brc20Key) -> Vec<Instruction> {
+pub fn publish_snapshot_ix(
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    snapshot: Snapshot,
+) -> Vec<Instruction> {
     let (committee_info_address, _) = find_committee_address(program_id);
+    let (snapshot_address, _) = find_snapshot_address(program_id, snapshot.height, &snapshot.tick);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(snapshot_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_snapshot(&snapshot).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let header = typed_header(MessageKind::Snapshot, snapshot.try_to_vec().unwrap().len());
+    let data = Brc20OracleInstruction::PublishSnapshot(header, snapshot, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+pub fn materialize_entry_ix(
+    program_id: &Pubkey,
+    payer: &Keypair,
+    key: Brc20Key,
+    amount: Brc20Amount,
+    proof: Vec<[u8; 32]>,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_network_committee_address(program_id, key.network());
+    let (snapshot_address, _) = find_snapshot_address(program_id, key.height, &key.tick);
     let (asset_address, _) = find_asset_address(program_id, &key);
     let accounts = vec![
         AccountMeta::new(payer.pubkey(), true),
         AccountMeta::new(committee_info_address, false),
+        AccountMeta::new_readonly(snapshot_address, false),
         AccountMeta::new(asset_address, false),
         AccountMeta::new_readonly(system_program::id(), false),
     ];
 
-    let data = Brc20OracleInstruction::Request(key).try_to_vec().unwrap();
+    let data = Brc20OracleInstruction::MaterializeEntry(key, amount, proof)
+        .try_to_vec()
+        .unwrap();
     vec![
         Instruction {
-            program_id: program_id.clone(),
+            program_id: *program_id,
             accounts,
             data,
         }
     ]
 }
 
-pub fn insert_ix(
+pub fn set_verifying_key_ix(
     program_id: &Pubkey,
+    payer: &Keypair,
     committee: &Keypair,
-    committee_info: Pubkey,
-    uid: u64,
+    vk: VerifyingKey,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_committee_address(program_id);
+    let (vk_address, _) = find_verifying_key_address(program_id);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(vk_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_verifying_key(&vk).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::SetVerifyingKey(vk, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+/// `stats` also counts the insert in the global stats account, serializing it with other inserts passing it.
+pub fn insert_with_proof_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
     key: Brc20Key,
-    amount: u128,
+    amount: Brc20Amount,
+    proof: Groth16Proof,
+    stats: bool,
 ) -> Vec<Instruction> {
+    let (vk_address, _) = find_verifying_key_address(program_id);
     let (asset_address, _) = find_asset_address(program_id, &key);
+    let (head_address, _) = find_head_address(program_id, &key.tick, &key.owner);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(vk_address, false),
+        AccountMeta::new(asset_address, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(head_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if stats {
+        accounts.push(AccountMeta::new(find_stats_address(program_id).0, false));
+    }
 
-    let accounts = vec![
-        AccountMeta::new_readonly(committee_info.clone(), false),
+    let data = Brc20OracleInstruction::InsertWithProof(key, amount, proof)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    ]
+}
+
+/// Expires `request`, closing the records of `requesters` along with it and dropping it from its
+/// tick registry and index page.
+pub fn expire_ix(program_id: &Pubkey, request: &Brc20Asset, requesters: &[Pubkey]) -> Vec<Instruction> {
+    let (asset_address, _) = find_asset_address(program_id, &request.key);
+    let (tick_registry_address, _) = find_tick_registry_address(program_id, &request.key.tick);
+    let (index_page_address, _) = find_index_page_address(program_id, request.uid / INDEX_PAGE_CAPACITY);
+    let mut accounts = vec![
         AccountMeta::new(asset_address, false),
-        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new(request.requester, false),
+        AccountMeta::new(tick_registry_address, false),
+        AccountMeta::new(index_page_address, false),
+    ];
+    for requester in requesters {
+        let (request_address, _) = find_request_address(program_id, &asset_address, requester);
+        accounts.push(AccountMeta::new(request_address, false));
+        accounts.push(AccountMeta::new(*requester, false));
+    }
+
+    let data = Brc20OracleInstruction::Expire(request.key.clone())
+        .try_to_vec()
+        .unwrap();
+    vec![
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    ]
+}
+
+pub fn close_request_ix(program_id: &Pubkey, requester: &Keypair, key: Brc20Key) -> Vec<Instruction> {
+    let (asset_address, _) = find_asset_address(program_id, &key);
+    let (request_address, _) = find_request_address(program_id, &asset_address, &requester.pubkey());
+    let accounts = vec![
+        AccountMeta::new(requester.pubkey(), true),
+        AccountMeta::new(request_address, false),
     ];
-    let asset = Brc20Asset {
-        prefix: ASSET_PREFIX,
-        uid,
-        set: true,
-        key: key.clone(),
-        amount,
+
+    let data = Brc20OracleInstruction::CloseRequest(key).try_to_vec().unwrap();
+    vec![
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    ]
+}
+
+/// Moves the committee (`key` None) or the asset of a version 0 key to the current account layout.
+pub fn migrate_ix(program_id: &Pubkey, payer: &Pubkey, key: Option<Brc20Key>) -> Vec<Instruction> {
+    let account = match &key {
+        Some(key) => find_asset_address(program_id, key).0,
+        None => find_committee_address(program_id).0,
     };
-    let asset_msg = asset.try_to_vec().unwrap();
-    let signature = committee.sign_message(&asset_msg).as_ref().to_vec();
-    let data = Brc20OracleInstruction::Insert(key, amount, signature)
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let data = Brc20OracleInstruction::Migrate(key).try_to_vec().unwrap();
+    vec![
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    ]
+}
+
+/// `stats` also counts missed heartbeats in the global stats account.
+pub fn heartbeat_ix(program_id: &Pubkey, committee: &Keypair, heartbeat: Heartbeat, stats: bool) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_committee_address(program_id);
+    let mut accounts = vec![
+        AccountMeta::new(committee_info_address, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    if stats {
+        accounts.push(AccountMeta::new(find_stats_address(program_id).0, false));
+    }
+
+    let sign_msg = signing_message_for_heartbeat(&heartbeat).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::Heartbeat(heartbeat, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+/// `admin` is the upgrade authority of the program and must sign the transaction too.
+pub fn set_tick_policy_ix(
+    program_id: &Pubkey,
+    payer: &Keypair,
+    admin: &Pubkey,
+    committee: &Keypair,
+    policy: TickPolicy,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_committee_address(program_id);
+    let (policy_address, _) = find_tick_policy_address(program_id, &policy.tick);
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(policy_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new_readonly(programdata_address, false),
+    ];
+
+    let sign_msg = signing_message_for_tick_policy(&policy).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::SetTickPolicy(policy, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+pub fn get_amount_ix(program_id: &Pubkey, key: Brc20Key) -> Vec<Instruction> {
+    let (asset_address, _) = find_asset_address(program_id, &key);
+    let (policy_address, _) = find_tick_policy_address(program_id, &key.tick);
+    let accounts = vec![
+        AccountMeta::new_readonly(asset_address, false),
+        AccountMeta::new_readonly(policy_address, false),
+    ];
+
+    let data = Brc20OracleInstruction::GetAmount(key).try_to_vec().unwrap();
+    vec![
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    ]
+}
+
+pub fn register_consumer_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    consumer: &Pubkey,
+    target: ConsumerTarget,
+) -> Vec<Instruction> {
+    let (consumer_address, _) = find_consumer_address(program_id, &target, consumer);
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(*consumer, true),
+        AccountMeta::new(consumer_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let data = Brc20OracleInstruction::RegisterConsumer(target).try_to_vec().unwrap();
+    vec![
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    ]
+}
+
+// SPL memo program v2.
+pub const MEMO_PROGRAM_ID: Pubkey = solana_program::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// Memo instruction without signers, `memo` must be valid UTF-8.
+pub fn memo_ix(memo: &str) -> Instruction {
+    Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: vec![],
+        data: memo.as_bytes().to_vec(),
+    }
+}
+
+/// Appends a memo, e.g. an integrator order id, to the instructions of any `*_ix` builder. The
+/// memo goes last so the ed25519 verify instruction stays at index 0.
+pub fn with_memo(mut ixs: Vec<Instruction>, memo: Option<&str>) -> Vec<Instruction> {
+    if let Some(memo) = memo {
+        ixs.push(memo_ix(memo));
+    }
+    ixs
+}
+
+/// Memos of a confirmed transaction, in instruction order.
+pub fn decode_memos(transaction: &VersionedTransaction) -> Vec<String> {
+    let keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&MEMO_PROGRAM_ID))
+        .filter_map(|ix| String::from_utf8(ix.data.clone()).ok())
+        .collect()
+}
+
+pub fn get_version_ix(program_id: &Pubkey, payer: &Pubkey) -> Vec<Instruction> {
+    let (version_address, _) = find_version_address(program_id);
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(version_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let data = Brc20OracleInstruction::GetVersion.try_to_vec().unwrap();
+    vec![
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    ]
+}
+
+/// `signature` is the signer's output over `message`, typically from an external signer, checked
+/// against `committee` as the committee of `network` without changing any state.
+pub fn verify_committee_signature_ix(
+    program_id: &Pubkey,
+    network: BitcoinNetwork,
+    committee: &Pubkey,
+    message: Vec<u8>,
+    signature: [u8; 64],
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_network_committee_address(program_id, network);
+    let accounts = vec![
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let verify_instruction = ed25519_ix(committee, &signature, &message);
+    let data = Brc20OracleInstruction::VerifyCommitteeSignature(network, message, signature.to_vec())
         .try_to_vec()
         .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
 
-    let verify_instruction = new_ed25519_instruction(
-        &ed25519_dalek::Keypair::from_bytes(&committee.to_bytes()).unwrap(),
-        &asset_msg,
-    );
+pub fn attest_sat_range_ix(
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    attestation: SatRangeAttestation,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_committee_address(program_id);
+    let (sat_range_address, _) = find_sat_range_address(program_id, &attestation.key);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(sat_range_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_sat_range(&attestation).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let header = typed_header(MessageKind::SatRange, attestation.try_to_vec().unwrap().len());
+    let data = Brc20OracleInstruction::AttestSatRange(header, attestation, signature)
+        .try_to_vec()
+        .unwrap();
     vec![
         verify_instruction,
         Instruction {
-            program_id: program_id.clone(),
+            program_id: *program_id,
             accounts,
             data,
         },
     ]
 }
 
-pub fn find_committee_address(program_id: &Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[&COMMITTEE_PREFIX], program_id)
+pub fn insert_address_entry_ix(
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    key: Brc20Key,
+    amount: Brc20Amount,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_network_committee_address(program_id, key.network());
+    let (address_map_address, _) = find_address_map_address(program_id, &key.owner);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(address_map_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_address_entry(&address_map_address, &key, amount).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let data = Brc20OracleInstruction::InsertAddressEntry(key, amount, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
+}
+
+pub fn attest_height_range_ix(
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    attestation: HeightRangeAttestation,
+) -> Vec<Instruction> {
+    let (committee_info_address, _) = find_committee_address(program_id);
+    let (height_range_address, _) = find_height_range_address(program_id, &attestation.key);
+    let accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(committee_info_address, false),
+        AccountMeta::new(height_range_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    let sign_msg = signing_message_for_height_range(&attestation).unwrap();
+    let (verify_instruction, signature) = signed_ed25519_ix(committee, &sign_msg);
+    let header = typed_header(MessageKind::HeightRange, attestation.try_to_vec().unwrap().len());
+    let data = Brc20OracleInstruction::AttestHeightRange(header, attestation, signature)
+        .try_to_vec()
+        .unwrap();
+    vec![
+        verify_instruction,
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        },
+    ]
 }
 
-pub fn find_asset_address(program_id: &Pubkey, key: &Brc20Key) -> (Pubkey, u8) {
-    Pubkey::find_program_address(
-        &[
-            &ASSET_PREFIX,
-            solana_program::keccak::hash(key.try_to_vec().unwrap().as_slice()).as_ref(),
-        ],
-        program_id,
-    )
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use brc20_oracle::types::BRC20_KEY_VERSION;
+
+    fn request(program_id: &Pubkey, owner: &str, height: u32) -> Brc20Asset {
+        Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set: false,
+            uid: 0,
+            key: Brc20Key { version: BRC20_KEY_VERSION, height, tick: *b"ordi", owner: owner.to_string() },
+            amount: Brc20Amount::ZERO,
+            requester: *program_id,
+            expire_slot: None,
+            attested_slot: 0,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_insert_write_locks() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let committee = Keypair::new();
+        let committee_info = find_committee_address(&program_id).0;
+
+        let a = request(&program_id, "a", 10);
+        let b = request(&program_id, "b", 10);
+        let locks_a = writable_accounts(&insert_ix(
+            &program_id, &payer, &committee, a.clone(), Brc20Amount(1), None, AttestationSource::default(),
+        ));
+        let locks_b = writable_accounts(&insert_ix(
+            &program_id, &payer, &committee, b.clone(), Brc20Amount(1), None, AttestationSource::default(),
+        ));
+        assert!(!locks_a.contains(&committee_info));
+        // inserts for different owners only share the fee payer.
+        let shared: Vec<&Pubkey> = locks_a.iter().filter(|pubkey| locks_b.contains(pubkey)).collect();
+        assert_eq!(vec![&payer], shared);
+
+        let delta = AmountDelta { base_height: 9, amount_change: 1 };
+        let locks = writable_accounts(&insert_delta_ix(&program_id, &payer, &committee, a.clone(), delta));
+        assert!(!locks.contains(&committee_info));
+        assert!(!locks.contains(&find_asset_address(&program_id, &Brc20Key { height: 9, ..a.key.clone() }).0));
+
+        let asset_a = find_asset_address(&program_id, &a.key).0;
+        let locks = writable_accounts(&reject_ix(&program_id, &committee, a, RejectReason::InvalidTick));
+        assert_eq!(vec![asset_a], locks);
+    }
+
+    #[test]
+    fn test_memo() {
+        let program_id = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let committee = Keypair::new();
+        let ixs = insert_ix(
+            &program_id, &payer.pubkey(), &committee, request(&program_id, "a", 10), Brc20Amount(1), None,
+            AttestationSource::default(),
+        );
+        let ixs = with_memo(ixs, Some("order-42"));
+        assert_eq!(MEMO_PROGRAM_ID, ixs.last().unwrap().program_id);
+
+        let transaction = solana_sdk::transaction::Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+        assert_eq!(vec!["order-42".to_string()], decode_memos(&VersionedTransaction::from(transaction)));
+    }
+
+    // the seeds are spelled out the way the processors sign with them, so a change to either side of
+    // the derivation shows up here instead of as a failed create_account.
+    #[test]
+    fn test_pda_derivation() {
+        use solana_program::keccak::hash;
+        let program_id = Pubkey::new_from_array([7; 32]);
+        let payer = Keypair::new();
+        let asset = request(&program_id, "bc1qowner", 10);
+        let key = asset.key.clone();
+
+        let (asset_address, asset_bump) = find_asset_address(&program_id, &key);
+        let asset_seed = hash(&key.try_to_vec().unwrap()).to_bytes();
+        assert_eq!(
+            Ok(asset_address),
+            Pubkey::create_program_address(&[b"Asset", &asset_seed, &[asset_bump]], &program_id)
+        );
+        let (head_address, head_bump) = find_head_address(&program_id, &key.tick, &key.owner);
+        let owner_seed = hash(key.owner.as_bytes()).to_bytes();
+        assert_eq!(
+            Ok(head_address),
+            Pubkey::create_program_address(&[b"Head", &key.tick, &owner_seed, &[head_bump]], &program_id)
+        );
+        let (request_address, request_bump) = find_request_address(&program_id, &asset_address, &payer.pubkey());
+        assert_eq!(
+            Ok(request_address),
+            Pubkey::create_program_address(
+                &[b"Request", asset_address.as_ref(), payer.pubkey().as_ref(), &[request_bump]],
+                &program_id,
+            )
+        );
+
+        // and the builders pass exactly those accounts.
+        let ixs = request_ix(&program_id, &payer, key.clone(), None, None, None);
+        assert_eq!(asset_address, ixs[0].accounts[2].pubkey);
+        assert_eq!(request_address, ixs[0].accounts[3].pubkey);
+        let committee = Keypair::new();
+        let ixs = insert_ix(
+            &program_id, &payer.pubkey(), &committee, asset, Brc20Amount(1), None, AttestationSource::default(),
+        );
+        assert!(ixs.last().unwrap().accounts.iter().any(|meta| meta.pubkey == head_address));
+    }
 }
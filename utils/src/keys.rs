@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use solana_sdk::signer::keypair::{read_keypair, read_keypair_file, Keypair};
+use thiserror::Error;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Error)]
+pub enum KeyError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("environment variable {0} not set")]
+    MissingEnv(String),
+    #[error("invalid keypair: {0}")]
+    InvalidKeypair(String),
+    #[error("wrong passphrase or corrupted key file")]
+    Decrypt,
+}
+
+/// Where to load a committee or payer keypair from.
+pub enum KeySource {
+    // solana-keygen JSON file.
+    File(PathBuf),
+    // environment variable holding the solana-keygen JSON byte array.
+    Env(String),
+    // file written by `write_encrypted_keypair`.
+    Encrypted { path: PathBuf, passphrase: String },
+}
+
+impl KeySource {
+    pub fn load(&self) -> Result<Keypair, KeyError> {
+        match self {
+            KeySource::File(path) => {
+                read_keypair_file(path).map_err(|e| KeyError::InvalidKeypair(e.to_string()))
+            }
+            KeySource::Env(name) => {
+                let value = std::env::var(name).map_err(|_| KeyError::MissingEnv(name.clone()))?;
+                read_keypair(&mut value.as_bytes()).map_err(|e| KeyError::InvalidKeypair(e.to_string()))
+            }
+            KeySource::Encrypted { path, passphrase } => {
+                decrypt_keypair(&std::fs::read(path)?, passphrase)
+            }
+        }
+    }
+}
+
+/// Encrypts the keypair with a key derived from `passphrase` (argon2id), laid out as salt || nonce || ciphertext.
+pub fn encrypt_keypair(keypair: &Keypair, passphrase: &str) -> Result<Vec<u8>, KeyError> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), keypair.to_bytes().as_ref())
+        .map_err(|_| KeyError::Decrypt)?;
+    Ok([&salt[..], &nonce[..], &ciphertext[..]].concat())
+}
+
+pub fn decrypt_keypair(data: &[u8], passphrase: &str) -> Result<Keypair, KeyError> {
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(KeyError::Decrypt);
+    }
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| KeyError::Decrypt)?;
+    Keypair::from_bytes(&plaintext).map_err(|e| KeyError::InvalidKeypair(e.to_string()))
+}
+
+pub fn write_encrypted_keypair(path: &PathBuf, keypair: &Keypair, passphrase: &str) -> Result<(), KeyError> {
+    std::fs::write(path, encrypt_keypair(keypair, passphrase)?)?;
+    Ok(())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, KeyError> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|_| KeyError::Decrypt)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signer::Signer;
+    use super::*;
+
+    #[test]
+    fn test_encrypted_keypair_roundtrip() {
+        let keypair = Keypair::new();
+        let data = encrypt_keypair(&keypair, "correct horse").unwrap();
+        let decrypted = decrypt_keypair(&data, "correct horse").unwrap();
+        assert_eq!(keypair.pubkey(), decrypted.pubkey());
+        assert!(decrypt_keypair(&data, "battery staple").is_err());
+    }
+}
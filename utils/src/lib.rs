@@ -1,2 +1,33 @@
+pub mod accounts;
+pub mod alerts;
+pub mod amount;
+pub mod archive;
+pub mod backfill;
+pub mod batch;
+pub mod builder;
+pub mod bundle;
+pub mod cache;
 pub mod call_process;
+pub mod ceremony;
+pub mod deployment;
+pub mod diff;
+pub mod ed25519;
+pub mod export;
+#[cfg(feature = "test-utils")]
+pub mod forge;
+pub mod idempotency;
 pub mod instruction;
+pub mod keys;
+pub mod loadtest;
+pub mod merkle;
+#[cfg(feature = "bitcoin")]
+pub mod taproot;
+pub mod packer;
+pub mod payers;
+pub mod rotation;
+#[cfg(feature = "sled")]
+pub mod store;
+pub mod subscription;
+pub mod tracker;
+pub mod verify;
+pub mod watch;
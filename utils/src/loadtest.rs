@@ -0,0 +1,163 @@
+//! Synthetic request and insert traffic against a local validator, to measure throughput, compute
+//! units and failure rates before sizing production batches. Starting `solana-test-validator` with
+//! the program deployed and a committee set is left to the caller.
+
+use std::time::{Duration, Instant};
+use borsh::BorshDeserialize;
+use brc20_oracle::types::{AttestationSource, Brc20Amount, Brc20Asset, Brc20Key, BRC20_KEY_VERSION};
+use brc20_oracle::INDEX_PAGE_CAPACITY;
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::keccak::hashv;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::{keypair::Keypair, Signer};
+use solana_transaction_status::UiTransactionEncoding;
+use crate::call_process::{get_committee, submit_all};
+use crate::instruction::{find_asset_address, find_tick_registry_address, insert_ix, request_ix};
+
+// getMultipleAccounts accepts at most 100 keys.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PhaseReport {
+    pub sent: usize,
+    pub failed: usize,
+    pub elapsed: Duration,
+    // per confirmed transaction, as reported by getTransaction.
+    pub compute_units: Vec<u64>,
+}
+
+impl PhaseReport {
+    /// Confirmed transactions per second.
+    pub fn throughput(&self) -> f64 {
+        (self.sent - self.failed) as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        self.failed as f64 / self.sent.max(1) as f64
+    }
+
+    pub fn max_compute_units(&self) -> Option<u64> {
+        self.compute_units.iter().copied().max()
+    }
+
+    pub fn mean_compute_units(&self) -> Option<u64> {
+        let total: u64 = self.compute_units.iter().sum();
+        total.checked_div(self.compute_units.len() as u64)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadTestReport {
+    pub request: PhaseReport,
+    pub insert: PhaseReport,
+}
+
+/// `count` distinct canonical mainnet keys, the same for the same `seed`.
+pub fn synthetic_keys(count: usize, seed: u64) -> Vec<Brc20Key> {
+    (0..count as u64)
+        .map(|i| {
+            let digest = hashv(&[&seed.to_le_bytes(), &i.to_le_bytes()]).to_bytes();
+            let owner: String = digest[..20].iter().map(|b| format!("{:02x}", b)).collect();
+            Brc20Key {
+                version: BRC20_KEY_VERSION,
+                height: 800_000 + u32::from(digest[20]),
+                tick: [digest[21], digest[22], digest[23], digest[24]].map(|b| b'a' + b % 26),
+                owner: format!("bc1q{}", owner),
+            }
+        })
+        .collect()
+}
+
+/// Requests `count` synthetic keys, then fills every request that landed with the committee,
+/// `max_in_flight` transactions at a time. The committee must be the one set on chain.
+pub async fn run_load_test(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    committee: &Keypair,
+    count: usize,
+    seed: u64,
+    max_in_flight: usize,
+) -> Result<LoadTestReport> {
+    let keys = synthetic_keys(count, seed);
+    // uids are handed out in landing order, a request landing on a full page fails and is counted.
+    let uid = get_committee(client, program_id).await?.uid;
+    let batches = keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let tick_registry = find_tick_registry_address(program_id, &key.tick).0;
+            request_ix(program_id, payer, key.clone(), None, Some(tick_registry), Some((uid + i as u64) / INDEX_PAGE_CAPACITY))
+        })
+        .collect();
+    let (request, _) = run_phase(client, payer, batches, max_in_flight).await?;
+
+    let mut requests = vec![];
+    let addresses: Vec<Pubkey> = keys.iter().map(|key| find_asset_address(program_id, key).0).collect();
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts_with_commitment(chunk, client.commitment()).await?.value;
+        requests.extend(accounts.into_iter().flatten().filter_map(|account| Brc20Asset::try_from_slice(&account.data).ok()));
+    }
+    let batches = requests
+        .into_iter()
+        .filter(|request| !request.set)
+        .map(|request| {
+            let amount = Brc20Amount(u128::from(request.key.height));
+            insert_ix(program_id, &payer.pubkey(), committee, request, amount, None, AttestationSource::default())
+        })
+        .collect();
+    let (insert, _) = run_phase(client, payer, batches, max_in_flight).await?;
+    Ok(LoadTestReport { request, insert })
+}
+
+// sends `batches` and reads back the compute units of the ones that landed.
+async fn run_phase(
+    client: &RpcClient,
+    payer: &Keypair,
+    batches: Vec<Vec<solana_program::instruction::Instruction>>,
+    max_in_flight: usize,
+) -> Result<(PhaseReport, Vec<Signature>)> {
+    let sent = batches.len();
+    let started = Instant::now();
+    let results = submit_all(client, payer, &[payer], batches, max_in_flight).await;
+    let elapsed = started.elapsed();
+
+    let signatures: Vec<Signature> = results.into_iter().filter_map(|result| result.ok()).collect();
+    let mut compute_units = vec![];
+    for signature in &signatures {
+        let confirmed = client.get_transaction(signature, UiTransactionEncoding::Base64).await?;
+        if let Some(units) = confirmed.transaction.meta.and_then(|meta| Option::<u64>::from(meta.compute_units_consumed)) {
+            compute_units.push(units);
+        }
+    }
+    let report = PhaseReport { sent, failed: sent - signatures.len(), elapsed, compute_units };
+    Ok((report, signatures))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_keys() {
+        let keys = synthetic_keys(50, 7);
+        assert_eq!(keys, synthetic_keys(50, 7));
+        assert_ne!(keys, synthetic_keys(50, 8));
+        assert!(keys.iter().all(|key| key.is_canonical()));
+        let mut owners: Vec<&String> = keys.iter().map(|key| &key.owner).collect();
+        owners.sort();
+        owners.dedup();
+        assert_eq!(50, owners.len());
+    }
+
+    #[test]
+    fn test_phase_report() {
+        let report = PhaseReport { sent: 10, failed: 2, elapsed: Duration::from_secs(2), compute_units: vec![100, 300] };
+        assert_eq!(4.0, report.throughput());
+        assert_eq!(0.2, report.failure_rate());
+        assert_eq!((Some(300), Some(200)), (report.max_compute_units(), report.mean_compute_units()));
+        assert_eq!(None, PhaseReport::default().mean_compute_units());
+    }
+}
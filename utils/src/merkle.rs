@@ -0,0 +1,68 @@
+use brc20_oracle::merkle::{leaf_hash, node_hash};
+use brc20_oracle::types::{Brc20Amount, Brc20Key};
+
+/// Leaves for a snapshot, in the order the committee commits to them.
+pub fn snapshot_leaves(entries: &[(Brc20Key, Brc20Amount)]) -> Vec<[u8; 32]> {
+    entries
+        .iter()
+        .map(|(key, amount)| leaf_hash(key, *amount).unwrap())
+        .collect()
+}
+
+/// Root of the tree built over `leaves`. An odd node is carried up to the next level unchanged.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0u8; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling path from `leaves[index]` up to the root, as expected by `MaterializeEntry`.
+pub fn merkle_proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = vec![];
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [a, b] => node_hash(a, b),
+            [a] => *a,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use brc20_oracle::merkle::verify_proof;
+    use brc20_oracle::types::BRC20_KEY_VERSION;
+    use super::*;
+
+    #[test]
+    fn test_proofs_verify_against_root() {
+        let entries: Vec<(Brc20Key, Brc20Amount)> = (0..5)
+            .map(|i| (Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: format!("owner{}", i) }, Brc20Amount(i as u128 * 100)))
+            .collect();
+        let leaves = snapshot_leaves(&entries);
+        let root = merkle_root(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            assert!(verify_proof(&root, *leaf, &merkle_proof(&leaves, index)));
+        }
+        assert!(!verify_proof(&root, leaves[0], &merkle_proof(&leaves, 1)));
+    }
+}
@@ -0,0 +1,94 @@
+//! Transaction size estimates and packing of instruction groups under the packet limit.
+
+use solana_program::instruction::Instruction;
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+
+const SIGNATURE_LEN: usize = 64;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Packed {
+    // each batch fits one transaction, groups kept in order.
+    pub batches: Vec<Vec<Instruction>>,
+    // groups too large for a transaction even on their own.
+    pub oversized: Vec<Vec<Instruction>>,
+}
+
+/// Serialized size of a legacy transaction paid by `payer` carrying `instructions`, signatures included.
+pub fn transaction_size(payer: &Pubkey, instructions: &[Instruction]) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+    short_vec_len(signatures) + signatures * SIGNATURE_LEN + message.serialize().len()
+}
+
+/// Greedily packs `groups` into as few transactions as fit under `PACKET_DATA_SIZE`. A group is never
+/// split. Committee signed oracle instructions read their ed25519 instruction at index 0, so each of
+/// those groups still needs a transaction of its own.
+pub fn pack_instructions(payer: &Pubkey, groups: Vec<Vec<Instruction>>) -> Packed {
+    let mut packed = Packed::default();
+    let mut current: Vec<Instruction> = vec![];
+    for group in groups {
+        if transaction_size(payer, &group) > PACKET_DATA_SIZE {
+            packed.oversized.push(group);
+            continue;
+        }
+        let mut candidate = current.clone();
+        candidate.extend(group.iter().cloned());
+        if transaction_size(payer, &candidate) <= PACKET_DATA_SIZE {
+            current = candidate;
+        } else {
+            packed.batches.push(std::mem::replace(&mut current, group));
+        }
+    }
+    if !current.is_empty() {
+        packed.batches.push(current);
+    }
+    packed
+}
+
+fn short_vec_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::hash::Hash;
+    use solana_program::instruction::AccountMeta;
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+    use solana_sdk::transaction::Transaction;
+    use super::*;
+
+    fn memo(len: usize) -> Vec<Instruction> {
+        vec![Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            data: vec![1; len],
+        }]
+    }
+
+    #[test]
+    fn test_transaction_size() {
+        let payer = Keypair::new();
+        let instructions = [memo(100), memo(300)].concat();
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer], Hash::default());
+        let wire = short_vec_len(transaction.signatures.len())
+            + transaction.signatures.len() * SIGNATURE_LEN
+            + transaction.message.serialize().len();
+        assert_eq!(wire, transaction_size(&payer.pubkey(), &instructions));
+    }
+
+    #[test]
+    fn test_pack_instructions() {
+        let payer = Pubkey::new_unique();
+        let packed = pack_instructions(&payer, vec![memo(400), memo(400), memo(400), memo(2000)]);
+        assert_eq!(1, packed.oversized.len());
+        assert_eq!(vec![2, 1], packed.batches.iter().map(|batch| batch.len()).collect::<Vec<_>>());
+        assert!(packed.batches.iter().all(|batch| transaction_size(&payer, batch) <= PACKET_DATA_SIZE));
+    }
+}
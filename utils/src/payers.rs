@@ -0,0 +1,144 @@
+//! A pool of fee payers that batched submissions rotate through, so inserts don't all wait on one
+//! payer's write lock, plus the balance checks to keep every payer funded. Topping up is left to
+//! whoever gets the alerts, nothing here moves lamports.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use futures::lock::Mutex;
+use futures::stream::{self, StreamExt};
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::{keypair::Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use crate::call_process::cached_blockhash;
+
+// getMultipleAccounts accepts at most 100 keys.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+pub struct PayerPool {
+    payers: Vec<Keypair>,
+    next: AtomicUsize,
+}
+
+impl PayerPool {
+    /// Panics on an empty pool.
+    pub fn new(payers: Vec<Keypair>) -> Self {
+        assert!(!payers.is_empty(), "a payer pool needs at least one payer");
+        Self { payers, next: AtomicUsize::new(0) }
+    }
+
+    /// Payers in turn, shared by concurrent submissions.
+    pub fn next_payer(&self) -> &Keypair {
+        &self.payers[self.next.fetch_add(1, Ordering::Relaxed) % self.payers.len()]
+    }
+
+    pub fn pubkeys(&self) -> Vec<Pubkey> {
+        self.payers.iter().map(|payer| payer.pubkey()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.payers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.payers.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LowBalance {
+    pub payer: Pubkey,
+    pub lamports: u64,
+    // lamports to send to get back to the target balance.
+    pub top_up: u64,
+}
+
+/// Payers under `min_lamports`, poorest first.
+pub fn check_balances(balances: &[(Pubkey, u64)], min_lamports: u64, target_lamports: u64) -> Vec<LowBalance> {
+    let mut low: Vec<LowBalance> = balances
+        .iter()
+        .filter(|(_, lamports)| *lamports < min_lamports)
+        .map(|(payer, lamports)| LowBalance {
+            payer: *payer,
+            lamports: *lamports,
+            top_up: target_lamports.saturating_sub(*lamports),
+        })
+        .collect();
+    low.sort_by_key(|low| low.lamports);
+    low
+}
+
+/// `check_balances` over the pool's current balances. A payer without an account has no lamports.
+pub async fn fetch_low_balances(
+    client: &RpcClient,
+    pool: &PayerPool,
+    min_lamports: u64,
+    target_lamports: u64,
+) -> Result<Vec<LowBalance>> {
+    let pubkeys = pool.pubkeys();
+    let mut balances = vec![];
+    for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts_with_commitment(chunk, client.commitment()).await?.value;
+        balances.extend(chunk.iter().zip(accounts).map(|(payer, account)| (*payer, account.map_or(0, |account| account.lamports))));
+    }
+    Ok(check_balances(&balances, min_lamports, target_lamports))
+}
+
+/// `submit_all` with every transaction paid by the next payer of `pool`. `build` makes the
+/// instructions of an item for the payer picked, e.g. `|request, payer| insert_ix(.., &payer.pubkey(), ..)`.
+/// Statuses are returned in the order of `items`.
+pub async fn submit_all_rotating<T, F>(
+    client: &RpcClient,
+    pool: &PayerPool,
+    items: Vec<T>,
+    build: F,
+    max_in_flight: usize,
+) -> Vec<Result<Signature>>
+where
+    F: Fn(T, &Keypair) -> Vec<Instruction>,
+{
+    let blockhash: Mutex<Option<(Hash, Instant)>> = Mutex::new(None);
+    let (blockhash, build) = (&blockhash, &build);
+    stream::iter(items)
+        .map(|item| async move {
+            let payer = pool.next_payer();
+            let instructions = build(item, payer);
+            let recent_blockhash = cached_blockhash(client, blockhash).await?;
+            let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+            transaction.sign(&[payer], recent_blockhash);
+            client.send_and_confirm_transaction(&transaction).await
+        })
+        .buffered(max_in_flight.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_payer() {
+        let pool = PayerPool::new(vec![Keypair::new(), Keypair::new()]);
+        let pubkeys = pool.pubkeys();
+        let picked: Vec<Pubkey> = (0..4).map(|_| pool.next_payer().pubkey()).collect();
+        assert_eq!(vec![pubkeys[0], pubkeys[1], pubkeys[0], pubkeys[1]], picked);
+    }
+
+    #[test]
+    fn test_check_balances() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let low = check_balances(&[(a, 500), (b, 2_000), (c, 0)], 1_000, 5_000);
+        assert_eq!(
+            vec![
+                LowBalance { payer: c, lamports: 0, top_up: 5_000 },
+                LowBalance { payer: a, lamports: 500, top_up: 4_500 },
+            ],
+            low
+        );
+    }
+}
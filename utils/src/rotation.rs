@@ -0,0 +1,122 @@
+//! Dry run of a committee rotation: build the `SetCommittee`, check the old committee's signature
+//! locally, simulate it and diff the committee account. Nothing is sent.
+
+use std::fmt;
+use borsh::BorshDeserialize;
+use brc20_oracle::message::signing_message_for_committee;
+use brc20_oracle::types::{Brc20OracleInstruction, Committee};
+use ed25519_dalek::Verifier;
+use solana_client::client_error::{ClientErrorKind, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signer::keypair::Keypair;
+use crate::call_process::{get_committee, simulate_instruction, Simulation};
+use crate::instruction::{find_committee_address, init_committee_ix};
+
+#[derive(Clone, Debug)]
+pub struct RotationPlan {
+    pub committee_address: Pubkey,
+    pub before: Committee,
+    // committee account after the simulated rotation, `None` if the simulation failed.
+    pub after: Option<Committee>,
+    pub simulation: Simulation,
+}
+
+impl RotationPlan {
+    pub fn is_ok(&self) -> bool {
+        self.simulation.is_ok() && self.after.is_some()
+    }
+}
+
+impl fmt::Display for RotationPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "committee {}", self.committee_address)?;
+        let after = match &self.after {
+            Some(after) => after,
+            None => {
+                writeln!(f, "  simulation failed: {:?}", self.simulation.err)?;
+                for log in &self.simulation.logs {
+                    writeln!(f, "  {}", log)?;
+                }
+                return Ok(());
+            }
+        };
+        let fields = [
+            ("id", self.before.id.to_string(), after.id.to_string()),
+            ("address", self.before.address.to_string(), after.address.to_string()),
+            ("uid", self.before.uid.to_string(), after.uid.to_string()),
+            ("last_heartbeat_slot", self.before.last_heartbeat_slot.to_string(), after.last_heartbeat_slot.to_string()),
+        ];
+        for (name, before, after) in fields {
+            if before == after {
+                writeln!(f, "  {}: {} (unchanged)", name, before)?;
+            } else {
+                writeln!(f, "  {}: {} -> {}", name, before, after)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plans the rotation from the current committee to `new_committee`. Fails before simulating when
+/// `old_committee` isn't the on-chain committee or its signature doesn't verify over the exact message
+/// the program checks.
+pub async fn plan_rotation(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    old_committee: &Keypair,
+    new_committee: &Pubkey,
+) -> Result<RotationPlan> {
+    let before = get_committee(client, program_id).await?;
+    let id = before.id.checked_add(1).ok_or_else(|| ClientErrorKind::Custom("committee id exhausted".to_string()))?;
+    let ixs = init_committee_ix(program_id, payer, Some(old_committee), new_committee, id);
+
+    let signature = match Brc20OracleInstruction::try_from_slice(&ixs[1].data)? {
+        Brc20OracleInstruction::SetCommittee(_, signature) => signature,
+        _ => unreachable!("init_committee_ix builds a SetCommittee"),
+    };
+    let msg = signing_message_for_committee(id, new_committee)?;
+    let verified = match (
+        ed25519_dalek::PublicKey::from_bytes(before.address.as_ref()),
+        ed25519_dalek::Signature::try_from(signature.as_slice()),
+    ) {
+        (Ok(public), Ok(signature)) => public.verify(&msg, &signature).is_ok(),
+        _ => false,
+    };
+    if !verified {
+        return Err(ClientErrorKind::Custom(format!("signature doesn't verify against committee {}", before.address)).into());
+    }
+
+    let committee_address = find_committee_address(program_id).0;
+    let simulation = simulate_instruction(client, payer, &[payer], &ixs).await?;
+    let after = simulation
+        .accounts
+        .iter()
+        .find(|(pubkey, _)| pubkey == &committee_address)
+        .and_then(|(_, data)| data.as_ref())
+        .and_then(|data| Committee::try_from_slice(data).ok())
+        .filter(|_| simulation.is_ok());
+    Ok(RotationPlan { committee_address, before, after, simulation })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_diff() {
+        let before = Committee { id: 1, address: Pubkey::new_unique(), uid: 7, last_heartbeat_slot: 100, bump: 0 };
+        let after = Committee { id: 2, address: Pubkey::new_unique(), last_heartbeat_slot: 200, ..before.clone() };
+        let plan = RotationPlan {
+            committee_address: Pubkey::new_unique(),
+            before: before.clone(),
+            after: Some(after.clone()),
+            simulation: Simulation { err: None, logs: vec![], units_consumed: None, accounts: vec![] },
+        };
+        let diff = plan.to_string();
+        assert!(diff.contains("  id: 1 -> 2\n"));
+        assert!(diff.contains(&format!("  address: {} -> {}\n", before.address, after.address)));
+        assert!(diff.contains("  uid: 7 (unchanged)\n"));
+    }
+}
@@ -0,0 +1,154 @@
+//! On-disk asset cache keyed by `Brc20Key`, so reads keep working through an RPC outage. Entries
+//! older than `max_age` are still served while a background fetch replaces them.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::types::{Brc20Asset, Brc20Key};
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use crate::instruction::find_asset_address;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct CachedAsset {
+    pub asset: Brc20Asset,
+    // slot of the RPC response the asset was read at.
+    pub slot: u64,
+    // unix seconds.
+    pub fetched_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Freshness {
+    Fresh,
+    // older than `max_age`, a refresh was started in the background.
+    Stale,
+}
+
+#[derive(Clone)]
+pub struct AssetStore {
+    tree: sled::Tree,
+    max_age: Duration,
+}
+
+impl AssetStore {
+    pub fn open(path: impl AsRef<Path>, max_age: Duration) -> sled::Result<Self> {
+        Self::from_db(&sled::open(path)?, max_age)
+    }
+
+    pub fn from_db(db: &sled::Db, max_age: Duration) -> sled::Result<Self> {
+        Ok(Self { tree: db.open_tree("assets")?, max_age })
+    }
+
+    pub fn get(&self, key: &Brc20Key) -> std::io::Result<Option<CachedAsset>> {
+        match self.tree.get(key.try_to_vec()?)? {
+            Some(data) => Ok(Some(CachedAsset::try_from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Keeps `cached` unless an entry read at a later slot is already stored.
+    pub fn put(&self, key: &Brc20Key, cached: &CachedAsset) -> std::io::Result<()> {
+        if matches!(self.get(key)?, Some(current) if current.slot > cached.slot) {
+            return Ok(());
+        }
+        self.tree.insert(key.try_to_vec()?, cached.try_to_vec()?)?;
+        Ok(())
+    }
+
+    pub fn freshness(&self, cached: &CachedAsset) -> Freshness {
+        if unix_now().saturating_sub(cached.fetched_at) < self.max_age.as_secs() {
+            Freshness::Fresh
+        } else {
+            Freshness::Stale
+        }
+    }
+
+    /// Stale-while-revalidate read: a fresh entry is returned as is, a stale one is returned and
+    /// refreshed in the background, a missing one is fetched before returning. `None` if the asset
+    /// was never requested.
+    pub async fn get_asset(
+        &self,
+        client: &Arc<RpcClient>,
+        program_id: &Pubkey,
+        key: &Brc20Key,
+    ) -> Result<Option<(CachedAsset, Freshness)>> {
+        if let Some(cached) = self.get(key)? {
+            let freshness = self.freshness(&cached);
+            if freshness == Freshness::Stale {
+                let (store, client, program_id, key) = (self.clone(), client.clone(), *program_id, key.clone());
+                // a failed refresh keeps serving the stale entry.
+                tokio::spawn(async move { store.refresh(&client, &program_id, &key).await });
+            }
+            return Ok(Some((cached, freshness)));
+        }
+        Ok(self.refresh(client, program_id, key).await?.map(|cached| (cached, Freshness::Fresh)))
+    }
+
+    /// Reads the asset from RPC and stores it.
+    pub async fn refresh(&self, client: &RpcClient, program_id: &Pubkey, key: &Brc20Key) -> Result<Option<CachedAsset>> {
+        let address = find_asset_address(program_id, key).0;
+        let response = client.get_account_with_commitment(&address, client.commitment()).await?;
+        let account = match response.value {
+            Some(account) => account,
+            None => return Ok(None),
+        };
+        let cached = CachedAsset {
+            asset: Brc20Asset::try_from_slice(&account.data)?,
+            slot: response.context.slot,
+            fetched_at: unix_now(),
+        };
+        self.put(key, &cached)?;
+        Ok(Some(cached))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use brc20_oracle::types::{AttestationSource, Brc20Amount, BRC20_KEY_VERSION};
+
+    fn cached(key: &Brc20Key, amount: u128, slot: u64, fetched_at: u64) -> CachedAsset {
+        let asset = Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set: true,
+            uid: 0,
+            key: key.clone(),
+            amount: Brc20Amount(amount),
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: slot,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        };
+        CachedAsset { asset, slot, fetched_at }
+    }
+
+    #[test]
+    fn test_asset_store() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let store = AssetStore::from_db(&db, Duration::from_secs(60)).unwrap();
+        let key = Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: "12345".to_string() };
+        assert_eq!(None, store.get(&key).unwrap());
+
+        store.put(&key, &cached(&key, 1000, 10, unix_now())).unwrap();
+        let entry = store.get(&key).unwrap().unwrap();
+        assert_eq!(Brc20Amount(1000), entry.asset.amount);
+        assert_eq!(Freshness::Fresh, store.freshness(&entry));
+
+        // a response from an older slot doesn't overwrite a newer one.
+        store.put(&key, &cached(&key, 900, 5, unix_now())).unwrap();
+        assert_eq!(Brc20Amount(1000), store.get(&key).unwrap().unwrap().asset.amount);
+
+        assert_eq!(Freshness::Stale, store.freshness(&cached(&key, 1000, 10, unix_now() - 60)));
+    }
+}
@@ -0,0 +1,252 @@
+//! Asset account updates over a websocket that survive dropped connections. After a reconnect the
+//! oracle transactions landed since the last update are found with getSignaturesForAddress and the
+//! assets they wrote are read again, so a consumer sees every changed asset at least once.
+
+use std::str::FromStr;
+use std::time::Duration;
+use borsh::BorshDeserialize;
+use brc20_oracle::types::Brc20Asset;
+use brc20_oracle::ASSET_PREFIX;
+use futures::StreamExt;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::client_error::{ClientErrorKind, Result};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_client::rpc_response::{Response, RpcKeyedAccount};
+use solana_program::pubkey::{ParsePubkeyError, Pubkey};
+use solana_sdk::account::Account;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::UiTransactionEncoding;
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+// getMultipleAccounts accepts at most 100 keys.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetUpdate {
+    pub address: Pubkey,
+    pub asset: Brc20Asset,
+    pub slot: u64,
+    // read again after a reconnect rather than pushed by the websocket.
+    pub backfilled: bool,
+}
+
+/// Calls `on_update` for every asset account change of `program_id` until it returns false. A
+/// dropped websocket is reconnected with backoff and the gap filled from `client`. Pass the slot of
+/// the last update seen as `since_slot` to also fill the gap since an earlier run. An update can be
+/// reported twice around a reconnect, never skipped.
+pub async fn watch_assets<F: FnMut(AssetUpdate) -> bool>(
+    ws_url: &str,
+    client: &RpcClient,
+    program_id: &Pubkey,
+    since_slot: Option<u64>,
+    mut on_update: F,
+) -> Result<()> {
+    let mut last_slot = since_slot;
+    let mut delay = MIN_RECONNECT_DELAY;
+    loop {
+        let pubsub = match PubsubClient::new(ws_url).await {
+            Ok(pubsub) => pubsub,
+            Err(_) => {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                continue;
+            }
+        };
+        let (mut stream, _unsubscribe) = match pubsub.program_subscribe(program_id, Some(asset_subscribe_config(client))).await {
+            Ok(subscription) => subscription,
+            Err(_) => {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                continue;
+            }
+        };
+        delay = MIN_RECONNECT_DELAY;
+
+        // subscribed before reading the gap, anything landing meanwhile shows up in one or both.
+        if let Some(slot) = last_slot {
+            for update in changed_assets_since(client, program_id, slot).await? {
+                last_slot = last_slot.max(Some(update.slot));
+                if !on_update(update) {
+                    return Ok(());
+                }
+            }
+        }
+
+        while let Some(response) = stream.next().await {
+            last_slot = last_slot.max(Some(response.context.slot));
+            if let Some(update) = decode_update(&response).map_err(|e| ClientErrorKind::Custom(e.to_string()))? {
+                if !on_update(update) {
+                    return Ok(());
+                }
+            }
+        }
+        // the stream only ends when the socket dropped.
+    }
+}
+
+// the asset pushed by the websocket, `None` for an account that doesn't decode as one.
+fn decode_update(response: &Response<RpcKeyedAccount>) -> std::result::Result<Option<AssetUpdate>, ParsePubkeyError> {
+    let address = Pubkey::from_str(&response.value.pubkey)?;
+    let asset = match response.value.account.decode::<Account>() {
+        Some(account) => Brc20Asset::try_from_slice(&account.data).ok(),
+        None => None,
+    };
+    Ok(asset.map(|asset| AssetUpdate { address, asset, slot: response.context.slot, backfilled: false }))
+}
+
+fn asset_subscribe_config(client: &RpcClient) -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &ASSET_PREFIX))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(client.commitment()),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    }
+}
+
+/// Assets written by successful oracle transactions from `since_slot` on, read at their current state.
+/// Only static account keys are looked at, the oracle builders don't use lookup tables.
+pub async fn changed_assets_since(client: &RpcClient, program_id: &Pubkey, since_slot: u64) -> Result<Vec<AssetUpdate>> {
+    let mut written: Vec<Pubkey> = vec![];
+    let mut before = None;
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            commitment: Some(client.commitment()),
+            ..GetConfirmedSignaturesForAddress2Config::default()
+        };
+        // newest first.
+        let statuses = client.get_signatures_for_address_with_config(program_id, config).await?;
+        for status in statuses.iter().filter(|status| status.slot >= since_slot && status.err.is_none()) {
+            let signature = Signature::from_str(&status.signature).map_err(|e| ClientErrorKind::Custom(e.to_string()))?;
+            let confirmed = client.get_transaction(&signature, UiTransactionEncoding::Base64).await?;
+            if let Some(transaction) = confirmed.transaction.transaction.decode() {
+                add_written_accounts(&transaction, program_id, &mut written);
+            }
+        }
+        match statuses.last() {
+            Some(oldest) if oldest.slot >= since_slot => {
+                before = Some(Signature::from_str(&oldest.signature).map_err(|e| ClientErrorKind::Custom(e.to_string()))?)
+            }
+            _ => break,
+        }
+    }
+
+    let mut updates = vec![];
+    for chunk in written.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let response = client.get_multiple_accounts_with_commitment(chunk, client.commitment()).await?;
+        for (address, account) in chunk.iter().zip(response.value) {
+            if let Some(asset) = decode_asset(program_id, account) {
+                updates.push(AssetUpdate { address: *address, asset, slot: response.context.slot, backfilled: true });
+            }
+        }
+    }
+    Ok(updates)
+}
+
+// accounts `transaction` may have written through instructions of `program_id`, added to `written` once.
+fn add_written_accounts(transaction: &VersionedTransaction, program_id: &Pubkey, written: &mut Vec<Pubkey>) {
+    let keys = transaction.message.static_account_keys();
+    for instruction in transaction.message.instructions() {
+        if keys.get(instruction.program_id_index as usize) != Some(program_id) {
+            continue;
+        }
+        for index in instruction.accounts.iter().map(|index| *index as usize) {
+            if transaction.message.is_maybe_writable(index) && !written.contains(&keys[index]) {
+                written.push(keys[index]);
+            }
+        }
+    }
+}
+
+// a written account is only reported if it is still one of the program's assets.
+fn decode_asset(program_id: &Pubkey, account: Option<Account>) -> Option<Brc20Asset> {
+    account
+        .filter(|account| &account.owner == program_id)
+        .and_then(|account| Brc20Asset::try_from_slice(&account.data).ok())
+        .filter(|asset| asset.prefix == ASSET_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+    use brc20_oracle::types::{AttestationSource, Brc20Amount, Brc20Key, BRC20_KEY_VERSION};
+    use solana_account_decoder::UiAccount;
+    use solana_client::rpc_response::RpcResponseContext;
+    use solana_program::instruction::{AccountMeta, Instruction};
+    use solana_sdk::transaction::Transaction;
+    use super::*;
+
+    fn asset() -> Brc20Asset {
+        Brc20Asset {
+            prefix: ASSET_PREFIX,
+            set: true,
+            uid: 0,
+            key: Brc20Key { version: BRC20_KEY_VERSION, height: 10, tick: *b"ordi", owner: "a".to_string() },
+            amount: Brc20Amount(1000),
+            requester: Pubkey::new_from_array([1; 32]),
+            expire_slot: None,
+            attested_slot: 5,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        }
+    }
+
+    fn account(owner: Pubkey, data: Vec<u8>) -> Account {
+        Account { lamports: 1, data, owner, executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn test_decode_update() {
+        let program_id = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+        let response = |data: Vec<u8>| Response {
+            context: RpcResponseContext { slot: 7, api_version: None },
+            value: RpcKeyedAccount {
+                pubkey: address.to_string(),
+                account: UiAccount::encode(&address, &account(program_id, data), UiAccountEncoding::Base64, None, None),
+            },
+        };
+        let update = AssetUpdate { address, asset: asset(), slot: 7, backfilled: false };
+        assert_eq!(Some(update), decode_update(&response(asset().try_to_vec().unwrap())).unwrap());
+        assert_eq!(None, decode_update(&response(vec![1, 2, 3])).unwrap());
+    }
+
+    #[test]
+    fn test_changed_assets_filter() {
+        let program_id = Pubkey::new_unique();
+        let (asset_address, committee, elsewhere) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let oracle = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new(asset_address, false), AccountMeta::new_readonly(committee, false)],
+        );
+        let other = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![AccountMeta::new(elsewhere, false)]);
+        let payer = Pubkey::new_unique();
+        let transaction = VersionedTransaction::from(Transaction::new_with_payer(&[oracle, other], Some(&payer)));
+        // read only accounts, the payer and other programs' accounts are left out, each address once.
+        let mut written = vec![];
+        add_written_accounts(&transaction, &program_id, &mut written);
+        add_written_accounts(&transaction, &program_id, &mut written);
+        assert_eq!(vec![asset_address], written);
+
+        let data = asset().try_to_vec().unwrap();
+        assert_eq!(Some(asset()), decode_asset(&program_id, Some(account(program_id, data.clone()))));
+        assert_eq!(None, decode_asset(&program_id, Some(account(Pubkey::new_unique(), data))));
+        let not_asset = Brc20Asset { prefix: *b"Other", ..asset() };
+        assert_eq!(None, decode_asset(&program_id, Some(account(program_id, not_asset.try_to_vec().unwrap()))));
+        // closed since the transaction.
+        assert_eq!(None, decode_asset(&program_id, None));
+    }
+}
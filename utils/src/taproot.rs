@@ -0,0 +1,55 @@
+//! BIP341 key tweaking, to derive the P2TR owner address of a `Brc20Key` from an internal key.
+
+use bitcoin::key::{TapTweak, TweakedPublicKey, UntweakedPublicKey};
+use bitcoin::secp256k1::{Secp256k1, Verification};
+use bitcoin::taproot::TapNodeHash;
+use bitcoin::{Address, Network};
+
+/// Output key committed to by a P2TR output: `internal` tweaked with the tap tweak hash of
+/// `merkle_root`, `None` for key path only outputs.
+pub fn tweaked_output_key<C: Verification>(
+    secp: &Secp256k1<C>,
+    internal: UntweakedPublicKey,
+    merkle_root: Option<TapNodeHash>,
+) -> TweakedPublicKey {
+    internal.tap_tweak(secp, merkle_root).0
+}
+
+/// Whether `output` is `internal` correctly tweaked with `merkle_root`.
+pub fn check_tweak<C: Verification>(
+    secp: &Secp256k1<C>,
+    internal: UntweakedPublicKey,
+    merkle_root: Option<TapNodeHash>,
+    output: &TweakedPublicKey,
+) -> bool {
+    &tweaked_output_key(secp, internal, merkle_root) == output
+}
+
+/// Owner string to request for a P2TR output, already in canonical (lowercase) form.
+pub fn p2tr_owner(output: TweakedPublicKey, network: Network) -> String {
+    Address::p2tr_tweaked(output, network).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use bitcoin::secp256k1::XOnlyPublicKey;
+    use super::*;
+
+    // BIP86 test vector, first receiving address.
+    #[test]
+    fn test_bip86_vector() {
+        let secp = Secp256k1::verification_only();
+        let internal =
+            XOnlyPublicKey::from_str("cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115").unwrap();
+        let output = tweaked_output_key(&secp, internal, None);
+        assert_eq!(
+            "a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c",
+            output.to_inner().to_string()
+        );
+        assert!(check_tweak(&secp, internal, None, &output));
+        // using the internal key untweaked is the usual mistake.
+        assert!(!check_tweak(&secp, internal, None, &TweakedPublicKey::dangerous_assume_tweaked(internal)));
+        assert_eq!("bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr", p2tr_owner(output, Network::Bitcoin));
+    }
+}
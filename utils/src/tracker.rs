@@ -0,0 +1,225 @@
+use std::time::Duration;
+use solana_client::client_error::{ClientError, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::instruction::Instruction;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::keypair::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::signers::Signers;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Confirmation {
+    Sent,
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+/// Emitted for every batch handed to `TxTracker::track`, `index` is the batch position.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TxEvent {
+    Status { index: usize, signature: Signature, confirmation: Confirmation },
+    // the previous signature expired without landing and was re-signed with a fresh blockhash.
+    Resent { index: usize, dropped: Signature, signature: Signature },
+    // failed on chain, or in preflight when it was sent.
+    Failed { index: usize, signature: Signature, error: TransactionError },
+    // gave up after `max_resends`, or sending failed for another reason than the transaction itself.
+    Dropped { index: usize, signature: Signature },
+}
+
+struct Pending {
+    index: usize,
+    instructions: Vec<Instruction>,
+    signature: Signature,
+    last_valid_block_height: u64,
+    confirmation: Confirmation,
+    resends: usize,
+}
+
+pub struct TxTracker<'a, T: Signers> {
+    client: &'a RpcClient,
+    payer: &'a Keypair,
+    signers: &'a T,
+    pub poll_interval: Duration,
+    pub max_resends: usize,
+}
+
+impl<'a, T: Signers> TxTracker<'a, T> {
+    pub fn new(client: &'a RpcClient, payer: &'a Keypair, signers: &'a T) -> Self {
+        Self {
+            client,
+            payer,
+            signers,
+            poll_interval: Duration::from_millis(500),
+            max_resends: 3,
+        }
+    }
+
+    /// Sends every batch and follows it until finalized, failed or dropped, calling `on_event` on each change.
+    /// Returns the final event of every batch, in order. A batch that can't be sent ends there, the
+    /// others are still followed. Errors are only returned for the RPC calls shared by every batch.
+    pub async fn track(
+        &self,
+        instruction_batches: Vec<Vec<Instruction>>,
+        mut on_event: impl FnMut(&TxEvent),
+    ) -> Result<Vec<TxEvent>> {
+        let mut outcomes: Vec<Option<TxEvent>> = vec![None; instruction_batches.len()];
+        let mut pending = vec![];
+        // nothing is sent yet if this fails.
+        let (blockhash, last_valid_block_height) = self.latest_blockhash().await?;
+        for (index, instructions) in instruction_batches.into_iter().enumerate() {
+            let signature = match self.send(index, &instructions, blockhash).await {
+                Ok(signature) => signature,
+                Err(event) => {
+                    on_event(&event);
+                    outcomes[index] = Some(event);
+                    continue;
+                }
+            };
+            on_event(&TxEvent::Status { index, signature, confirmation: Confirmation::Sent });
+            pending.push(Pending {
+                index,
+                instructions,
+                signature,
+                last_valid_block_height,
+                confirmation: Confirmation::Sent,
+                resends: 0,
+            });
+        }
+
+        while !pending.is_empty() {
+            tokio::time::sleep(self.poll_interval).await;
+            let signatures: Vec<Signature> = pending.iter().map(|tx| tx.signature).collect();
+            let statuses = self.client.get_signature_statuses(&signatures).await?.value;
+            let block_height = self.client.get_block_height().await?;
+
+            let mut still_pending = vec![];
+            for (mut tx, status) in pending.into_iter().zip(statuses) {
+                match status {
+                    Some(status) => {
+                        if let Some(error) = status.err.clone() {
+                            let event = TxEvent::Failed { index: tx.index, signature: tx.signature, error };
+                            on_event(&event);
+                            outcomes[tx.index] = Some(event);
+                            continue;
+                        }
+                        let confirmation = if status.satisfies_commitment(CommitmentConfig::finalized()) {
+                            Confirmation::Finalized
+                        } else if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                            Confirmation::Confirmed
+                        } else {
+                            Confirmation::Processed
+                        };
+                        if confirmation > tx.confirmation {
+                            tx.confirmation = confirmation;
+                            let event = TxEvent::Status { index: tx.index, signature: tx.signature, confirmation };
+                            on_event(&event);
+                            if confirmation == Confirmation::Finalized {
+                                outcomes[tx.index] = Some(event);
+                                continue;
+                            }
+                        }
+                        still_pending.push(tx);
+                    }
+                    // unknown past its last valid block height: it can never land, so re-signing can't double submit.
+                    None if block_height > tx.last_valid_block_height => {
+                        if tx.resends >= self.max_resends {
+                            let event = TxEvent::Dropped { index: tx.index, signature: tx.signature };
+                            on_event(&event);
+                            outcomes[tx.index] = Some(event);
+                            continue;
+                        }
+                        let resent = match self.latest_blockhash().await {
+                            Ok((blockhash, last_valid_block_height)) => self
+                                .send(tx.index, &tx.instructions, blockhash)
+                                .await
+                                .map(|signature| (signature, last_valid_block_height)),
+                            // the expired signature is all there is to report.
+                            Err(_) => Err(TxEvent::Dropped { index: tx.index, signature: tx.signature }),
+                        };
+                        let (signature, last_valid_block_height) = match resent {
+                            Ok(resent) => resent,
+                            Err(event) => {
+                                on_event(&event);
+                                outcomes[tx.index] = Some(event);
+                                continue;
+                            }
+                        };
+                        on_event(&TxEvent::Resent { index: tx.index, dropped: tx.signature, signature });
+                        tx.signature = signature;
+                        tx.last_valid_block_height = last_valid_block_height;
+                        tx.confirmation = Confirmation::Sent;
+                        tx.resends += 1;
+                        still_pending.push(tx);
+                    }
+                    None => still_pending.push(tx),
+                }
+            }
+            pending = still_pending;
+        }
+        Ok(outcomes.into_iter().map(|outcome| outcome.unwrap()).collect())
+    }
+
+    async fn latest_blockhash(&self) -> Result<(Hash, u64)> {
+        self.client.get_latest_blockhash_with_commitment(self.client.commitment()).await
+    }
+
+    // the final event of the batch if it couldn't be sent.
+    async fn send(
+        &self,
+        index: usize,
+        instructions: &[Instruction],
+        blockhash: Hash,
+    ) -> std::result::Result<Signature, TxEvent> {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        transaction.sign(self.signers, blockhash);
+        let signature = transaction.signatures[0];
+        self.client.send_transaction(&transaction).await.map_err(|err| send_failure(index, signature, err))
+    }
+}
+
+// a preflight rejection is the transaction's own failure, anything else leaves it unknown.
+fn send_failure(index: usize, signature: Signature, err: ClientError) -> TxEvent {
+    match err.get_transaction_error() {
+        Some(error) => TxEvent::Failed { index, signature, error },
+        None => TxEvent::Dropped { index, signature },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_program::pubkey::Pubkey;
+    use solana_program_test::tokio;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_failure() {
+        let payer = Keypair::new();
+        let signers = [&payer];
+        // distinct instructions, so the two batches don't sign to the same transaction.
+        let batches: Vec<Vec<Instruction>> =
+            (0..2u8).map(|i| vec![Instruction::new_with_bytes(Pubkey::new_unique(), &[i], vec![])]).collect();
+
+        // nothing was sent, so failing outright loses nothing.
+        let client = RpcClient::new_mock("fails".to_string());
+        assert!(TxTracker::new(&client, &payer, &signers).track(batches.clone(), |_| {}).await.is_err());
+
+        // the first send gets garbage back, the second goes through.
+        let mocks = HashMap::from([(RpcRequest::SendTransaction, serde_json::Value::Null)]);
+        let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let mut tracker = TxTracker::new(&client, &payer, &signers);
+        tracker.poll_interval = Duration::ZERO;
+        let mut events = vec![];
+        let outcomes = tracker.track(batches, |event| events.push(event.clone())).await.unwrap();
+
+        assert!(matches!(outcomes[0], TxEvent::Dropped { index: 0, .. }));
+        assert!(matches!(outcomes[1], TxEvent::Status { index: 1, confirmation: Confirmation::Finalized, .. }));
+        assert_eq!(outcomes[0], events[0]);
+        assert!(matches!(events[1], TxEvent::Status { index: 1, confirmation: Confirmation::Sent, .. }));
+    }
+}
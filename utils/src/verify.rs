@@ -0,0 +1,55 @@
+//! Checks that a deployed program matches a locally built artifact, e.g. target/deploy/brc20_oracle.so.
+
+use solana_client::client_error::{ClientErrorKind, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_sdk::hash::{hash, Hash};
+
+/// Executable of `program_id` as stored on chain, including the zero padding of upgradeable programs.
+pub async fn fetch_program_data(client: &RpcClient, program_id: &Pubkey) -> Result<Vec<u8>> {
+    let account = client.get_account(program_id).await?;
+    if account.owner != bpf_loader_upgradeable::id() {
+        return Ok(account.data);
+    }
+    let programdata_address = match account.deserialize_data::<UpgradeableLoaderState>() {
+        Ok(UpgradeableLoaderState::Program { programdata_address }) => programdata_address,
+        _ => return Err(ClientErrorKind::Custom(format!("{} is not an upgradeable program", program_id)).into()),
+    };
+    let programdata = client.get_account_data(&programdata_address).await?;
+    let offset = UpgradeableLoaderState::size_of_programdata_metadata();
+    if programdata.len() < offset {
+        return Err(ClientErrorKind::Custom(format!("program data {} is too short", programdata_address)).into());
+    }
+    Ok(programdata[offset..].to_vec())
+}
+
+/// Sha256 of an executable without trailing zeros, so the on chain copy hashes like the artifact
+/// it was deployed from.
+pub fn program_hash(data: &[u8]) -> Hash {
+    let len = data.iter().rposition(|byte| *byte != 0).map_or(0, |i| i + 1);
+    hash(&data[..len])
+}
+
+/// Whether the program deployed at `program_id` was built from `artifact`.
+pub async fn verify_build(client: &RpcClient, program_id: &Pubkey, artifact: &[u8]) -> Result<bool> {
+    let deployed = fetch_program_data(client, program_id).await?;
+    Ok(program_hash(&deployed) == program_hash(artifact))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_hash_ignores_padding() {
+        let artifact = b"\x7fELF program".to_vec();
+        let mut deployed = artifact.clone();
+        deployed.resize(artifact.len() + 1024, 0);
+        assert_eq!(program_hash(&artifact), program_hash(&deployed));
+
+        let mut other = artifact.clone();
+        other[1] = b'X';
+        assert_ne!(program_hash(&artifact), program_hash(&other));
+    }
+}
@@ -0,0 +1,202 @@
+//! Watch-only auditing of the committee: what an independent indexer would have attested, next to
+//! what was inserted on chain. Needs no signing key, asking the indexer is left to the caller.
+//! Shadow operators publishing their own values can be compared with each other the same way.
+
+use std::collections::HashMap;
+use borsh::{BorshDeserialize, BorshSerialize};
+use brc20_oracle::types::{Brc20Amount, Brc20Asset, Brc20Key, RejectReason, RevokeReason};
+use solana_client::client_error::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::{keypair::Keypair, Signer};
+use crate::call_process::get_assets_by_tick;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    // the committee inserted the amount we computed.
+    Agree,
+    Disagree { attested: Brc20Amount },
+    // still unfilled, we would have attested the expected amount.
+    WouldAttest,
+    Rejected(RejectReason),
+    Revoked(RevokeReason),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WatchEntry {
+    pub asset: Pubkey,
+    pub key: Brc20Key,
+    pub expected: Brc20Amount,
+    pub verdict: Verdict,
+}
+
+/// Compares `assets` with the amounts `expected` returns, keys it has no amount for are skipped.
+pub fn audit<F: Fn(&Brc20Key) -> Option<Brc20Amount>>(assets: &[(Pubkey, Brc20Asset)], expected: F) -> Vec<WatchEntry> {
+    assets
+        .iter()
+        .filter_map(|(address, asset)| {
+            let expected = expected(&asset.key)?;
+            let verdict = match (asset.revoked, asset.rejected) {
+                (Some(reason), _) => Verdict::Revoked(reason),
+                (None, Some(reason)) => Verdict::Rejected(reason),
+                _ if !asset.set => Verdict::WouldAttest,
+                _ if asset.amount == expected => Verdict::Agree,
+                _ => Verdict::Disagree { attested: asset.amount },
+            };
+            Some(WatchEntry { asset: *address, key: asset.key.clone(), expected, verdict })
+        })
+        .collect()
+}
+
+/// `audit` over every asset of `ticks`.
+pub async fn audit_ticks<F: Fn(&Brc20Key) -> Option<Brc20Amount>>(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    ticks: &[[u8; 4]],
+    expected: F,
+) -> Result<Vec<WatchEntry>> {
+    let mut assets = vec![];
+    for tick in ticks {
+        assets.extend(get_assets_by_tick(client, program_id, tick).await?);
+    }
+    Ok(audit(&assets, expected))
+}
+
+/// One shadow operator's amount for a key, as published to the shared log.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ShadowValue {
+    pub operator: Pubkey,
+    pub key: Brc20Key,
+    pub amount: Brc20Amount,
+    // operator signature over the borsh encoded (key, amount).
+    pub signature: Vec<u8>,
+}
+
+impl ShadowValue {
+    pub fn new(operator: &Keypair, key: Brc20Key, amount: Brc20Amount) -> Self {
+        let msg = (key.clone(), amount).try_to_vec().unwrap();
+        let signature = operator.sign_message(&msg).as_ref().to_vec();
+        ShadowValue { operator: operator.pubkey(), key, amount, signature }
+    }
+
+    /// Whether `signature` is the operator's, log entries failing this should be dropped.
+    pub fn verify(&self) -> bool {
+        let msg = match (self.key.clone(), self.amount).try_to_vec() {
+            Ok(msg) => msg,
+            Err(_) => return false,
+        };
+        match Signature::try_from(self.signature.as_slice()) {
+            Ok(signature) => signature.verify(self.operator.as_ref(), &msg),
+            Err(_) => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OperatorStats {
+    pub answered: u64,
+    // answers differing from a strict majority of the operators that answered the key.
+    pub outvoted: u64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DisagreementStats {
+    pub keys: u64,
+    pub unanimous: u64,
+    // keys where no amount has a strict majority.
+    pub split: u64,
+    pub operators: HashMap<Pubkey, OperatorStats>,
+}
+
+/// Disagreement between shadow operators over `values`. Entries with a bad signature are ignored and
+/// only an operator's last value per key counts.
+pub fn disagreement_stats(values: &[ShadowValue]) -> DisagreementStats {
+    let mut by_key: HashMap<Vec<u8>, HashMap<Pubkey, Brc20Amount>> = HashMap::new();
+    for value in values.iter().filter(|value| value.verify()) {
+        by_key.entry(value.key.try_to_vec().unwrap()).or_default().insert(value.operator, value.amount);
+    }
+
+    let mut stats = DisagreementStats::default();
+    for answers in by_key.values() {
+        let mut votes: HashMap<Brc20Amount, usize> = HashMap::new();
+        for amount in answers.values() {
+            *votes.entry(*amount).or_default() += 1;
+        }
+        let majority = votes.iter().find(|(_, count)| **count * 2 > answers.len()).map(|(amount, _)| *amount);
+        stats.keys += 1;
+        if votes.len() == 1 {
+            stats.unanimous += 1;
+        }
+        if majority.is_none() {
+            stats.split += 1;
+        }
+        for (operator, amount) in answers {
+            let operator_stats = stats.operators.entry(*operator).or_default();
+            operator_stats.answered += 1;
+            if matches!(majority, Some(majority) if majority != *amount) {
+                operator_stats.outvoted += 1;
+            }
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use brc20_oracle::types::{AttestationSource, BRC20_KEY_VERSION};
+    use super::*;
+
+    fn asset(owner: &str, set: bool, amount: u128) -> (Pubkey, Brc20Asset) {
+        let asset = Brc20Asset {
+            prefix: brc20_oracle::ASSET_PREFIX,
+            set,
+            uid: 0,
+            key: Brc20Key { version: BRC20_KEY_VERSION, height: 1, tick: *b"ordi", owner: owner.to_string() },
+            amount: Brc20Amount(amount),
+            requester: Pubkey::new_unique(),
+            expire_slot: None,
+            attested_slot: 0,
+            rejected: None,
+            confidence: None,
+            source: AttestationSource::default(),
+            revoked: None,
+            bump: 0,
+        };
+        (Pubkey::new_unique(), asset)
+    }
+
+    #[test]
+    fn test_audit() {
+        let assets = vec![asset("a", true, 100), asset("b", true, 90), asset("c", false, 0), asset("d", true, 1)];
+        let expected = |key: &Brc20Key| match key.owner.as_str() {
+            "d" => None,
+            _ => Some(Brc20Amount(100)),
+        };
+        let verdicts: Vec<Verdict> = audit(&assets, expected).into_iter().map(|entry| entry.verdict).collect();
+        assert_eq!(
+            vec![Verdict::Agree, Verdict::Disagree { attested: Brc20Amount(90) }, Verdict::WouldAttest],
+            verdicts
+        );
+    }
+
+    #[test]
+    fn test_disagreement_stats() {
+        let operators: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+        let (a, b) = (asset("a", false, 0).1.key, asset("b", false, 0).1.key);
+        let mut values = vec![
+            ShadowValue::new(&operators[0], a.clone(), Brc20Amount(100)),
+            ShadowValue::new(&operators[1], a.clone(), Brc20Amount(100)),
+            ShadowValue::new(&operators[2], a, Brc20Amount(90)),
+            ShadowValue::new(&operators[0], b.clone(), Brc20Amount(5)),
+            ShadowValue::new(&operators[1], b.clone(), Brc20Amount(5)),
+        ];
+        // a forged entry isn't counted.
+        values.push(ShadowValue { operator: operators[2].pubkey(), ..ShadowValue::new(&operators[0], b, Brc20Amount(6)) });
+
+        let stats = disagreement_stats(&values);
+        assert_eq!((2, 1, 0), (stats.keys, stats.unanimous, stats.split));
+        assert_eq!(OperatorStats { answered: 1, outvoted: 1 }, stats.operators[&operators[2].pubkey()]);
+        assert_eq!(OperatorStats { answered: 2, outvoted: 0 }, stats.operators[&operators[0].pubkey()]);
+    }
+}